@@ -1,6 +1,171 @@
-use std::any::{Any, TypeId};
-use std::collections::HashMap;
-use std::fmt::{self, Formatter};
+use std::any::{type_name, Any, TypeId};
+use std::collections::hash_map::{self, HashMap};
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+
+/// A typed key of a value stored in a [`Depot`].
+///
+/// Values stored with typed keys do not share the namespace of string keys, and reading a value with a key of
+/// another type returns [`DepotError::TypeMismatch`] instead of silently returning nothing.
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::prelude::*;
+///
+/// const USER_ID: DepotKey<u64> = DepotKey::new("user_id");
+///
+/// let mut depot = Depot::new();
+/// depot.put(USER_ID, 42).unwrap();
+/// assert_eq!(depot.fetch(USER_ID), Ok(&42));
+/// ```
+pub struct DepotKey<T> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<T> DepotKey<T> {
+    /// Create a new `DepotKey`.
+    #[inline]
+    pub const fn new(name: &'static str) -> Self {
+        DepotKey {
+            name,
+            _marker: PhantomData,
+        }
+    }
+    /// Get key name.
+    #[inline]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+impl<T> Clone for DepotKey<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for DepotKey<T> {}
+impl<T> fmt::Debug for DepotKey<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DepotKey")
+            .field("name", &self.name)
+            .field("type", &type_name::<T>())
+            .finish()
+    }
+}
+
+/// Error returned when a value can not be read from a [`Depot`] with a [`DepotKey`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DepotError {
+    /// There is no value with this key.
+    NotFound {
+        /// Key name.
+        key: &'static str,
+    },
+    /// The value stored with this key has another type.
+    TypeMismatch {
+        /// Key name.
+        key: &'static str,
+        /// Type of the key.
+        expected: &'static str,
+        /// Type of the stored value.
+        found: &'static str,
+    },
+}
+impl Display for DepotError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound { key } => write!(f, "depot key `{}` not found", key),
+            Self::TypeMismatch { key, expected, found } => write!(
+                f,
+                "depot key `{}` holds a value of type `{}`, not `{}`",
+                key, found, expected
+            ),
+        }
+    }
+}
+impl StdError for DepotError {}
+
+struct TypedValue {
+    value: Box<dyn Any + Send>,
+    type_name: &'static str,
+}
+impl TypedValue {
+    #[inline]
+    fn new<T: Any + Send>(value: T) -> Self {
+        TypedValue {
+            value: Box::new(value),
+            type_name: type_name::<T>(),
+        }
+    }
+    #[inline]
+    fn check<T: Any + Send>(&self, key: &'static str) -> Result<(), DepotError> {
+        if self.value.is::<T>() {
+            Ok(())
+        } else {
+            Err(DepotError::TypeMismatch {
+                key,
+                expected: type_name::<T>(),
+                found: self.type_name,
+            })
+        }
+    }
+}
+
+/// A view into an entry of a [`Depot`] whose value has the type of its [`DepotKey`].
+pub struct DepotEntry<'a, T> {
+    inner: hash_map::Entry<'a, &'static str, TypedValue>,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<'a, T: Any + Send> DepotEntry<'a, T> {
+    /// Get key name.
+    #[inline]
+    pub fn key(&self) -> &'static str {
+        self.inner.key()
+    }
+    /// Returns `true` if the entry has a value.
+    #[inline]
+    pub fn is_occupied(&self) -> bool {
+        matches!(self.inner, hash_map::Entry::Occupied(_))
+    }
+    /// Calls `f` with the value if the entry is occupied and returns `Self`.
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        DepotEntry {
+            inner: self.inner.and_modify(|typed| {
+                if let Some(value) = typed.value.downcast_mut::<T>() {
+                    f(value)
+                }
+            }),
+            _marker: PhantomData,
+        }
+    }
+    /// Inserts `value` if the entry is vacant and returns a mutable reference to the value.
+    #[inline]
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        self.or_insert_with(|| value)
+    }
+    /// Inserts the result of `f` if the entry is vacant and returns a mutable reference to the value.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'a mut T {
+        self.inner
+            .or_insert_with(|| TypedValue::new(f()))
+            .value
+            .downcast_mut::<T>()
+            .expect("depot entry type is checked when created")
+    }
+    /// Inserts the default value if the entry is vacant and returns a mutable reference to the value.
+    #[inline]
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+}
 
 /// Depot if for store temp data of current request. Each handler can read or write data to it.
 ///
@@ -28,6 +193,8 @@ use std::fmt::{self, Formatter};
 #[derive(Default)]
 pub struct Depot {
     map: HashMap<String, Box<dyn Any + Send>>,
+    typed: HashMap<&'static str, TypedValue>,
+    injected: HashMap<TypeId, Box<dyn Any + Send>>,
 }
 
 impl Depot {
@@ -36,7 +203,11 @@ impl Depot {
     /// The depot is initially created with a capacity of 0, so it will not allocate until it is first inserted into.
     #[inline]
     pub fn new() -> Depot {
-        Depot { map: HashMap::new() }
+        Depot {
+            map: HashMap::new(),
+            typed: HashMap::new(),
+            injected: HashMap::new(),
+        }
     }
 
     /// Get reference to depot inner map.
@@ -52,6 +223,8 @@ impl Depot {
     pub fn with_capacity(capacity: usize) -> Depot {
         Depot {
             map: HashMap::with_capacity(capacity),
+            typed: HashMap::new(),
+            injected: HashMap::new(),
         }
     }
     /// Returns the number of elements the depot can hold without reallocating.
@@ -60,15 +233,106 @@ impl Depot {
         self.map.capacity()
     }
 
-    /// Inject a value into the depot.
+    /// Inject a value into the depot, it is indexed by its type.
     #[inline]
     pub fn inject<V: Any + Send>(&mut self, value: V) {
-        self.map.insert(format!("{:?}", TypeId::of::<V>()), Box::new(value));
+        self.injected.insert(TypeId::of::<V>(), Box::new(value));
     }
     /// Obtain a reference to a value previous inject to the depot.
     #[inline]
     pub fn obtain<T: Any + Send>(&self) -> Option<&T> {
-        self.get(&format!("{:?}", TypeId::of::<T>()))
+        self.injected
+            .get(&TypeId::of::<T>())
+            .and_then(|b| b.downcast_ref::<T>())
+    }
+    /// Obtain a mutable reference to a value previous inject to the depot.
+    #[inline]
+    pub fn obtain_mut<T: Any + Send>(&mut self) -> Option<&mut T> {
+        self.injected
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|b| b.downcast_mut::<T>())
+    }
+    /// Take a value previous inject out of the depot.
+    #[inline]
+    pub fn eject<T: Any + Send>(&mut self) -> Option<T> {
+        self.injected
+            .remove(&TypeId::of::<T>())
+            .and_then(|b| b.downcast::<T>().ok())
+            .map(|b| *b)
+    }
+
+    /// Inserts a value with a typed key, returns the previous value stored with this key.
+    ///
+    /// If a value of another type is stored with the same key, it is kept and [`DepotError::TypeMismatch`]
+    /// is returned.
+    #[inline]
+    pub fn put<T: Any + Send>(&mut self, key: DepotKey<T>, value: T) -> Result<Option<T>, DepotError> {
+        match self.typed.entry(key.name) {
+            hash_map::Entry::Occupied(mut entry) => {
+                entry.get().check::<T>(key.name)?;
+                let old = entry.insert(TypedValue::new(value));
+                Ok(Some(*old.value.downcast::<T>().expect("type is checked")))
+            }
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(TypedValue::new(value));
+                Ok(None)
+            }
+        }
+    }
+    /// Check is there a value of the key's type stored with this key.
+    #[inline]
+    pub fn has<T: Any + Send>(&self, key: DepotKey<T>) -> bool {
+        self.typed
+            .get(key.name)
+            .map(|typed| typed.value.is::<T>())
+            .unwrap_or(false)
+    }
+    /// Immutably borrows the value stored with a typed key.
+    #[inline]
+    pub fn fetch<T: Any + Send>(&self, key: DepotKey<T>) -> Result<&T, DepotError> {
+        let typed = self.typed.get(key.name).ok_or(DepotError::NotFound { key: key.name })?;
+        typed.check::<T>(key.name)?;
+        Ok(typed.value.downcast_ref::<T>().expect("type is checked"))
+    }
+    /// Mutably borrows the value stored with a typed key.
+    #[inline]
+    pub fn fetch_mut<T: Any + Send>(&mut self, key: DepotKey<T>) -> Result<&mut T, DepotError> {
+        let typed = self
+            .typed
+            .get_mut(key.name)
+            .ok_or(DepotError::NotFound { key: key.name })?;
+        typed.check::<T>(key.name)?;
+        Ok(typed.value.downcast_mut::<T>().expect("type is checked"))
+    }
+    /// Takes the value stored with a typed key out of the depot, a value of another type is kept.
+    #[inline]
+    pub fn take<T: Any + Send>(&mut self, key: DepotKey<T>) -> Result<T, DepotError> {
+        self.typed
+            .get(key.name)
+            .ok_or(DepotError::NotFound { key: key.name })?
+            .check::<T>(key.name)?;
+        let typed = self.typed.remove(key.name).expect("key is checked");
+        Ok(*typed.value.downcast::<T>().expect("type is checked"))
+    }
+    /// Gets the entry of a typed key for in-place manipulation.
+    #[inline]
+    pub fn entry<T: Any + Send>(&mut self, key: DepotKey<T>) -> Result<DepotEntry<'_, T>, DepotError> {
+        if let Some(typed) = self.typed.get(key.name) {
+            typed.check::<T>(key.name)?;
+        }
+        Ok(DepotEntry {
+            inner: self.typed.entry(key.name),
+            _marker: PhantomData,
+        })
+    }
+    /// Returns the value stored with a typed key, inserting the result of `f` if there is none.
+    #[inline]
+    pub fn get_or_insert_with<T, F>(&mut self, key: DepotKey<T>, f: F) -> Result<&mut T, DepotError>
+    where
+        T: Any + Send,
+        F: FnOnce() -> T,
+    {
+        Ok(self.entry(key)?.or_insert_with(f))
     }
 
     /// Inserts a key-value pair into the depot.
@@ -112,14 +376,21 @@ impl Depot {
         for (k, v) in self.map.drain() {
             map.insert(k, v);
         }
-        Depot { map }
+        Depot {
+            map,
+            typed: std::mem::take(&mut self.typed),
+            injected: std::mem::take(&mut self.injected),
+        }
     }
 }
 
 impl fmt::Debug for Depot {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Depot").field("keys", &self.map.keys()).finish()
+        f.debug_struct("Depot")
+            .field("keys", &self.map.keys())
+            .field("typed_keys", &self.typed.keys())
+            .finish()
     }
 }
 
@@ -151,6 +422,52 @@ mod test {
         assert_eq!(depot.get::<String>("one").unwrap(), &"ONE".to_owned());
     }
 
+    #[test]
+    fn test_typed_keys() {
+        const COUNT: DepotKey<u32> = DepotKey::new("count");
+        const COUNT_NAME: DepotKey<String> = DepotKey::new("count");
+
+        let mut depot = Depot::new();
+        depot.insert("count", "string keys are separated".to_owned());
+        assert_eq!(depot.fetch(COUNT), Err(DepotError::NotFound { key: "count" }));
+        assert_eq!(depot.put(COUNT, 1), Ok(None));
+        assert!(depot.has(COUNT) && !depot.has(COUNT_NAME));
+        assert_eq!(depot.fetch(COUNT), Ok(&1));
+        assert_eq!(
+            depot.fetch(COUNT_NAME),
+            Err(DepotError::TypeMismatch {
+                key: "count",
+                expected: std::any::type_name::<String>(),
+                found: "u32",
+            })
+        );
+        assert!(depot.entry(COUNT_NAME).is_err());
+        assert!(matches!(
+            depot.put(COUNT_NAME, "other".to_owned()),
+            Err(DepotError::TypeMismatch { key: "count", .. })
+        ));
+        assert_eq!(depot.put(COUNT, 1), Ok(Some(1)));
+        assert_eq!(
+            depot.take(COUNT_NAME).unwrap_err().to_string(),
+            format!(
+                "depot key `count` holds a value of type `u32`, not `{}`",
+                std::any::type_name::<String>()
+            )
+        );
+
+        *depot.fetch_mut(COUNT).unwrap() += 1;
+        *depot.get_or_insert_with(COUNT, || 10).unwrap() += 1;
+        depot.entry(COUNT).unwrap().and_modify(|count| *count *= 2).or_insert(0);
+        assert_eq!(depot.take(COUNT), Ok(6));
+        assert_eq!(*depot.get_or_insert_with(COUNT, || 10).unwrap(), 10);
+        assert_eq!(depot.get::<String>("count").unwrap(), "string keys are separated");
+
+        depot.inject(7u8);
+        assert_eq!(depot.obtain::<u8>(), Some(&7));
+        assert_eq!(depot.eject::<u8>(), Some(7));
+        assert!(depot.obtain::<u8>().is_none());
+    }
+
     #[tokio::test]
     async fn test_middleware_use_depot() {
         #[handler(internal)]
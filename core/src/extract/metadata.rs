@@ -107,6 +107,23 @@ pub enum SourceFormat {
     MultiMap,
     /// Json format.
     Json,
+    /// Nested format, keys in bracket and dot notation like `filter[tags][]=a&sort[0].field=name` are decoded into
+    /// nested maps, sequences and structs, within the limits of [`NestedConfig::default`](crate::extract::NestedConfig).
+    ///
+    /// Supported for query and form body sources.
+    Nested,
+    /// MessagePack format, the MessagePack body is decoded as a map and this field is looked up by name.
+    ///
+    /// Requires the `msgpack` feature, without it extracting the field fails with an error naming the missing feature.
+    MsgPack,
+    /// CBOR format, the CBOR body is decoded as a map and this field is looked up by name.
+    ///
+    /// Requires the `cbor` feature, without it extracting the field fails with an error naming the missing feature.
+    Cbor,
+    /// XML format, the XML body is decoded as the root's child elements and this field is looked up by name.
+    ///
+    /// Requires the `xml` feature, without it extracting the field fails with an error naming the missing feature.
+    Xml,
     /// Request format means this field is [`Extractible`] and it will extract from the request.
     Request,
 }
@@ -118,6 +135,10 @@ impl FromStr for SourceFormat {
         match input {
             "multimap" => Ok(Self::MultiMap),
             "json" => Ok(Self::Json),
+            "nested" => Ok(Self::Nested),
+            "msgpack" => Ok(Self::MsgPack),
+            "cbor" => Ok(Self::Cbor),
+            "xml" => Ok(Self::Xml),
             "request" => Ok(Self::Request),
             _ => Err(crate::Error::Other("invalid source format".into())),
         }
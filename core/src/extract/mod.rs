@@ -1,13 +1,368 @@
 //! Extract supported types.
 
+use async_trait::async_trait;
 use serde::Deserialize;
 
+use crate::http::{ParseError, StatusError};
+use crate::{Depot, Request, Response, Writer};
+
 /// Metadata types.
 pub mod metadata;
+pub use crate::serde::NestedConfig;
 pub use metadata::Metadata;
+mod typed_header;
+pub use typed_header::TypedHeader;
+pub mod validation;
+pub use validation::{ValidationError, ValidationErrors};
 
 /// If a type implements this trait, it will give a metadata, this will help request to extracts data to this type.
 pub trait Extractible<'de>: Deserialize<'de> {
     /// Metadata for Extractible type.
     fn metadata() -> &'de Metadata;
+
+    /// Validate the extracted data, generated from `#[validate(...)]` field attributes.
+    ///
+    /// See [`validation`] for the supported validators.
+    #[inline]
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
+
+/// Rejection of [`Extractible`] handler parameters.
+///
+/// It is written as `422 Unprocessable Entity` listing the invalid fields if validation fails, else as
+/// `400 Bad Request`.
+#[derive(Debug)]
+pub struct ExtractRejection(pub ParseError);
+#[async_trait]
+impl Writer for ExtractRejection {
+    async fn write(mut self, req: &mut Request, depot: &mut Depot, res: &mut Response) {
+        match self.0 {
+            ParseError::Validation(errors) => errors.write(req, depot, res).await,
+            e => {
+                tracing::error!(error = ?e, "failed to extract data");
+                res.set_status_error(StatusError::bad_request().with_detail("Extract data failed."));
+            }
+        }
+    }
+}
+
+/// Types which can be created from [`Request`] and [`Depot`], used as parameters of `#[handler]` functions.
+///
+/// It is implemented for [`Extractible`] types without lifetimes, which are validated, [`TypedHeader`], [`State`](crate::state::State),
+/// [`Scoped`](crate::state::Scoped) and `Option<T>`, which is `None` if `T` is absent from the request, see
+/// [`from_request_optional`](FromRequest::from_request_optional).
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::extract::FromRequest;
+/// use salvo_core::prelude::*;
+///
+/// struct CurrentUser(String);
+///
+/// #[async_trait]
+/// impl FromRequest for CurrentUser {
+///     type Rejection = StatusError;
+///
+///     async fn from_request(req: &mut Request, _depot: &mut Depot) -> Result<Self, Self::Rejection> {
+///         req.header::<String>("x-user")
+///             .map(CurrentUser)
+///             .ok_or_else(StatusError::unauthorized)
+///     }
+/// }
+///
+/// #[handler]
+/// async fn hello(user: CurrentUser) -> String {
+///     format!("Hello {}", user.0)
+/// }
+/// ```
+#[async_trait]
+pub trait FromRequest: Sized {
+    /// Written to response if extraction fails.
+    type Rejection: Writer + Send;
+
+    /// Create `Self` from request and depot.
+    async fn from_request(req: &mut Request, depot: &mut Depot) -> Result<Self, Self::Rejection>;
+
+    /// Create `Option<Self>` from request and depot, used to extract `Option<Self>` handler parameters.
+    ///
+    /// Returns `None` only if `Self` is absent from the request, other errors are rejected. The default
+    /// implementation never returns `None`. [`Extractible`] types are absent if none of their fields is in the
+    /// request, and [`TypedHeader`] is absent if the header is missing.
+    #[inline]
+    async fn from_request_optional(req: &mut Request, depot: &mut Depot) -> Result<Option<Self>, Self::Rejection> {
+        Self::from_request(req, depot).await.map(Some)
+    }
+}
+
+#[async_trait]
+impl<T> FromRequest for T
+where
+    T: for<'de> Extractible<'de> + Send,
+{
+    type Rejection = ExtractRejection;
+
+    #[inline]
+    async fn from_request(req: &mut Request, _depot: &mut Depot) -> Result<Self, Self::Rejection> {
+        req.extract().await.map_err(ExtractRejection)
+    }
+
+    async fn from_request_optional(req: &mut Request, _depot: &mut Depot) -> Result<Option<Self>, Self::Rejection> {
+        match req.extract().await {
+            Ok(data) => Ok(Some(data)),
+            Err(ParseError::Validation(errors)) => Err(ExtractRejection(ParseError::Validation(errors))),
+            Err(e) => match crate::serde::is_absent(req, T::metadata()) {
+                Ok(true) => Ok(None),
+                _ => Err(ExtractRejection(e)),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<T> FromRequest for Option<T>
+where
+    T: FromRequest + Send,
+{
+    type Rejection = T::Rejection;
+
+    #[inline]
+    async fn from_request(req: &mut Request, depot: &mut Depot) -> Result<Self, Self::Rejection> {
+        T::from_request_optional(req, depot).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use headers::{IfModifiedSince, UserAgent};
+    use serde::Deserialize;
+
+    use crate::macros::Extractible;
+    use crate::prelude::*;
+    use crate::test::{ResponseExt, TestClient};
+
+    use super::FromRequest;
+
+    #[derive(Deserialize, Extractible, Debug)]
+    #[extract(internal, default_source(from = "query"))]
+    struct Page {
+        page: u32,
+    }
+
+    struct CurrentUser(String);
+    #[async_trait]
+    impl FromRequest for CurrentUser {
+        type Rejection = StatusError;
+
+        async fn from_request(req: &mut Request, _depot: &mut Depot) -> Result<Self, Self::Rejection> {
+            req.header::<String>("x-user")
+                .map(CurrentUser)
+                .ok_or_else(StatusError::unauthorized)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_from_request() {
+        #[handler(internal)]
+        async fn hello(
+            user: CurrentUser,
+            user_agent: TypedHeader<UserAgent>,
+            page: Option<Page>,
+            since: Option<TypedHeader<IfModifiedSince>>,
+        ) -> String {
+            format!(
+                "{} {} {:?} {}",
+                user.0,
+                user_agent.as_str(),
+                page.map(|p| p.page),
+                since.is_some()
+            )
+        }
+        let service = Service::new(Router::new().get(hello));
+
+        let content = TestClient::get("http://127.0.0.1:7979/?page=2")
+            .insert_header("x-user", "jobs")
+            .insert_header("user-agent", "test")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "jobs test Some(2) false");
+
+        let content = TestClient::get("http://127.0.0.1:7979/?size=10")
+            .insert_header("x-user", "jobs")
+            .insert_header("user-agent", "test")
+            .insert_header("if-modified-since", "Sun, 06 Nov 1994 08:49:37 GMT")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "jobs test None true");
+
+        let res = TestClient::get("http://127.0.0.1:7979/?page=x")
+            .insert_header("x-user", "jobs")
+            .insert_header("user-agent", "test")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::BAD_REQUEST));
+
+        let res = TestClient::get("http://127.0.0.1:7979/?page=2")
+            .insert_header("x-user", "jobs")
+            .insert_header("user-agent", "test")
+            .insert_header("if-modified-since", "yesterday")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::BAD_REQUEST));
+
+        let res = TestClient::get("http://127.0.0.1:7979/")
+            .insert_header("user-agent", "test")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::get("http://127.0.0.1:7979/")
+            .insert_header("x-user", "jobs")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn test_optional_rejections() {
+        struct Pool;
+        #[handler(internal)]
+        async fn with_pool(pool: Option<State<Pool>>) -> &'static str {
+            if pool.is_some() {
+                "pool"
+            } else {
+                "none"
+            }
+        }
+        #[handler(internal)]
+        async fn with_address(address: Option<Address>) -> String {
+            format!("{:?}", address.map(|a| a.city))
+        }
+        let router = Router::new()
+            .push(Router::with_path("pool").get(with_pool))
+            .push(Router::with_path("address").get(with_address));
+        let service = Service::new(router);
+
+        let res = TestClient::get("http://127.0.0.1:7979/pool").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+
+        let content = TestClient::get("http://127.0.0.1:7979/address")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "None");
+        let content = TestClient::get("http://127.0.0.1:7979/address?city=sf")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "Some(\"sf\")");
+        let res = TestClient::get("http://127.0.0.1:7979/address?city=s")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    fn not_admin(name: &str) -> Result<(), String> {
+        if name == "admin" {
+            Err("name is reserved".into())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[derive(Deserialize, Extractible, Debug)]
+    #[extract(internal, default_source(from = "query"))]
+    struct Address {
+        #[validate(length(min = 2))]
+        city: String,
+    }
+
+    #[derive(Deserialize, Extractible, Debug)]
+    #[extract(internal, default_source(from = "query"), rename_all = "camelCase")]
+    struct NewUser<'a> {
+        #[validate(length(min = 1, max = 8), custom = "not_admin")]
+        user_name: &'a str,
+        #[validate(email(message = "bad email"))]
+        #[extract(source(from = "header"), rename = "x-email")]
+        email: String,
+        #[validate(range(min = 13, max = 130))]
+        age: Option<u8>,
+        #[validate(regex = "^[a-z]+$")]
+        tag: String,
+        #[validate(nested)]
+        #[extract(source(from = "request"))]
+        address: Address,
+    }
+
+    #[tokio::test]
+    async fn test_validation() {
+        #[handler(internal)]
+        async fn create(user: NewUser<'_>) -> String {
+            format!("{} {}", user.user_name, user.address.city)
+        }
+        let service = Service::new(Router::new().post(create));
+
+        let content = TestClient::post("http://127.0.0.1:7979/?userName=jobs&age=20&tag=abc&city=sf")
+            .insert_header("x-email", "jobs@salvo.rs")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "jobs sf");
+
+        let mut res = TestClient::post("http://127.0.0.1:7979/?userName=admin&age=7&tag=A1&city=s")
+            .insert_header("x-email", "jobs")
+            .insert_header("accept", "application/json")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+        let body: serde_json::Value = serde_json::from_str(&res.take_string().await.unwrap()).unwrap();
+        let fields = body["error"]["fields"].as_array().unwrap();
+        let fields = fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{} {} {} {}",
+                    f["source"].as_str().unwrap(),
+                    f["path"].as_str().unwrap(),
+                    f["code"].as_str().unwrap(),
+                    f["message"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                "query userName custom name is reserved",
+                "header x-email email bad email",
+                "query age range value must be between 13 and 130",
+                "query tag regex value must match `^[a-z]+$`",
+                "query address.city length length must be at least 2",
+            ]
+        );
+
+        let mut res = TestClient::post("http://127.0.0.1:7979/?userName=toolongname&age=20&tag=a&city=sf")
+            .insert_header("x-email", "jobs@salvo.rs")
+            .insert_header("accept", "text/plain")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+        assert_eq!(
+            res.take_string().await.unwrap(),
+            "code:422,\nname:Unprocessable Entity,\nsummary:validation failed,\nfield:userName (query): length must be between 1 and 8"
+        );
+    }
 }
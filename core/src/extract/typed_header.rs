@@ -0,0 +1,71 @@
+use std::ops::{Deref, DerefMut};
+
+use async_trait::async_trait;
+use headers::{Header, HeaderMapExt};
+
+use super::FromRequest;
+use crate::http::StatusError;
+use crate::{Depot, Request};
+
+/// A header of the request decoded as a type of the [`headers`] crate, extracted as a handler parameter.
+///
+/// Extraction fails with `400 Bad Request` if the header is missing or invalid, use `Option<TypedHeader<H>>`
+/// for an optional header.
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::http::headers::UserAgent;
+/// use salvo_core::prelude::*;
+///
+/// #[handler]
+/// async fn hello(user_agent: TypedHeader<UserAgent>) -> String {
+///     format!("Hello {}", user_agent.as_str())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypedHeader<H>(pub H);
+impl<H> TypedHeader<H> {
+    /// Consumes self and returns the inner header.
+    #[inline]
+    pub fn into_inner(self) -> H {
+        self.0
+    }
+}
+impl<H> Deref for TypedHeader<H> {
+    type Target = H;
+
+    #[inline]
+    fn deref(&self) -> &H {
+        &self.0
+    }
+}
+impl<H> DerefMut for TypedHeader<H> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut H {
+        &mut self.0
+    }
+}
+
+#[async_trait]
+impl<H> FromRequest for TypedHeader<H>
+where
+    H: Header + Send,
+{
+    type Rejection = StatusError;
+
+    async fn from_request(req: &mut Request, _depot: &mut Depot) -> Result<Self, Self::Rejection> {
+        match req.headers().typed_try_get::<H>() {
+            Ok(Some(header)) => Ok(TypedHeader(header)),
+            Ok(None) => Err(StatusError::bad_request().with_detail(format!("Missing header `{}`.", H::name()))),
+            Err(_) => Err(StatusError::bad_request().with_detail(format!("Invalid header `{}`.", H::name()))),
+        }
+    }
+
+    async fn from_request_optional(req: &mut Request, _depot: &mut Depot) -> Result<Option<Self>, Self::Rejection> {
+        match req.headers().typed_try_get::<H>() {
+            Ok(header) => Ok(header.map(TypedHeader)),
+            Err(_) => Err(StatusError::bad_request().with_detail(format!("Invalid header `{}`.", H::name()))),
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Validation of [`Extractible`](super::Extractible) types.
+//!
+//! Fields are validated with `#[validate(...)]` attributes after the type is extracted by
+//! [`Request::extract`](crate::Request::extract):
+//!
+//! * `length(min = 1, max = 20)`: the number of chars of a string, or of items of a collection;
+//! * `range(min = 0, max = 100)`: a number, attribute values must be literals so negative bounds are written as
+//!   strings like `range(min = "-10")`;
+//! * `regex = "^[a-z]+$"`: a string matches the pattern, an invalid pattern is a compile error;
+//! * `email`: a string is an email address;
+//! * `custom = "path::to::function"`: a `fn(&FieldType) -> Result<(), String>` returns `Ok`;
+//! * `nested`: the field, extracted with the `request` source, is valid.
+//!
+//! Validators of an `Option` field only check the value if there is one. Every validator except `nested` accepts a
+//! `message` replacing the default message, like `length(max = 20, message = "name is too long")`.
+//!
+//! # Example
+//!
+//! ```
+//! use salvo_core::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Extractible, Debug)]
+//! #[extract(default_source(from = "body"))]
+//! struct NewUser {
+//!     #[validate(length(min = 1, max = 20))]
+//!     name: String,
+//!     #[validate(email)]
+//!     email: String,
+//!     #[validate(range(min = 13, message = "too young"))]
+//!     age: u8,
+//!     #[validate(range(min = "-12", max = 14))]
+//!     utc_offset: i8,
+//! }
+//!
+//! #[handler]
+//! async fn create_user(user: NewUser) -> String {
+//!     format!("created {}", user.name)
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! use salvo_core::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Extractible, Debug)]
+//! struct Tag {
+//!     #[validate(regex = "[a-z")]
+//!     name: String,
+//! }
+//! ```
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter, Write};
+
+use async_trait::async_trait;
+use mime::Mime;
+use regex::Regex;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use super::metadata::{Metadata, SourceFrom};
+use crate::http::header::{HeaderValue, CONTENT_TYPE};
+use crate::http::{guess_accept_mime, Request, Response, StatusCode};
+use crate::{Depot, Writer};
+
+/// A field failing validation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Where the field is extracted from, `None` if it has no source.
+    pub source: Option<SourceFrom>,
+    /// Path of the field, in the names used by the request, like `address.city` for nested types.
+    pub path: String,
+    /// The validator which fails, like `length`.
+    pub code: Cow<'static, str>,
+    /// Message describing the failure.
+    pub message: Cow<'static, str>,
+}
+impl ValidationError {
+    /// Create a new `ValidationError`.
+    #[inline]
+    pub fn new(
+        source: Option<SourceFrom>,
+        path: impl Into<String>,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        ValidationError {
+            source,
+            path: path.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+    /// Create a new `ValidationError` for field `field` of the type described by `metadata`, whose source and path
+    /// are taken from the metadata.
+    pub fn for_field(
+        metadata: &Metadata,
+        field: &str,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        let (source, path) = field_source_path(metadata, field);
+        ValidationError::new(source, path, code, message)
+    }
+}
+impl Display for ValidationError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Some(source) => write!(f, "{} ({}): {}", self.path, source_name(source), self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+impl Serialize for ValidationError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ValidationError", 4)?;
+        state.serialize_field("source", &self.source.map(source_name))?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("message", &self.message)?;
+        state.end()
+    }
+}
+
+fn field_source_path(metadata: &Metadata, field: &str) -> (Option<SourceFrom>, String) {
+    match metadata.fields.iter().find(|f| f.name == field) {
+        Some(f) => {
+            let source = f.sources.first().or_else(|| metadata.default_sources.first());
+            let path = match (f.rename, metadata.rename_all) {
+                (Some(rename), _) => rename.to_owned(),
+                (None, Some(rule)) => rule.rename(f.name),
+                (None, None) => f.name.to_owned(),
+            };
+            (source.map(|s| s.from), path)
+        }
+        None => (metadata.default_sources.first().map(|s| s.from), field.to_owned()),
+    }
+}
+
+fn source_name(source: SourceFrom) -> &'static str {
+    match source {
+        SourceFrom::Param => "param",
+        SourceFrom::Query => "query",
+        SourceFrom::Header => "header",
+        SourceFrom::Body => "body",
+        SourceFrom::Request => "request",
+    }
+}
+
+/// All fields failing validation, written to response as `422 Unprocessable Entity`.
+///
+/// Like [`CatcherImpl`](crate::CatcherImpl), the errors are rendered in the format preferred by the client: JSON,
+/// XML, plain text or HTML.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<ValidationError>);
+impl ValidationErrors {
+    /// Create an empty `ValidationErrors`.
+    #[inline]
+    pub fn new() -> Self {
+        ValidationErrors(Vec::new())
+    }
+    /// Add an error.
+    #[inline]
+    pub fn add(&mut self, error: ValidationError) {
+        self.0.push(error);
+    }
+    /// Add the errors of a nested field, prefixing their paths with the path of the field.
+    pub fn add_nested(&mut self, metadata: &Metadata, field: &str, errors: ValidationErrors) {
+        let (_, prefix) = field_source_path(metadata, field);
+        for mut error in errors.0 {
+            error.path = format!("{}.{}", prefix, error.path);
+            self.0.push(error);
+        }
+    }
+    /// Get the errors.
+    #[inline]
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.0
+    }
+    /// Returns `true` if there is no error.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Returns `Ok` if there is no error, else `Err(self)`.
+    #[inline]
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    fn to_bytes(&self, prefer_format: &Mime) -> (Mime, String) {
+        let code = StatusCode::UNPROCESSABLE_ENTITY;
+        let name = code.canonical_reason().unwrap_or_default();
+        match prefer_format.subtype().as_str() {
+            "json" => {
+                let content = serde_json::json!({
+                    "error": {
+                        "code": code.as_u16(),
+                        "name": name,
+                        "summary": SUMMARY,
+                        "fields": &self.0,
+                    }
+                });
+                (mime::APPLICATION_JSON, content.to_string())
+            }
+            "xml" => {
+                let mut content = format!(
+                    "<error><code>{}</code><name>{}</name><summary>{}</summary><fields>",
+                    code.as_u16(),
+                    name,
+                    SUMMARY
+                );
+                for error in &self.0 {
+                    write!(
+                        content,
+                        r#"<field source="{}" path="{}" code="{}">{}</field>"#,
+                        error.source.map(source_name).unwrap_or_default(),
+                        escape(&error.path),
+                        escape(&error.code),
+                        escape(&error.message)
+                    )
+                    .ok();
+                }
+                content.push_str("</fields></error>");
+                (mime::TEXT_XML, content)
+            }
+            "plain" => {
+                let mut content = format!("code:{},\nname:{},\nsummary:{}", code.as_u16(), name, SUMMARY);
+                for error in &self.0 {
+                    write!(content, ",\nfield:{}", error).ok();
+                }
+                (mime::TEXT_PLAIN, content)
+            }
+            _ => {
+                let mut items = String::new();
+                for error in &self.0 {
+                    write!(items, "<li>{}</li>", escape(&error.to_string())).ok();
+                }
+                let content = format!(
+                    r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width">
+    <title>{0}: {1}</title>
+</head>
+<body>
+    <div>
+        <h1>{0}: {1}</h1><h3>{2}</h3><ul>{3}</ul><hr />
+        <footer><a href="https://salvo.rs" target="_blank">salvo</a></footer>
+    </div>
+</body>
+</html>"#,
+                    code.as_u16(),
+                    name,
+                    SUMMARY,
+                    items
+                );
+                (mime::TEXT_HTML, content)
+            }
+        }
+    }
+}
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(SUMMARY)?;
+        for (i, error) in self.0.iter().enumerate() {
+            f.write_str(if i == 0 { ": " } else { "; " })?;
+            Display::fmt(error, f)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ValidationErrors {}
+
+const SUMMARY: &str = "validation failed";
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[async_trait]
+impl Writer for ValidationErrors {
+    async fn write(mut self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        let (format, content) = self.to_bytes(&guess_accept_mime(req, None));
+        res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+        if let Ok(value) = HeaderValue::from_str(&format!("{}; charset=utf-8", format)) {
+            res.headers_mut().insert(CONTENT_TYPE, value);
+        }
+        res.write_body(content).ok();
+    }
+}
+
+/// Values validated by `length`.
+pub trait Length {
+    /// Returns the length of the value, `None` to skip validation.
+    fn length(&self) -> Option<usize>;
+}
+impl Length for str {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+impl Length for String {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        self.as_str().length()
+    }
+}
+impl Length for Cow<'_, str> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        self.as_ref().length()
+    }
+}
+impl<T> Length for [T] {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<T> Length for Vec<T> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<T> Length for HashSet<T> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<T> Length for BTreeSet<T> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<K, V> Length for HashMap<K, V> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<K, V> Length for BTreeMap<K, V> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+impl<T: Length + ?Sized> Length for &T {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        (**self).length()
+    }
+}
+impl<T: Length> Length for Option<T> {
+    #[inline]
+    fn length(&self) -> Option<usize> {
+        self.as_ref().and_then(Length::length)
+    }
+}
+
+/// Values validated by `range`.
+pub trait Number {
+    /// Returns the value as `f64`, `None` to skip validation.
+    fn number(&self) -> Option<f64>;
+}
+macro_rules! impl_number {
+    ($($ty:ty),+) => {
+        $(
+            impl Number for $ty {
+                #[inline]
+                fn number(&self) -> Option<f64> {
+                    Some(*self as f64)
+                }
+            }
+        )+
+    };
+}
+impl_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+impl<T: Number + ?Sized> Number for &T {
+    #[inline]
+    fn number(&self) -> Option<f64> {
+        (**self).number()
+    }
+}
+impl<T: Number> Number for Option<T> {
+    #[inline]
+    fn number(&self) -> Option<f64> {
+        self.as_ref().and_then(Number::number)
+    }
+}
+
+/// Values validated by `regex` and `email`.
+pub trait Text {
+    /// Returns the value as `str`, `None` to skip validation.
+    fn text(&self) -> Option<&str>;
+}
+impl Text for str {
+    #[inline]
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+impl Text for String {
+    #[inline]
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+impl Text for Cow<'_, str> {
+    #[inline]
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+impl<T: Text + ?Sized> Text for &T {
+    #[inline]
+    fn text(&self) -> Option<&str> {
+        (**self).text()
+    }
+}
+impl<T: Text> Text for Option<T> {
+    #[inline]
+    fn text(&self) -> Option<&str> {
+        self.as_ref().and_then(Text::text)
+    }
+}
+
+/// Validates the length of `value` is in `min..=max`.
+pub fn length<T: Length + ?Sized>(value: &T, min: Option<usize>, max: Option<usize>) -> Result<(), String> {
+    let length = match value.length() {
+        Some(length) => length,
+        None => return Ok(()),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if length < min || length > max => {
+            Err(format!("length must be between {} and {}", min, max))
+        }
+        (Some(min), None) if length < min => Err(format!("length must be at least {}", min)),
+        (None, Some(max)) if length > max => Err(format!("length must be at most {}", max)),
+        _ => Ok(()),
+    }
+}
+
+/// Validates `value` is in `min..=max`.
+pub fn range<T: Number + ?Sized>(value: &T, min: Option<f64>, max: Option<f64>) -> Result<(), String> {
+    let number = match value.number() {
+        Some(number) => number,
+        None => return Ok(()),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if number < min || number > max => {
+            Err(format!("value must be between {} and {}", min, max))
+        }
+        (Some(min), None) if number < min => Err(format!("value must be at least {}", min)),
+        (None, Some(max)) if number > max => Err(format!("value must be at most {}", max)),
+        _ => Ok(()),
+    }
+}
+
+/// Validates `value` matches `regex`.
+pub fn regex<T: Text + ?Sized>(value: &T, regex: &Regex) -> Result<(), String> {
+    match value.text() {
+        Some(text) if !regex.is_match(text) => Err(format!("value must match `{}`", regex.as_str())),
+        _ => Ok(()),
+    }
+}
+
+/// Validates `value` is an email address.
+///
+/// This only checks the shape of the address: a non empty local part and a domain with a dot, no whitespace.
+pub fn email<T: Text + ?Sized>(value: &T) -> Result<(), String> {
+    let text = match value.text() {
+        Some(text) => text,
+        None => return Ok(()),
+    };
+    let valid = match text.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && local.len() <= 64
+                && domain.len() <= 255
+                && !text.chars().any(|c| c.is_whitespace() || c.is_control())
+                && !local.contains('@')
+                && domain
+                    .split('.')
+                    .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+                && domain.contains('.')
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err("value must be an email address".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators() {
+        assert!(length("salvo", Some(1), Some(5)).is_ok());
+        assert_eq!(length("", Some(1), None), Err("length must be at least 1".to_owned()));
+        assert_eq!(
+            length(&vec![1, 2, 3], None, Some(2)),
+            Err("length must be at most 2".to_owned())
+        );
+        assert!(length(&None::<String>, Some(1), None).is_ok());
+
+        assert!(range(&7u8, Some(1.0), Some(10.0)).is_ok());
+        assert_eq!(
+            range(&-1i32, Some(0.0), Some(10.0)),
+            Err("value must be between 0 and 10".to_owned())
+        );
+        assert!(range(&Some(2.5f64), None, Some(3.0)).is_ok());
+
+        let re = Regex::new("^[a-z]+$").unwrap();
+        assert!(regex("salvo", &re).is_ok());
+        assert!(regex("Salvo", &re).is_err());
+
+        assert!(email("user@salvo.rs").is_ok());
+        for invalid in ["user", "@salvo.rs", "user@salvo", "us er@salvo.rs", "user@salvo..rs"] {
+            assert!(email(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
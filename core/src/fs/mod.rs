@@ -1,20 +1,56 @@
 //! Filesystem module
+mod named_content;
 mod named_file;
+pub use named_content::*;
 pub use named_file::*;
 
 use std::cmp;
-use std::future::Future;
+use std::collections::VecDeque;
 use std::io::{self, Error as IoError, ErrorKind, Read, Seek};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
+use futures_util::future::BoxFuture;
 use futures_util::ready;
-use futures_util::stream::Stream;
+use futures_util::stream::{self, Stream};
+
+use crate::http::Response;
+
+pub(crate) const CHUNK_SIZE: u64 = 1024 * 1024;
+pub(crate) const MAX_RANGES: usize = 16;
+
+/// A source [`FileChunk`] reads chunks from.
+///
+/// Blocking readers like [`std::fs::File`] are read on the blocking thread pool, [`ContentReader`]s are read
+/// asynchronously.
+pub trait ChunkRead: Send + Sized + 'static {
+    /// Reads at most `max_bytes` bytes at `offset`, returns the source with the bytes read.
+    ///
+    /// Fails with [`ErrorKind::UnexpectedEof`] if there is nothing left to read.
+    fn read_chunk(self, offset: u64, max_bytes: u64) -> BoxFuture<'static, io::Result<(Self, Bytes)>>;
+}
+impl<T> ChunkRead for T
+where
+    T: Read + Seek + Send + 'static,
+{
+    fn read_chunk(mut self, offset: u64, max_bytes: u64) -> BoxFuture<'static, io::Result<(Self, Bytes)>> {
+        let fut = tokio::task::spawn_blocking(move || {
+            let mut buf = Vec::with_capacity(max_bytes as usize);
+            self.seek(io::SeekFrom::Start(offset))?;
+            let bytes = self.by_ref().take(max_bytes).read_to_end(&mut buf)?;
+            if bytes == 0 {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            Ok((self, Bytes::from(buf)))
+        });
+        Box::pin(async move { fut.await.map_err(|_| IoError::other("BlockingErr"))? })
+    }
+}
 
 pub(crate) enum ChunkedState<T> {
     File(Option<T>),
-    Future(tokio::task::JoinHandle<Result<(T, Bytes), IoError>>),
+    Future(BoxFuture<'static, io::Result<(T, Bytes)>>),
 }
 
 /// FileChunk
@@ -28,7 +64,7 @@ pub struct FileChunk<T> {
 
 impl<T> Stream for FileChunk<T>
 where
-    T: Read + Seek + Unpin + Send + 'static,
+    T: ChunkRead + Unpin,
 {
     type Item = Result<Bytes, IoError>;
 
@@ -39,25 +75,13 @@ where
 
         match self.state {
             ChunkedState::File(ref mut file) => {
-                let mut file = file.take().expect("ChunkedReadFile polled after completion");
-                let max_bytes = cmp::min(self.chunk_size.saturating_sub(self.read_size), self.buffer_size) as usize;
-                let offset = self.offset;
-                let fut = tokio::task::spawn_blocking(move || {
-                    let mut buf = Vec::with_capacity(max_bytes);
-                    file.seek(io::SeekFrom::Start(offset))?;
-                    let bytes = file.by_ref().take(max_bytes as u64).read_to_end(&mut buf)?;
-                    if bytes == 0 {
-                        return Err(ErrorKind::UnexpectedEof.into());
-                    }
-                    Ok((file, Bytes::from(buf)))
-                });
-
-                self.state = ChunkedState::Future(fut);
+                let file = file.take().expect("ChunkedReadFile polled after completion");
+                let max_bytes = cmp::min(self.chunk_size.saturating_sub(self.read_size), self.buffer_size);
+                self.state = ChunkedState::Future(file.read_chunk(self.offset, max_bytes));
                 self.poll_next(cx)
             }
             ChunkedState::Future(ref mut fut) => {
-                let (file, bytes) =
-                    ready!(Pin::new(fut).poll(cx)).map_err(|_| IoError::new(ErrorKind::Other, "BlockingErr"))??;
+                let (file, bytes) = ready!(fut.as_mut().poll(cx))?;
                 self.state = ChunkedState::File(Some(file));
 
                 self.offset += bytes.len() as u64;
@@ -69,6 +93,55 @@ where
     }
 }
 
+/// A part of the response body, content ranges are read from the source.
+pub(crate) enum RangePart {
+    Bytes(Bytes),
+    Content { offset: u64, length: u64 },
+}
+
+/// Streams `parts` of `source` to `res`, reading content ranges at most `buffer_size` bytes at a time.
+///
+/// A single content part is streamed with [`FileChunk`], `multipart/byteranges` bodies interleave the content
+/// ranges with their part headers.
+pub(crate) fn stream_parts<T>(source: T, mut parts: VecDeque<RangePart>, buffer_size: u64, res: &mut Response)
+where
+    T: ChunkRead + Unpin,
+{
+    if let [RangePart::Content { offset, length }] = parts.make_contiguous() {
+        let reader = FileChunk {
+            offset: *offset,
+            chunk_size: *length,
+            read_size: 0,
+            state: ChunkedState::File(Some(source)),
+            buffer_size,
+        };
+        res.streaming(reader).ok();
+        return;
+    }
+    let stream = stream::unfold((Some(source), parts), move |(source, mut parts)| async move {
+        let source = source?;
+        match parts.pop_front()? {
+            RangePart::Bytes(bytes) => Some((Ok(bytes), (Some(source), parts))),
+            RangePart::Content { offset, length } => {
+                match source.read_chunk(offset, cmp::min(length, buffer_size)).await {
+                    Ok((source, bytes)) => {
+                        let read = bytes.len() as u64;
+                        if read < length {
+                            parts.push_front(RangePart::Content {
+                                offset: offset + read,
+                                length: length - read,
+                            });
+                        }
+                        Some((Ok(bytes), (Some(source), parts)))
+                    }
+                    Err(e) => Some((Err(e), (None, parts))),
+                }
+            }
+        }
+    });
+    res.streaming(stream).ok();
+}
+
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
@@ -122,4 +195,183 @@ mod test {
             &HeaderValue::from_static("attachment; filename=attach.file")
         );
     }
+
+    #[tokio::test]
+    async fn test_named_file_ranges() {
+        use crate::prelude::*;
+        use crate::test::{ResponseExt, TestClient};
+
+        struct SendFile(std::path::PathBuf);
+        #[async_trait]
+        impl Handler for SendFile {
+            async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, _ctrl: &mut FlowCtrl) {
+                NamedFile::builder(&self.0).with_max_ranges(3).send(req, res).await;
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        std::fs::write(&path, "0123456789abcdefghij").unwrap();
+        let service = Service::new(Router::with_path("data.txt").get(SendFile(path)));
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=2-4,15-,3-6")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        let content_type = res.headers()["content-type"].to_str().unwrap().to_owned();
+        let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+        let content_length = res.headers()["content-length"]
+            .to_str()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        let body = res.take_string().await.unwrap();
+        assert_eq!(body.len(), content_length);
+        assert_eq!(
+            body,
+            format!(
+                "\r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 2-6/20\r\n\r\n23456\
+                 \r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 15-19/20\r\n\r\nfghij\
+                 \r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=0-1,3-4,6-7,9-10")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "0123456789abcdefghij");
+
+        let res = TestClient::get("http://127.0.0.1:7878/data.txt").send(&service).await;
+        let etag = res.headers()["etag"].clone();
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=10-")
+            .insert_header("if-range", etag)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.headers()["content-range"], "bytes 10-19/20");
+        assert_eq!(res.take_string().await.unwrap(), "abcdefghij");
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=10-")
+            .insert_header("if-range", "\"outdated\"")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "0123456789abcdefghij");
+    }
+
+    #[tokio::test]
+    async fn test_named_file_if_none_match() {
+        use crate::prelude::*;
+        use crate::test::{ResponseExt, TestClient};
+
+        #[handler(internal)]
+        async fn send_file(req: &mut Request, res: &mut Response) {
+            NamedFile::builder("Cargo.toml").send(req, res).await;
+        }
+        let service = Service::new(Router::new().get(send_file));
+
+        let res = TestClient::get("http://127.0.0.1:7878/").send(&service).await;
+        let etag = res.headers()["etag"].clone();
+        let res = TestClient::get("http://127.0.0.1:7878/")
+            .insert_header("if-none-match", etag.clone())
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_MODIFIED));
+        let res = TestClient::get("http://127.0.0.1:7878/")
+            .insert_header("if-none-match", "\"outdated\"")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        let mut res = TestClient::get("http://127.0.0.1:7878/")
+            .insert_header("if-match", etag)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert!(!res.take_string().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_named_content() {
+        use std::time::SystemTime;
+
+        use crate::prelude::*;
+        use crate::test::{ResponseExt, TestClient};
+
+        struct SendBytes;
+        #[async_trait]
+        impl Handler for SendBytes {
+            async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, _ctrl: &mut FlowCtrl) {
+                NamedContent::from_bytes("report.csv", "id,name\n1,salvo\n")
+                    .with_last_modified(SystemTime::UNIX_EPOCH)
+                    .send(req, res)
+                    .await;
+            }
+        }
+        struct SendReader;
+        #[async_trait]
+        impl Handler for SendReader {
+            async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, _ctrl: &mut FlowCtrl) {
+                let data = b"0123456789abcdefghij".to_vec();
+                NamedContent::from_reader("data.txt", std::io::Cursor::new(data), 20)
+                    .with_buffer_size(4)
+                    .send(req, res)
+                    .await;
+            }
+        }
+        let service = Service::new(
+            Router::new()
+                .push(Router::with_path("report.csv").get(SendBytes))
+                .push(Router::with_path("data.txt").get(SendReader)),
+        );
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/report.csv").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert_eq!(res.headers()["content-type"], "text/csv; charset=utf-8");
+        assert_eq!(res.headers()["last-modified"], "Thu, 01 Jan 1970 00:00:00 GMT");
+        let etag = res.headers()["etag"].clone();
+        assert_eq!(res.take_string().await.unwrap(), "id,name\n1,salvo\n");
+
+        let res = TestClient::get("http://127.0.0.1:7878/report.csv")
+            .insert_header("if-none-match", etag)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_MODIFIED));
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/report.csv")
+            .insert_header("range", "bytes=3-6")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.take_string().await.unwrap(), "name");
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=5-14")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert!(!res.headers().contains_key("etag"));
+        assert_eq!(res.take_string().await.unwrap(), "56789abcde");
+
+        let mut res = TestClient::get("http://127.0.0.1:7878/data.txt")
+            .insert_header("range", "bytes=0-1,18-")
+            .send(&service)
+            .await;
+        let content_type = res.headers()["content-type"].to_str().unwrap().to_owned();
+        let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+        assert_eq!(
+            res.take_string().await.unwrap(),
+            format!(
+                "\r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-1/20\r\n\r\n01\
+                 \r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 18-19/20\r\n\r\nij\
+                 \r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+    }
 }
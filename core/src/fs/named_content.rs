@@ -0,0 +1,336 @@
+use std::io::{self, Cursor, ErrorKind, SeekFrom};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use enumflags2::BitFlags;
+use futures_util::future::BoxFuture;
+use headers::*;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use super::named_file::{disposition, guess_content_type, prepare, ContentHeaders, Flag};
+use super::{stream_parts, ChunkRead, CHUNK_SIZE, MAX_RANGES};
+use crate::http::{Request, Response};
+use crate::{Depot, Error, Result, Writer};
+
+/// A reader of [`NamedContent`].
+pub trait ContentReader: AsyncRead + AsyncSeek + Send + Unpin + 'static {}
+impl<T> ContentReader for T where T: AsyncRead + AsyncSeek + Send + Unpin + 'static {}
+
+enum Source {
+    Bytes(Bytes),
+    Reader(Box<dyn ContentReader>),
+}
+
+/// A [`ContentReader`] read as a [`ChunkRead`] source.
+struct Reader(Box<dyn ContentReader>);
+impl ChunkRead for Reader {
+    fn read_chunk(mut self, offset: u64, max_bytes: u64) -> BoxFuture<'static, io::Result<(Self, Bytes)>> {
+        Box::pin(async move {
+            let mut buf = vec![0; max_bytes as usize];
+            self.0.seek(SeekFrom::Start(offset)).await?;
+            let read = self.0.read(&mut buf).await?;
+            if read == 0 {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            buf.truncate(read);
+            Ok((self, Bytes::from(buf)))
+        })
+    }
+}
+
+/// Content not stored in a file, like a report generated in memory, sent with the same headers, conditional
+/// requests and range handling as [`NamedFile`](super::NamedFile).
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::fs::NamedContent;
+/// use salvo_core::prelude::*;
+///
+/// #[fn_handler]
+/// async fn report(req: &mut Request, res: &mut Response) {
+///     let csv = "id,name\n1,salvo\n";
+///     NamedContent::from_bytes("report.csv", csv)
+///         .with_attached_name("report.csv")
+///         .send(req, res)
+///         .await;
+/// }
+/// ```
+pub struct NamedContent {
+    name: String,
+    source: Source,
+    len: u64,
+    modified: Option<SystemTime>,
+    etag: Option<ETag>,
+    buffer_size: u64,
+    max_ranges: usize,
+    flags: BitFlags<Flag>,
+    content_type: mime::Mime,
+    content_disposition: HeaderValue,
+    content_encoding: Option<HeaderValue>,
+}
+
+/// Builder for build [`NamedContent`].
+pub struct NamedContentBuilder {
+    name: String,
+    source: Source,
+    len: u64,
+    modified: Option<SystemTime>,
+    etag: Option<ETag>,
+    attached_name: Option<String>,
+    disposition_type: Option<String>,
+    content_type: Option<mime::Mime>,
+    content_encoding: Option<String>,
+    buffer_size: Option<u64>,
+    max_ranges: Option<usize>,
+    flags: BitFlags<Flag>,
+}
+impl NamedContentBuilder {
+    /// Set attached filename and returns `Self`.
+    #[inline]
+    pub fn with_attached_name<T: Into<String>>(mut self, attached_name: T) -> Self {
+        self.attached_name = Some(attached_name.into());
+        self
+    }
+    /// Set disposition encoding and returns `Self`.
+    #[inline]
+    pub fn with_disposition_type<T: Into<String>>(mut self, disposition_type: T) -> Self {
+        self.disposition_type = Some(disposition_type.into());
+        self
+    }
+    /// Set content type and returns `Self`.
+    #[inline]
+    pub fn with_content_type<T: Into<mime::Mime>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+    /// Set content encoding and returns `Self`.
+    #[inline]
+    pub fn with_content_encoding<T: Into<String>>(mut self, content_encoding: T) -> Self {
+        self.content_encoding = Some(content_encoding.into());
+        self
+    }
+    /// Set last modified time and returns `Self`.
+    #[inline]
+    pub fn with_last_modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+    /// Set ETag and returns `Self`.
+    ///
+    /// By default the ETag is derived from the length and the last modified time, there is no ETag if the last
+    /// modified time is unknown.
+    #[inline]
+    pub fn with_etag(mut self, etag: ETag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+    /// Set buffer size and returns `Self`.
+    #[inline]
+    pub fn with_buffer_size(mut self, buffer_size: u64) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+    /// Set max number of ranges served in one response and returns `Self`.
+    ///
+    /// Requests with more ranges, after overlapping ones are merged, get the whole content. Default is 16.
+    #[inline]
+    pub fn with_max_ranges(mut self, max_ranges: usize) -> Self {
+        self.max_ranges = Some(max_ranges);
+        self
+    }
+
+    ///Specifies whether to use ETag or not.
+    ///
+    ///Default is true.
+    #[inline]
+    pub fn use_etag(mut self, value: bool) -> Self {
+        if value {
+            self.flags.insert(Flag::Etag);
+        } else {
+            self.flags.remove(Flag::Etag);
+        }
+        self
+    }
+
+    ///Specifies whether to use Last-Modified or not.
+    ///
+    ///Default is true.
+    #[inline]
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        if value {
+            self.flags.insert(Flag::LastModified);
+        } else {
+            self.flags.remove(Flag::LastModified);
+        }
+        self
+    }
+
+    /// Build a new `NamedContent` and send it.
+    pub async fn send(self, req: &mut Request, res: &mut Response) {
+        match self.build() {
+            Ok(content) => content.send(req, res).await,
+            Err(e) => {
+                tracing::error!(error = ?e, "build named content failed");
+                res.set_status_error(crate::http::StatusError::internal_server_error());
+            }
+        }
+    }
+    /// Build a new [`NamedContent`].
+    pub fn build(self) -> Result<NamedContent> {
+        let NamedContentBuilder {
+            name,
+            source,
+            len,
+            modified,
+            etag,
+            attached_name,
+            disposition_type,
+            content_type,
+            content_encoding,
+            buffer_size,
+            max_ranges,
+            flags,
+        } = self;
+
+        let content_type = content_type.unwrap_or_else(|| guess_content_type(Path::new(&name)));
+        let content_disposition = disposition(&content_type, disposition_type, attached_name, || {
+            name.rsplit('/')
+                .next()
+                .filter(|file_name| !file_name.is_empty())
+                .unwrap_or("file")
+                .to_owned()
+        });
+        let content_disposition = content_disposition.parse::<HeaderValue>().map_err(Error::other)?;
+        let content_encoding = match content_encoding {
+            Some(content_encoding) => Some(content_encoding.parse::<HeaderValue>().map_err(Error::other)?),
+            None => None,
+        };
+        let etag = etag.or_else(|| {
+            // Same format as the ETag of `NamedFile`, without the inode.
+            let dur = modified?.duration_since(UNIX_EPOCH).ok()?;
+            format!("\"{:x}-{:x}-{:x}\"", len, dur.as_secs(), dur.subsec_nanos())
+                .parse::<ETag>()
+                .ok()
+        });
+
+        Ok(NamedContent {
+            name,
+            source,
+            len,
+            modified,
+            etag,
+            buffer_size: buffer_size.unwrap_or(CHUNK_SIZE),
+            max_ranges: max_ranges.unwrap_or(MAX_RANGES),
+            flags,
+            content_type,
+            content_disposition,
+            content_encoding,
+        })
+    }
+}
+
+impl NamedContent {
+    #[inline]
+    fn builder(name: String, source: Source, len: u64) -> NamedContentBuilder {
+        NamedContentBuilder {
+            name,
+            source,
+            len,
+            modified: None,
+            etag: None,
+            attached_name: None,
+            disposition_type: None,
+            content_type: None,
+            content_encoding: None,
+            buffer_size: None,
+            max_ranges: None,
+            flags: BitFlags::default(),
+        }
+    }
+    /// Create new [`NamedContentBuilder`] sending `data`.
+    ///
+    /// `name` is used to guess the content type and as file name of attachments.
+    #[inline]
+    pub fn from_bytes(name: impl Into<String>, data: impl Into<Bytes>) -> NamedContentBuilder {
+        let data = data.into();
+        let len = data.len() as u64;
+        Self::builder(name.into(), Source::Bytes(data), len)
+    }
+    /// Create new [`NamedContentBuilder`] sending `len` bytes read from `reader`, starting at its beginning.
+    ///
+    /// `name` is used to guess the content type and as file name of attachments.
+    #[inline]
+    pub fn from_reader(name: impl Into<String>, reader: impl ContentReader, len: u64) -> NamedContentBuilder {
+        Self::builder(name.into(), Source::Reader(Box::new(reader)), len)
+    }
+
+    /// Get the name of the content.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Get the content length.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    /// Returns `true` if the content is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get content type value.
+    #[inline]
+    pub fn content_type(&self) -> &mime::Mime {
+        &self.content_type
+    }
+    /// Get Content-Disposition value.
+    #[inline]
+    pub fn content_disposition(&self) -> &HeaderValue {
+        &self.content_disposition
+    }
+    /// Get content encoding value reference.
+    #[inline]
+    pub fn content_encoding(&self) -> Option<&HeaderValue> {
+        self.content_encoding.as_ref()
+    }
+    /// Get ETag value.
+    #[inline]
+    pub fn etag(&self) -> Option<&ETag> {
+        self.etag.as_ref()
+    }
+    /// Get last modified time.
+    #[inline]
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    ///Consume self and send content to [`Response`].
+    pub async fn send(self, req: &mut Request, res: &mut Response) {
+        let headers = ContentHeaders {
+            etag: self.etag.clone().filter(|_| self.flags.contains(Flag::Etag)),
+            last_modified: self.modified.filter(|_| self.flags.contains(Flag::LastModified)),
+            content_type: &self.content_type,
+            content_disposition: &self.content_disposition,
+            content_encoding: self.content_encoding.as_ref(),
+        };
+        let parts = match prepare(&headers, self.len, self.max_ranges, req, res) {
+            Some(parts) => parts,
+            None => return,
+        };
+        match self.source {
+            Source::Bytes(data) => stream_parts(Cursor::new(data), parts, self.buffer_size, res),
+            Source::Reader(reader) => stream_parts(Reader(reader), parts, self.buffer_size, res),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for NamedContent {
+    async fn write(mut self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        self.send(req, res).await;
+    }
+}
@@ -1,4 +1,5 @@
 use std::cmp;
+use std::collections::VecDeque;
 use std::fs::Metadata;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
@@ -9,17 +10,16 @@ use tokio::fs::File;
 use std::os::unix::fs::MetadataExt;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use enumflags2::{bitflags, BitFlags};
 use headers::*;
 use mime_guess::from_path;
 
-use super::{ChunkedState, FileChunk};
+use super::{stream_parts, RangePart, CHUNK_SIZE, MAX_RANGES};
 use crate::http::header::{self, CONTENT_DISPOSITION, CONTENT_ENCODING};
 use crate::http::{HttpRange, Request, Response, StatusCode, StatusError};
 use crate::{Depot, Error, Result, Writer};
 
-const CHUNK_SIZE: u64 = 1024 * 1024;
-
 #[bitflags(default = Etag | LastModified | ContentDisposition)]
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -36,6 +36,7 @@ pub struct NamedFile {
     file: File,
     modified: Option<SystemTime>,
     buffer_size: u64,
+    max_ranges: usize,
     metadata: Metadata,
     flags: BitFlags<Flag>,
     content_type: mime::Mime,
@@ -52,7 +53,9 @@ pub struct NamedFileBuilder {
     content_type: Option<mime::Mime>,
     content_encoding: Option<String>,
     content_disposition: Option<String>,
+    encoded_path: Option<PathBuf>,
     buffer_size: Option<u64>,
+    max_ranges: Option<usize>,
     flags: BitFlags<Flag>,
 }
 impl NamedFileBuilder {
@@ -80,12 +83,34 @@ impl NamedFileBuilder {
         self.content_encoding = Some(content_encoding.into());
         self
     }
+    /// Serves the content of `encoded_path` with `content_encoding` instead of the file itself and returns `Self`.
+    ///
+    /// `encoded_path` should be a precompressed version of the file, like `index.html.gz`. Content type
+    /// and disposition are still derived from the original path.
+    #[inline]
+    pub fn with_encoded_path<P: Into<PathBuf>, T: Into<String>>(
+        mut self,
+        encoded_path: P,
+        content_encoding: T,
+    ) -> Self {
+        self.encoded_path = Some(encoded_path.into());
+        self.content_encoding = Some(content_encoding.into());
+        self
+    }
     /// Set buffer size and returns `Self`.
     #[inline]
     pub fn with_buffer_size(mut self, buffer_size: u64) -> Self {
         self.buffer_size = Some(buffer_size);
         self
     }
+    /// Set max number of ranges served in one response and returns `Self`.
+    ///
+    /// Requests with more ranges, after overlapping ones are merged, get the whole file. Default is 16.
+    #[inline]
+    pub fn with_max_ranges(mut self, max_ranges: usize) -> Self {
+        self.max_ranges = Some(max_ranges);
+        self
+    }
 
     ///Specifies whether to use ETag or not.
     ///
@@ -131,49 +156,21 @@ impl NamedFileBuilder {
             content_type,
             content_encoding,
             content_disposition,
+            encoded_path,
             buffer_size,
+            max_ranges,
             disposition_type,
             attached_name,
             flags,
         } = self;
 
-        let file = File::open(&path).await?;
-        let content_type = content_type.unwrap_or_else(|| {
-            let ct = from_path(&path).first_or_octet_stream();
-            let ftype = ct.type_();
-            let stype = ct.subtype();
-            if (ftype == mime::TEXT || stype == mime::JSON || stype == mime::JAVASCRIPT)
-                && ct.get_param(mime::CHARSET).is_none()
-            {
-                //TODO: auto detect charset
-                format!("{}; charset=utf-8", ct).parse::<mime::Mime>().unwrap_or(ct)
-            } else {
-                ct
-            }
-        });
+        let file = File::open(encoded_path.as_ref().unwrap_or(&path)).await?;
+        let content_type = content_type.unwrap_or_else(|| guess_content_type(&path));
         let content_disposition = content_disposition.unwrap_or_else(|| {
-            disposition_type.unwrap_or_else(|| {
-                let disposition_type = if attached_name.is_some() {
-                    "attachment"
-                } else {
-                    match (content_type.type_(), content_type.subtype()) {
-                        (mime::IMAGE | mime::TEXT | mime::VIDEO | mime::AUDIO, _)
-                        | (_, mime::JAVASCRIPT | mime::JSON) => "inline",
-                        _ => "attachment",
-                    }
-                };
-                if disposition_type == "attachment" {
-                    let file_name = match attached_name {
-                        Some(file_name) => file_name,
-                        None => path
-                            .file_name()
-                            .map(|file_name| file_name.to_string_lossy().to_string())
-                            .unwrap_or_else(|| "file".into()),
-                    };
-                    format!("attachment; filename={}", file_name)
-                } else {
-                    disposition_type.into()
-                }
+            disposition(&content_type, disposition_type, attached_name, || {
+                path.file_name()
+                    .map(|file_name| file_name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "file".into())
             })
         });
         let content_disposition = content_disposition.parse::<HeaderValue>().map_err(Error::other)?;
@@ -193,6 +190,7 @@ impl NamedFileBuilder {
             modified,
             content_encoding,
             buffer_size: buffer_size.unwrap_or(CHUNK_SIZE),
+            max_ranges: max_ranges.unwrap_or(MAX_RANGES),
             flags,
         })
     }
@@ -209,7 +207,9 @@ impl NamedFile {
             content_type: None,
             content_encoding: None,
             content_disposition: None,
+            encoded_path: None,
             buffer_size: None,
+            max_ranges: None,
             flags: BitFlags::default(),
         }
     }
@@ -379,78 +379,163 @@ impl NamedFile {
         } else {
             None
         };
-
-        // check preconditions
-        let precondition_failed = if !any_match(etag.as_ref(), req) {
-            true
-        } else if let (Some(ref last_modified), Some(since)) =
-            (last_modified, req.headers().typed_get::<IfUnmodifiedSince>())
-        {
-            !since.precondition_passes(*last_modified)
-        } else {
-            false
+        let headers = ContentHeaders {
+            etag,
+            last_modified,
+            content_type: &self.content_type,
+            content_disposition: &self.content_disposition,
+            content_encoding: self.content_encoding.as_ref(),
         };
+        let parts = match prepare(&headers, self.metadata.len(), self.max_ranges, req, res) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let file = self.file.into_std().await;
+        stream_parts(file, parts, self.buffer_size, res);
+    }
+}
+
+/// Guesses the content type from the extension of `path`.
+pub(super) fn guess_content_type(path: &Path) -> mime::Mime {
+    let ct = from_path(path).first_or_octet_stream();
+    let ftype = ct.type_();
+    let stype = ct.subtype();
+    if (ftype == mime::TEXT || stype == mime::JSON || stype == mime::JAVASCRIPT)
+        && ct.get_param(mime::CHARSET).is_none()
+    {
+        //TODO: auto detect charset
+        format!("{}; charset=utf-8", ct).parse::<mime::Mime>().unwrap_or(ct)
+    } else {
+        ct
+    }
+}
 
-        // check last modified
-        let not_modified = if !none_match(etag.as_ref(), req) {
-            true
-        } else if req.headers().contains_key(header::IF_NONE_MATCH) {
-            false
-        } else if let (Some(ref last_modified), Some(since)) =
-            (last_modified, req.headers().typed_get::<IfModifiedSince>())
-        {
-            !since.is_modified(*last_modified)
+/// Builds the `Content-Disposition` value, `inline` for content browsers can display, `attachment` otherwise.
+pub(super) fn disposition(
+    content_type: &mime::Mime,
+    disposition_type: Option<String>,
+    attached_name: Option<String>,
+    file_name: impl FnOnce() -> String,
+) -> String {
+    disposition_type.unwrap_or_else(|| {
+        let disposition_type = if attached_name.is_some() {
+            "attachment"
         } else {
-            false
+            match (content_type.type_(), content_type.subtype()) {
+                (mime::IMAGE | mime::TEXT | mime::VIDEO | mime::AUDIO, _) | (_, mime::JAVASCRIPT | mime::JSON) => {
+                    "inline"
+                }
+                _ => "attachment",
+            }
         };
-
-        res.headers_mut()
-            .insert(CONTENT_DISPOSITION, self.content_disposition.clone());
-        res.headers_mut()
-            .typed_insert(ContentType::from(self.content_type.clone()));
-        if let Some(lm) = last_modified {
-            res.headers_mut().typed_insert(LastModified::from(lm));
-        }
-        if let Some(etag) = self.etag() {
-            res.headers_mut().typed_insert(etag);
+        if disposition_type == "attachment" {
+            format!("attachment; filename={}", attached_name.unwrap_or_else(file_name))
+        } else {
+            disposition_type.into()
         }
-        res.headers_mut().typed_insert(AcceptRanges::bytes());
+    })
+}
 
-        let mut length = self.metadata.len();
-        if let Some(content_encoding) = &self.content_encoding {
-            res.headers_mut().insert(CONTENT_ENCODING, content_encoding.clone());
-        }
-        let mut offset = 0;
-
-        // check for range header
-        // let mut range = None;
-        if let Some(ranges) = req.headers().get(header::RANGE) {
-            if let Ok(rangesheader) = ranges.to_str() {
-                if let Ok(rangesvec) = HttpRange::parse(rangesheader, length) {
-                    length = rangesvec[0].length;
-                    offset = rangesvec[0].start;
-                } else {
-                    res.headers_mut().typed_insert(ContentRange::unsatisfied_bytes(length));
-                    res.set_status_code(StatusCode::RANGE_NOT_SATISFIABLE);
-                    return;
-                };
+/// Headers describing the content sent by [`NamedFile`] and [`NamedContent`](super::NamedContent).
+pub(super) struct ContentHeaders<'a> {
+    pub(super) etag: Option<ETag>,
+    pub(super) last_modified: Option<SystemTime>,
+    pub(super) content_type: &'a mime::Mime,
+    pub(super) content_disposition: &'a HeaderValue,
+    pub(super) content_encoding: Option<&'a HeaderValue>,
+}
+
+/// Sets headers, answers preconditions and range requests for content of `total` bytes.
+///
+/// Returns the parts of the body to send, status and length are already set. It is a single content part unless
+/// several ranges are requested, then it is a `multipart/byteranges` body. Returns `None` if the response is
+/// complete.
+pub(super) fn prepare(
+    headers: &ContentHeaders<'_>,
+    total: u64,
+    max_ranges: usize,
+    req: &Request,
+    res: &mut Response,
+) -> Option<VecDeque<RangePart>> {
+    let etag = headers.etag.as_ref();
+    let last_modified = headers.last_modified;
+
+    // check preconditions
+    let precondition_failed = if !any_match(etag, req) {
+        true
+    } else if let (Some(ref last_modified), Some(since)) =
+        (last_modified, req.headers().typed_get::<IfUnmodifiedSince>())
+    {
+        !since.precondition_passes(*last_modified)
+    } else {
+        false
+    };
+
+    // check last modified
+    let not_modified = if !none_match(etag, req) {
+        true
+    } else if req.headers().contains_key(header::IF_NONE_MATCH) {
+        false
+    } else if let (Some(ref last_modified), Some(since)) = (last_modified, req.headers().typed_get::<IfModifiedSince>())
+    {
+        !since.is_modified(*last_modified)
+    } else {
+        false
+    };
+
+    res.headers_mut()
+        .insert(CONTENT_DISPOSITION, headers.content_disposition.clone());
+    res.headers_mut()
+        .typed_insert(ContentType::from(headers.content_type.clone()));
+    if let Some(lm) = last_modified {
+        res.headers_mut().typed_insert(LastModified::from(lm));
+    }
+    if let Some(etag) = etag {
+        res.headers_mut().typed_insert(etag.clone());
+    }
+    res.headers_mut().typed_insert(AcceptRanges::bytes());
+
+    if let Some(content_encoding) = headers.content_encoding {
+        res.headers_mut().insert(CONTENT_ENCODING, content_encoding.clone());
+    }
+
+    // check for range header, ranges are ignored if `If-Range` doesn't match the current content
+    let mut ranges = Vec::new();
+    let if_range_passes = match req.headers().typed_get::<IfRange>() {
+        Some(if_range) => !if_range.is_modified(etag, last_modified.map(LastModified::from).as_ref()),
+        None => true,
+    };
+    if let Some(header_value) = req.headers().get(header::RANGE).filter(|_| if_range_passes) {
+        if let Ok(header_value) = header_value.to_str() {
+            if let Ok(parsed) = HttpRange::parse(header_value, total) {
+                ranges = HttpRange::coalesce(parsed);
+                if ranges.len() > max_ranges {
+                    ranges.clear();
+                }
             } else {
-                res.set_status_code(StatusCode::BAD_REQUEST);
-                return;
+                res.headers_mut().typed_insert(ContentRange::unsatisfied_bytes(total));
+                res.set_status_code(StatusCode::RANGE_NOT_SATISFIABLE);
+                return None;
             };
-        }
+        } else {
+            res.set_status_code(StatusCode::BAD_REQUEST);
+            return None;
+        };
+    }
 
-        if precondition_failed {
-            res.set_status_code(StatusCode::PRECONDITION_FAILED);
-            return;
-        } else if not_modified {
-            res.set_status_code(StatusCode::NOT_MODIFIED);
-            return;
-        }
+    if precondition_failed {
+        res.set_status_code(StatusCode::PRECONDITION_FAILED);
+        return None;
+    } else if not_modified {
+        res.set_status_code(StatusCode::NOT_MODIFIED);
+        return None;
+    }
 
-        if offset != 0 || length != self.metadata.len() {
+    let mut parts = VecDeque::with_capacity(ranges.len() * 2 + 1);
+    match ranges.as_slice() {
+        [range] if range.start != 0 || range.length != total => {
             res.set_status_code(StatusCode::PARTIAL_CONTENT);
-            match ContentRange::bytes(offset..offset + length - 1, self.metadata.len()) {
+            match ContentRange::bytes(range.start..range.start + range.length, total) {
                 Ok(content_range) => {
                     res.headers_mut().typed_insert(content_range);
                 }
@@ -458,28 +543,57 @@ impl NamedFile {
                     tracing::error!(error = ?e, "set file's content ranage failed");
                 }
             }
-            let reader = FileChunk {
-                offset,
-                chunk_size: cmp::min(length, self.metadata.len()),
-                read_size: 0,
-                state: ChunkedState::File(Some(self.file.into_std().await)),
-                buffer_size: self.buffer_size,
-            };
-            res.headers_mut().typed_insert(ContentLength(reader.chunk_size));
-            res.streaming(reader).ok();
-        } else {
+            let length = cmp::min(range.length, total);
+            res.headers_mut().typed_insert(ContentLength(length));
+            parts.push_back(RangePart::Content {
+                offset: range.start,
+                length,
+            });
+        }
+        [_, _, ..] => {
+            res.set_status_code(StatusCode::PARTIAL_CONTENT);
+            let boundary = (0..24).map(|_| fastrand::alphanumeric()).collect::<String>();
+            let mut length = 0;
+            for range in &ranges {
+                let head = Bytes::from(format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                    boundary,
+                    headers.content_type,
+                    range.start,
+                    range.start + range.length - 1,
+                    total
+                ));
+                length += head.len() as u64 + range.length;
+                parts.push_back(RangePart::Bytes(head));
+                parts.push_back(RangePart::Content {
+                    offset: range.start,
+                    length: range.length,
+                });
+            }
+            let tail = Bytes::from(format!("\r\n--{}--\r\n", boundary));
+            length += tail.len() as u64;
+            parts.push_back(RangePart::Bytes(tail));
+
+            match format!("multipart/byteranges; boundary={}", boundary).parse::<HeaderValue>() {
+                Ok(content_type) => {
+                    res.headers_mut().insert(header::CONTENT_TYPE, content_type);
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "set multipart content type failed");
+                }
+            }
+            res.headers_mut().typed_insert(ContentLength(length));
+        }
+        _ => {
             res.set_status_code(StatusCode::OK);
-            let reader = FileChunk {
-                offset,
-                state: ChunkedState::File(Some(self.file.into_std().await)),
-                chunk_size: length,
-                read_size: 0,
-                buffer_size: self.buffer_size,
-            };
-            res.headers_mut().typed_insert(ContentLength(length - offset));
-            res.streaming(reader).ok();
+            res.headers_mut().typed_insert(ContentLength(total));
+            parts.push_back(RangePart::Content {
+                offset: 0,
+                length: total,
+            });
         }
     }
+    Some(parts)
 }
 
 #[async_trait]
@@ -519,17 +633,15 @@ fn any_match(etag: Option<&ETag>, req: &Request) -> bool {
     }
 }
 
-/// Returns true if `req` doesn't have an `If-None-Match` header matching `req`.
+/// Returns true if `req` doesn't have an `If-None-Match` header matching `etag`.
 fn none_match(etag: Option<&ETag>, req: &Request) -> bool {
-    match req.headers().typed_get::<IfMatch>() {
+    match req.headers().typed_get::<IfNoneMatch>() {
         None => true,
-        Some(if_match) => {
-            if if_match == IfMatch::any() {
-                false
-            } else if let Some(etag) = etag {
-                !if_match.precondition_passes(etag)
+        Some(if_none_match) => {
+            if let Some(etag) = etag {
+                if_none_match.precondition_passes(etag)
             } else {
-                true
+                if_none_match != IfNoneMatch::any()
             }
         }
     }
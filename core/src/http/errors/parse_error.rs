@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use serde::de::value::Error as DeError;
 use thiserror::Error;
 
+use crate::extract::ValidationErrors;
 use crate::http::StatusError;
 use crate::{Depot, Request, Response, Writer};
 
@@ -69,12 +70,44 @@ pub enum ParseError {
     /// Serde json error.
     #[error("Serde json error: {0}")]
     SerdeJson(#[from] serde_json::error::Error),
+
+    /// MessagePack decode error.
+    #[cfg(feature = "msgpack")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    #[error("MessagePack decode error: {0}")]
+    MsgPack(#[from] rmp_serde::decode::Error),
+
+    /// CBOR decode error.
+    #[cfg(feature = "cbor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    #[error("CBOR decode error: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+
+    /// XML decode error.
+    #[cfg(feature = "xml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    #[error("XML decode error: {0}")]
+    Xml(#[from] quick_xml::DeError),
+
+    /// Protobuf decode error.
+    #[cfg(feature = "protobuf")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    #[error("Protobuf decode error: {0}")]
+    Protobuf(#[from] prost::DecodeError),
+
+    /// Extracted data is invalid.
+    #[error("{0}")]
+    Validation(#[from] ValidationErrors),
 }
 
 #[async_trait]
 impl Writer for ParseError {
     #[inline]
-    async fn write(mut self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+    async fn write(mut self, req: &mut Request, depot: &mut Depot, res: &mut Response) {
+        if let ParseError::Validation(errors) = self {
+            errors.write(req, depot, res).await;
+            return;
+        }
         res.set_status_error(
             StatusError::internal_server_error()
                 .with_summary("http read error happened")
@@ -1,3 +1,5 @@
+use std::cmp;
+
 use crate::http::ParseError;
 
 /// HTTP Range header representation.
@@ -96,6 +98,22 @@ impl HttpRange {
 
         Ok(ranges)
     }
+
+    /// Sorts ranges and merges overlapping or adjacent ones, as recommended by RFC 7233 section 6.1.
+    pub fn coalesce(mut ranges: Vec<HttpRange>) -> Vec<HttpRange> {
+        ranges.sort_by_key(|range| range.start);
+        let mut coalesced: Vec<HttpRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match coalesced.last_mut() {
+                Some(last) if range.start <= last.start + last.length => {
+                    let end = cmp::max(last.start + last.length, range.start + range.length);
+                    last.length = end - last.start;
+                }
+                _ => coalesced.push(range),
+            }
+        }
+        coalesced
+    }
 }
 
 #[cfg(test)]
@@ -276,4 +294,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_coalesce() {
+        let ranges = HttpRange::parse("bytes=500-600,0-9,601-999,550-560,-5", 10000).unwrap();
+        let ranges = HttpRange::coalesce(ranges)
+            .into_iter()
+            .map(|range| (range.start, range.length))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(0, 10), (500, 500), (9995, 5)]);
+    }
 }
@@ -8,6 +8,7 @@ use cookie::{Cookie, CookieJar};
 use http::header::{self, HeaderMap};
 use http::method::Method;
 pub use http::request::Parts;
+use http::uri::Scheme;
 use http::version::Version;
 use http::{self, Extensions, Uri};
 pub use hyper::Body;
@@ -16,12 +17,12 @@ use once_cell::sync::OnceCell;
 use serde::de::{Deserialize, DeserializeOwned};
 
 use crate::addr::SocketAddr;
-use crate::extract::{Extractible, Metadata};
+use crate::extract::{Extractible, Metadata, NestedConfig};
 use crate::http::form::{FilePart, FormData};
 use crate::http::header::HeaderValue;
 use crate::http::Mime;
 use crate::http::ParseError;
-use crate::serde::{from_request, from_str_map, from_str_multi_map};
+use crate::serde::{from_request, from_str_map, from_str_multi_map, from_str_nested_map};
 
 /// Represents an HTTP request.
 ///
@@ -51,6 +52,7 @@ pub struct Request {
 
     /// The version of the HTTP protocol used.
     version: Version,
+    pub(crate) scheme: Scheme,
     pub(crate) remote_addr: Option<SocketAddr>,
 }
 
@@ -103,6 +105,7 @@ impl From<hyper::Request<Body>> for Request {
             CookieJar::new()
         };
 
+        let scheme = uri.scheme().cloned().unwrap_or(Scheme::HTTP);
         Request {
             queries: OnceCell::new(),
             uri,
@@ -117,6 +120,7 @@ impl From<hyper::Request<Body>> for Request {
             payload: tokio::sync::OnceCell::new(),
             // multipart: OnceCell::new(),
             version,
+            scheme,
             remote_addr: None,
         }
     }
@@ -138,6 +142,7 @@ impl Request {
             form_data: tokio::sync::OnceCell::new(),
             payload: tokio::sync::OnceCell::new(),
             version: Version::default(),
+            scheme: Scheme::HTTP,
             remote_addr: None,
         }
     }
@@ -209,6 +214,19 @@ impl Request {
     pub fn version_mut(&mut self) -> &mut Version {
         &mut self.version
     }
+    /// Returns the scheme of the connection the request was received on.
+    ///
+    /// Requests accepted by TLS listeners are `https`, others are `http`. This does not take
+    /// headers set by reverse proxies, such as `X-Forwarded-Proto`, into account.
+    #[inline]
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+    /// Returns a mutable reference to the associated scheme.
+    #[inline]
+    pub fn scheme_mut(&mut self) -> &mut Scheme {
+        &mut self.scheme
+    }
     /// Get request remote address.
     #[inline]
     pub fn remote_addr(&self) -> Option<&SocketAddr> {
@@ -502,13 +520,17 @@ impl Request {
         }
     }
 
-    /// Extract request as type `T` from request's different parts.
+    /// Extract request as type `T` from request's different parts, then validate it.
+    ///
+    /// Returns [`ParseError::Validation`] listing the invalid fields if validation fails.
     #[inline]
     pub async fn extract<'de, T>(&'de mut self) -> Result<T, ParseError>
     where
         T: Extractible<'de>,
     {
-        self.extract_with_metadata(T::metadata()).await
+        let data: T = self.extract_with_metadata(T::metadata()).await?;
+        data.validate()?;
+        Ok(data)
     }
 
     /// Extract request as type `T` from request's different parts.
@@ -540,6 +562,20 @@ impl Request {
         from_str_multi_map(queries).map_err(ParseError::Deserialize)
     }
 
+    /// Extract queries as type `T` from request, decoding bracket and dot notation keys like
+    /// `filter[tags][]=a&sort[0].field=name` into nested maps, sequences and structs within the limits of `config`.
+    #[inline]
+    pub fn extract_nested_queries<'de, T>(&'de mut self, config: &NestedConfig) -> Result<T, ParseError>
+    where
+        T: Deserialize<'de>,
+    {
+        let queries = self
+            .queries()
+            .iter_all()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)));
+        from_str_nested_map(queries, config).map_err(ParseError::Deserialize)
+    }
+
     /// Extract headers as type `T` from request.
     #[inline]
     pub fn extract_headers<'de, T>(&'de mut self) -> Result<T, ParseError>
@@ -570,6 +606,93 @@ impl Request {
         Err(ParseError::InvalidContentType)
     }
 
+    cfg_feature! {
+        #![feature = "msgpack"]
+        /// Extract MessagePack body as type `T` from request.
+        ///
+        /// The `Content-Type` must be `application/msgpack` or `application/x-msgpack`.
+        #[inline]
+        pub async fn extract_msgpack<'de, T>(&'de mut self) -> Result<T, ParseError>
+        where
+            T: Deserialize<'de>,
+        {
+            if let Some(ctype) = self.content_type() {
+                if is_msgpack(&ctype) {
+                    return self
+                        .payload()
+                        .await
+                        .and_then(|payload| rmp_serde::from_slice::<T>(payload).map_err(ParseError::MsgPack));
+                }
+            }
+            Err(ParseError::InvalidContentType)
+        }
+    }
+
+    cfg_feature! {
+        #![feature = "cbor"]
+        /// Extract CBOR body as type `T` from request.
+        ///
+        /// The `Content-Type` must be `application/cbor`.
+        #[inline]
+        pub async fn extract_cbor<T>(&mut self) -> Result<T, ParseError>
+        where
+            T: DeserializeOwned,
+        {
+            if let Some(ctype) = self.content_type() {
+                if is_cbor(&ctype) {
+                    return self
+                        .payload()
+                        .await
+                        .and_then(|payload| ciborium::from_reader::<T, _>(&payload[..]).map_err(ParseError::Cbor));
+                }
+            }
+            Err(ParseError::InvalidContentType)
+        }
+    }
+
+    cfg_feature! {
+        #![feature = "xml"]
+        /// Extract XML body as type `T` from request.
+        ///
+        /// The `Content-Type` must be `application/xml`, `text/xml` or end with `+xml`.
+        #[inline]
+        pub async fn extract_xml<'de, T>(&'de mut self) -> Result<T, ParseError>
+        where
+            T: Deserialize<'de>,
+        {
+            if let Some(ctype) = self.content_type() {
+                if is_xml(&ctype) {
+                    return self.payload().await.and_then(|payload| {
+                        quick_xml::de::from_str::<T>(std::str::from_utf8(payload)?).map_err(ParseError::Xml)
+                    });
+                }
+            }
+            Err(ParseError::InvalidContentType)
+        }
+    }
+
+    cfg_feature! {
+        #![feature = "protobuf"]
+        /// Extract Protobuf body as message `T` from request.
+        ///
+        /// The `Content-Type` must be `application/protobuf` or `application/x-protobuf`.
+        #[inline]
+        pub async fn extract_protobuf<T>(&mut self) -> Result<T, ParseError>
+        where
+            T: prost::Message + Default,
+        {
+            if let Some(ctype) = self.content_type() {
+                if is_protobuf(&ctype) {
+                    return self
+                        .payload()
+                        .await
+                        .and_then(|payload| T::decode(payload.as_slice()).map_err(ParseError::Protobuf));
+                }
+            }
+            Err(ParseError::InvalidContentType)
+        }
+    }
+
     /// Extract body as type `T` from request.
     #[inline]
     pub async fn extract_form<'de, T>(&'de mut self) -> Result<T, ParseError>
@@ -584,7 +707,30 @@ impl Request {
         Err(ParseError::InvalidContentType)
     }
 
-    /// Extract body as type `T` from request.
+    /// Extract form body as type `T` from request, decoding bracket and dot notation keys like
+    /// `filter[tags][]=a&sort[0].field=name` into nested maps, sequences and structs within the limits of `config`.
+    #[inline]
+    pub async fn extract_nested_form<'de, T>(&'de mut self, config: &NestedConfig) -> Result<T, ParseError>
+    where
+        T: Deserialize<'de>,
+    {
+        if let Some(ctype) = self.content_type() {
+            if ctype.subtype() == mime::WWW_FORM_URLENCODED || ctype.subtype() == mime::FORM_DATA {
+                let fields = self
+                    .form_data()
+                    .await?
+                    .fields
+                    .iter_all()
+                    .flat_map(|(key, values)| values.iter().map(move |value| (key, value)));
+                return from_str_nested_map(fields, config).map_err(ParseError::Deserialize);
+            }
+        }
+        Err(ParseError::InvalidContentType)
+    }
+
+    /// Extract body as type `T` from request, decoded according to its `Content-Type`.
+    ///
+    /// Forms and JSON are always supported, MessagePack, CBOR and XML are supported with their features.
     #[inline]
     pub async fn extract_body<T>(&mut self) -> Result<T, ParseError>
     where
@@ -599,11 +745,60 @@ impl Request {
                     .await
                     .and_then(|body| serde_json::from_slice::<T>(body).map_err(ParseError::SerdeJson));
             }
+            #[cfg(feature = "msgpack")]
+            if is_msgpack(&ctype) {
+                return self
+                    .payload()
+                    .await
+                    .and_then(|body| rmp_serde::from_slice::<T>(body).map_err(ParseError::MsgPack));
+            }
+            #[cfg(feature = "cbor")]
+            if is_cbor(&ctype) {
+                return self
+                    .payload()
+                    .await
+                    .and_then(|body| ciborium::from_reader::<T, _>(&body[..]).map_err(ParseError::Cbor));
+            }
+            #[cfg(feature = "xml")]
+            if is_xml(&ctype) {
+                return self.payload().await.and_then(|body| {
+                    quick_xml::de::from_str::<T>(std::str::from_utf8(body)?).map_err(ParseError::Xml)
+                });
+            }
         }
         Err(ParseError::InvalidContentType)
     }
 }
 
+cfg_feature! {
+    #![feature = "msgpack"]
+    #[inline]
+    pub(crate) fn is_msgpack(ctype: &Mime) -> bool {
+        ctype.type_() == mime::APPLICATION && (ctype.subtype() == "msgpack" || ctype.subtype() == "x-msgpack")
+    }
+}
+cfg_feature! {
+    #![feature = "cbor"]
+    #[inline]
+    pub(crate) fn is_cbor(ctype: &Mime) -> bool {
+        ctype.type_() == mime::APPLICATION && ctype.subtype() == "cbor"
+    }
+}
+cfg_feature! {
+    #![feature = "xml"]
+    #[inline]
+    pub(crate) fn is_xml(ctype: &Mime) -> bool {
+        ctype.subtype() == mime::XML || ctype.suffix() == Some(mime::XML)
+    }
+}
+cfg_feature! {
+    #![feature = "protobuf"]
+    #[inline]
+    pub(crate) fn is_protobuf(ctype: &Mime) -> bool {
+        ctype.type_() == mime::APPLICATION && (ctype.subtype() == "protobuf" || ctype.subtype() == "x-protobuf")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde::{Deserialize, Serialize};
@@ -642,6 +837,41 @@ mod tests {
         assert_eq!(man.wives, "a");
         assert_eq!(man.weapons, 69);
     }
+    #[tokio::test]
+    async fn test_parse_nested_queries() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Weapon {
+            name: String,
+            power: u32,
+        }
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Man {
+            name: String,
+            weapons: Vec<Weapon>,
+        }
+        let mut req = TestClient::get(
+            "http://127.0.0.1:7979/hello?name=rust&weapons[0][name]=stick&weapons[0][power]=3&weapons[1].name=gun&weapons[1].power=9",
+        )
+        .build();
+        let man = req.extract_nested_queries::<Man>(&NestedConfig::default()).unwrap();
+        assert_eq!(man.name, "rust");
+        assert_eq!(
+            man.weapons,
+            vec![
+                Weapon {
+                    name: "stick".into(),
+                    power: 3
+                },
+                Weapon {
+                    name: "gun".into(),
+                    power: 9
+                }
+            ]
+        );
+        assert!(req
+            .extract_nested_queries::<Man>(&NestedConfig::new().with_max_params(4))
+            .is_err());
+    }
 
     #[tokio::test]
     async fn test_parse_json() {
@@ -654,6 +884,75 @@ mod tests {
             .build();
         assert_eq!(req.extract_json::<User>().await.unwrap(), User { name: "jobs".into() });
     }
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_parse_msgpack() {
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        struct User<'a> {
+            name: &'a str,
+        }
+        let mut req = TestClient::post("http://127.0.0.1:7878/hello")
+            .insert_header("content-type", "application/msgpack")
+            .bytes(rmp_serde::to_vec_named(&User { name: "jobs" }).unwrap())
+            .build();
+        assert_eq!(req.extract_msgpack::<User>().await.unwrap(), User { name: "jobs" });
+        assert!(matches!(
+            req.extract_json::<User>().await,
+            Err(ParseError::InvalidContentType)
+        ));
+    }
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_parse_cbor() {
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        struct User {
+            name: String,
+        }
+        let mut req = TestClient::post("http://127.0.0.1:7878/hello")
+            .insert_header("content-type", "application/cbor")
+            .bytes({
+                let mut bytes = Vec::new();
+                ciborium::into_writer(&User { name: "jobs".into() }, &mut bytes).unwrap();
+                bytes
+            })
+            .build();
+        assert_eq!(req.extract_body::<User>().await.unwrap(), User { name: "jobs".into() });
+    }
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn test_parse_xml() {
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        struct User {
+            name: String,
+            age: u8,
+        }
+        let mut req = TestClient::post("http://127.0.0.1:7878/hello")
+            .insert_header("content-type", "application/atom+xml")
+            .text("<User><name>jobs</name><age>56</age></User>")
+            .build();
+        assert_eq!(
+            req.extract_xml::<User>().await.unwrap(),
+            User {
+                name: "jobs".into(),
+                age: 56
+            }
+        );
+    }
+    #[cfg(feature = "protobuf")]
+    #[tokio::test]
+    async fn test_parse_protobuf() {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct User {
+            #[prost(string, tag = "1")]
+            name: String,
+        }
+        let user = User { name: "jobs".into() };
+        let mut req = TestClient::post("http://127.0.0.1:7878/hello")
+            .insert_header("content-type", "application/x-protobuf")
+            .bytes(prost::Message::encode_to_vec(&user))
+            .build();
+        assert_eq!(req.extract_protobuf::<User>().await.unwrap(), user);
+    }
     #[tokio::test]
     async fn test_query() {
         let mut req = TestClient::get("http://127.0.0.1:7878/hello?q=rust").build();
@@ -28,6 +28,7 @@ pub mod routing;
 pub(crate) mod serde;
 mod server;
 mod service;
+pub mod state;
 mod transport;
 pub mod writer;
 cfg_feature! {
@@ -36,9 +37,9 @@ cfg_feature! {
 }
 
 pub use self::catcher::{Catcher, CatcherImpl};
-pub use self::depot::Depot;
+pub use self::depot::{Depot, DepotEntry, DepotError, DepotKey};
 pub use self::error::Error;
-pub use self::extract::Extractible;
+pub use self::extract::{Extractible, FromRequest};
 pub use self::handler::Handler;
 pub use self::http::{Request, Response};
 pub use self::listener::Listener;
@@ -52,9 +53,10 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// A list of things that automatically imports into application use salvo.
 pub mod prelude {
     pub use async_trait::async_trait;
-    pub use salvo_macros::{fn_handler, handler, Extractible};
+    pub use salvo_macros::{delete, fn_handler, get, handler, head, options, patch, post, put, Extractible};
 
-    pub use crate::depot::Depot;
+    pub use crate::depot::{Depot, DepotKey};
+    pub use crate::extract::TypedHeader;
     pub use crate::http::{Request, Response, StatusCode, StatusError};
     cfg_feature! {
         #![feature ="acme"]
@@ -68,19 +70,58 @@ pub mod prelude {
         #![unix]
         pub use crate::listener::UnixListener;
     }
+    cfg_feature! {
+        #![feature = "msgpack"]
+        pub use crate::writer::MsgPack;
+    }
+    cfg_feature! {
+        #![feature = "cbor"]
+        pub use crate::writer::Cbor;
+    }
+    cfg_feature! {
+        #![feature = "xml"]
+        pub use crate::writer::Xml;
+    }
+    cfg_feature! {
+        #![feature = "protobuf"]
+        pub use crate::writer::Protobuf;
+    }
     // pub use crate::extract::{Extractible, Extractor};
     pub use crate::listener::{JoinedListener, Listener, TcpListener};
     pub use crate::routing::{FlowCtrl, Router};
     pub use crate::server::Server;
     pub use crate::service::Service;
+    pub use crate::state::{Scoped, State};
     pub use crate::writer::{Json, Piece, Text, Writer};
     pub use crate::Handler;
 }
 
 #[doc(hidden)]
 pub mod __private {
+    #[cfg(feature = "route-registry")]
+    pub use inventory;
     pub use once_cell;
+    pub use regex;
     pub use tracing;
+
+    pub use crate::__register_route as register_route;
+
+    /// Registers a route declared by a route attribute macro, if the `route-registry` feature is enabled.
+    #[cfg(feature = "route-registry")]
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __register_route {
+        ($route:expr) => {
+            $crate::__private::inventory::submit! { $route }
+        };
+    }
+    /// Registers a route declared by a route attribute macro, if the `route-registry` feature is enabled.
+    #[cfg(not(feature = "route-registry"))]
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __register_route {
+        ($route:expr) => {};
+    }
 }
 
 use std::{future::Future, thread::available_parallelism};
@@ -81,6 +81,7 @@ use async_trait::async_trait;
 use client::AcmeClient;
 use futures_util::ready;
 use futures_util::Future;
+use http::uri::Scheme;
 use hyper::server::accept::Accept;
 use hyper::server::conn::{AddrIncoming, AddrStream};
 use parking_lot::RwLock;
@@ -427,6 +428,10 @@ impl Transport for AcmeStream {
     fn remote_addr(&self) -> Option<SocketAddr> {
         Some(self.remote_addr.clone())
     }
+    #[inline]
+    fn scheme(&self) -> Scheme {
+        Scheme::HTTPS
+    }
 }
 
 impl AcmeStream {
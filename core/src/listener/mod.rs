@@ -6,6 +6,7 @@ use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use http::uri::Scheme;
 use hyper::server::accept::Accept;
 use hyper::server::conn::AddrIncoming;
 use hyper::server::conn::AddrStream;
@@ -123,6 +124,13 @@ where
             JoinedStream::B(stream) => stream.remote_addr(),
         }
     }
+    #[inline]
+    fn scheme(&self) -> Scheme {
+        match self {
+            JoinedStream::A(stream) => stream.scheme(),
+            JoinedStream::B(stream) => stream.scheme(),
+        }
+    }
 }
 
 /// JoinedListener
@@ -8,6 +8,7 @@ use std::task::{Context, Poll};
 
 use futures_util::future::Ready;
 use futures_util::{ready, stream, Stream};
+use http::uri::Scheme;
 use hyper::server::accept::Accept;
 use hyper::server::conn::{AddrIncoming, AddrStream};
 use pin_project_lite::pin_project;
@@ -219,6 +220,10 @@ impl Transport for NativeTlsStream {
     fn remote_addr(&self) -> Option<SocketAddr> {
         Some(self.remote_addr.clone())
     }
+    #[inline]
+    fn scheme(&self) -> Scheme {
+        Scheme::HTTPS
+    }
 }
 
 impl NativeTlsStream {
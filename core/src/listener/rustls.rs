@@ -9,15 +9,18 @@ use std::task::{Context, Poll};
 
 use futures_util::future::Ready;
 use futures_util::{ready, stream, Stream};
+use http::uri::Scheme;
 use hyper::server::accept::Accept;
 use hyper::server::conn::{AddrIncoming, AddrStream};
 use pin_project_lite::pin_project;
 use rustls_pemfile::{self, pkcs8_private_keys, rsa_private_keys};
 use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncWrite, ErrorKind, ReadBuf};
+pub use tokio_rustls::rustls::client::ClientConfig;
 pub use tokio_rustls::rustls::server::ServerConfig;
 use tokio_rustls::rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth};
-use tokio_rustls::rustls::{Certificate, Error as RustlsError, PrivateKey, RootCertStore};
+pub use tokio_rustls::rustls::RootCertStore;
+use tokio_rustls::rustls::{Certificate, Error as RustlsError, PrivateKey};
 
 use super::{IntoAddrIncoming, LazyFile, Listener};
 use crate::addr::SocketAddr;
@@ -182,37 +185,8 @@ impl RustlsConfig {
         self
     }
     /// ServerConfig
-    pub fn build_server_config(mut self) -> Result<ServerConfig, Error> {
-        let mut cert_rdr = BufReader::new(self.cert);
-        let cert_chain = rustls_pemfile::certs(&mut cert_rdr)
-            .map_err(|_| Error::CertParseError)?
-            .into_iter()
-            .map(Certificate)
-            .collect();
-
-        let key = {
-            // convert it to Vec<u8> to allow reading it again if key is RSA
-            let mut key_vec = Vec::new();
-            self.key.read_to_end(&mut key_vec).map_err(Error::Io)?;
-
-            if key_vec.is_empty() {
-                return Err(Error::EmptyKey);
-            }
-
-            let mut pkcs8 = pkcs8_private_keys(&mut key_vec.as_slice()).map_err(|_| Error::Pkcs8ParseError)?;
-
-            if !pkcs8.is_empty() {
-                pkcs8.remove(0)
-            } else {
-                let mut rsa = rsa_private_keys(&mut key_vec.as_slice()).map_err(|_| Error::RsaParseError)?;
-
-                if !rsa.is_empty() {
-                    rsa.remove(0)
-                } else {
-                    return Err(Error::EmptyKey);
-                }
-            }
-        };
+    pub fn build_server_config(self) -> Result<ServerConfig, Error> {
+        let (cert_chain, key) = read_cert_and_key(self.cert, self.key)?;
 
         let client_auth = match self.client_auth {
             TlsClientAuth::Off => NoClientAuth::new(),
@@ -228,10 +202,70 @@ impl RustlsConfig {
             .with_safe_default_protocol_versions()
             .map_err(|_| Error::RsaParseError)?
             .with_client_cert_verifier(client_auth)
-            .with_single_cert_with_ocsp_and_sct(cert_chain, PrivateKey(key), self.ocsp_resp, Vec::new())
+            .with_single_cert_with_ocsp_and_sct(cert_chain, key, self.ocsp_resp, Vec::new())
             .map_err(Error::InvalidKey)?;
         Ok(config)
     }
+
+    /// Build [`ClientConfig`] for outbound connections, like requests of this server to other services.
+    ///
+    /// `roots` and the client authentication trust anchor of this config are trusted to verify servers, the
+    /// certificate and key, if set, are presented to servers which ask for client authentication.
+    pub fn build_client_config(mut self, mut roots: RootCertStore) -> Result<ClientConfig, Error> {
+        match self.client_auth {
+            TlsClientAuth::Off => {}
+            TlsClientAuth::Optional(trust_anchor) | TlsClientAuth::Required(trust_anchor) => {
+                roots.roots.extend(read_trust_anchor(trust_anchor)?.roots);
+            }
+        }
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        let mut cert = Vec::new();
+        self.cert.read_to_end(&mut cert).map_err(Error::Io)?;
+        if cert.is_empty() {
+            return Ok(builder.with_no_client_auth());
+        }
+        let (cert_chain, key) = read_cert_and_key(Box::new(Cursor::new(cert)), self.key)?;
+        builder.with_single_cert(cert_chain, key).map_err(Error::InvalidKey)
+    }
+}
+
+#[inline]
+fn read_cert_and_key(
+    cert: Box<dyn Read + Send + Sync>,
+    mut key: Box<dyn Read + Send + Sync>,
+) -> Result<(Vec<Certificate>, PrivateKey), Error> {
+    let mut cert_rdr = BufReader::new(cert);
+    let cert_chain = rustls_pemfile::certs(&mut cert_rdr)
+        .map_err(|_| Error::CertParseError)?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    // convert it to Vec<u8> to allow reading it again if key is RSA
+    let mut key_vec = Vec::new();
+    key.read_to_end(&mut key_vec).map_err(Error::Io)?;
+
+    if key_vec.is_empty() {
+        return Err(Error::EmptyKey);
+    }
+
+    let mut pkcs8 = pkcs8_private_keys(&mut key_vec.as_slice()).map_err(|_| Error::Pkcs8ParseError)?;
+
+    let key = if !pkcs8.is_empty() {
+        pkcs8.remove(0)
+    } else {
+        let mut rsa = rsa_private_keys(&mut key_vec.as_slice()).map_err(|_| Error::RsaParseError)?;
+
+        if !rsa.is_empty() {
+            rsa.remove(0)
+        } else {
+            return Err(Error::EmptyKey);
+        }
+    };
+    Ok((cert_chain, PrivateKey(key)))
 }
 
 #[inline]
@@ -388,6 +422,10 @@ impl Transport for RustlsStream {
     fn remote_addr(&self) -> Option<SocketAddr> {
         Some(self.remote_addr.clone())
     }
+    #[inline]
+    fn scheme(&self) -> Scheme {
+        Scheme::HTTPS
+    }
 }
 
 impl RustlsStream {
@@ -511,4 +549,35 @@ mod tests {
         let mut stream = listener.next().await.unwrap().unwrap();
         assert_eq!(stream.read_i32().await.unwrap(), 518);
     }
+
+    #[tokio::test]
+    async fn test_build_client_config() {
+        let config = || {
+            RustlsConfig::new()
+                .with_key_path("certs/rsa/end.rsa")
+                .with_cert_path("certs/rsa/end.cert")
+                .with_client_auth_required_path("certs/rsa/end.chain")
+        };
+        let mut listener = RustlsListener::with_rustls_config(config()).bind("127.0.0.1:0");
+        let addr = listener.local_addr();
+
+        // Files are read when the config is built.
+        assert!(config()
+            .with_key_path("certs/rsa/missing.rsa")
+            .build_client_config(RootCertStore::empty())
+            .is_err());
+        let client_config = config().build_client_config(RootCertStore::empty()).unwrap();
+        tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let connector = TlsConnector::from(Arc::new(client_config));
+            let mut tls_stream = connector
+                .connect(ServerName::try_from("testserver.com").unwrap(), stream)
+                .await
+                .unwrap();
+            tls_stream.write_i32(518).await.unwrap();
+        });
+
+        let mut stream = listener.next().await.unwrap().unwrap();
+        assert_eq!(stream.read_i32().await.unwrap(), 518);
+    }
 }
@@ -2,8 +2,12 @@
 //! Router can route http requests to different handlers.
 
 pub mod filter;
+mod route;
 mod router;
 pub use filter::*;
+#[cfg(feature = "route-registry")]
+pub use route::registered_routes;
+pub use route::Route;
 pub use router::{DetectMatched, Router};
 
 use std::borrow::Cow;
@@ -0,0 +1,375 @@
+use std::fmt::{self, Formatter};
+use std::sync::Arc;
+
+use super::{MethodFilter, Router};
+use crate::http::Method;
+use crate::Handler;
+
+/// A handler declared with a route attribute macro like [`get`](crate::macros::get), with its method and path.
+///
+/// Routers can be built from routes with [`Router::with_routes`]. With the `route-registry` feature, every route
+/// declared with an attribute macro is also registered at compile time, so routers can be built with
+/// `Router::with_module_routes` or `Router::with_registered_routes`.
+pub struct Route {
+    method: Method,
+    path: &'static str,
+    module: &'static str,
+    handler: fn() -> Arc<dyn Handler>,
+}
+cfg_feature! {
+    #![feature = "route-registry"]
+    inventory::collect!(Route);
+}
+
+impl Route {
+    /// Create a new `Route`, used by route attribute macros.
+    #[inline]
+    pub const fn new(
+        method: Method,
+        path: &'static str,
+        module: &'static str,
+        handler: fn() -> Arc<dyn Handler>,
+    ) -> Self {
+        Route {
+            method,
+            path,
+            module,
+            handler,
+        }
+    }
+    /// Get the method of the route.
+    #[inline]
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+    /// Get the path of the route.
+    #[inline]
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+    /// Get the path of the module which the route is declared in.
+    #[inline]
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+    /// Create the handler of the route.
+    #[inline]
+    pub fn handler(&self) -> Arc<dyn Handler> {
+        (self.handler)()
+    }
+
+    /// Path segments of the route, `/` inside a parameter like `<id:/\d+/>` does not split segments.
+    fn segments(&self) -> impl Iterator<Item = &'static str> {
+        let path = self.path;
+        let bytes = path.as_bytes();
+        let mut segments = Vec::new();
+        let (mut start, mut in_param, mut in_regex) = (0, false, false);
+        for (i, byte) in bytes.iter().enumerate() {
+            match byte {
+                b'/' if in_regex && path[i + 1..].trim_start_matches([' ', '\t']).starts_with('>') => in_regex = false,
+                b'/' if !in_param => {
+                    segments.push(&path[start..i]);
+                    start = i + 1;
+                }
+                b'<' if !in_regex => in_param = true,
+                b'>' if !in_regex => in_param = false,
+                b':' if in_param && bytes.get(i + 1) == Some(&b'/') => in_regex = true,
+                _ => {}
+            }
+        }
+        segments.push(&path[start..]);
+        segments.into_iter().filter(|segment| !segment.is_empty())
+    }
+}
+impl fmt::Debug for Route {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Route")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("module", &self.module)
+            .finish()
+    }
+}
+
+cfg_feature! {
+    #![feature = "route-registry"]
+    /// Returns all routes registered by route attribute macros, in all linked crates.
+    #[inline]
+    pub fn registered_routes() -> impl Iterator<Item = &'static Route> {
+        inventory::iter::<Route>.into_iter()
+    }
+
+    /// Sort rank of a path segment: static segments first, then parameters, then wildcards.
+    #[inline]
+    fn segment_rank(segment: &str) -> u8 {
+        if !segment.starts_with('<') {
+            0
+        } else if segment.starts_with("<*") {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Default)]
+struct RouteNode<'a> {
+    segment: &'a str,
+    routes: Vec<&'a Route>,
+    children: Vec<RouteNode<'a>>,
+}
+impl<'a> RouteNode<'a> {
+    fn insert(&mut self, route: &'a Route) {
+        let mut node = self;
+        for segment in route.segments() {
+            let index = match node.children.iter().position(|child| child.segment == segment) {
+                Some(index) => index,
+                None => {
+                    node.children.push(RouteNode {
+                        segment,
+                        ..Default::default()
+                    });
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+        }
+        node.routes.push(route);
+    }
+    fn into_router(self, mut router: Router) -> Router {
+        for child in self.children {
+            let child_router = Router::with_path(child.segment);
+            router = router.push(child.into_router(child_router));
+        }
+        for route in self.routes {
+            let mut method_router = Router::with_filter(MethodFilter(route.method.clone()));
+            method_router.handler = Some(route.handler());
+            router = router.push(method_router);
+        }
+        router
+    }
+}
+
+impl Router {
+    /// Create a new router with `routes`, in the same tree as written by hand: one child router per path segment,
+    /// and one child router per method for the handlers.
+    ///
+    /// Routes sharing path segments share routers, the order of `routes` is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use salvo_core::prelude::*;
+    ///
+    /// #[get("users")]
+    /// async fn list_users() -> &'static str {
+    ///     "users"
+    /// }
+    /// #[get("users/<id>")]
+    /// async fn show_user(req: &mut Request) -> String {
+    ///     req.param::<String>("id").unwrap()
+    /// }
+    ///
+    /// // Same as `Router::new().push(Router::with_path("users").push(Router::with_path("<id>").get(show_user)).get(list_users))`.
+    /// let router = Router::with_routes([&list_users::ROUTE, &show_user::ROUTE]);
+    /// ```
+    pub fn with_routes<'a>(routes: impl IntoIterator<Item = &'a Route>) -> Self {
+        let mut root = RouteNode::default();
+        for route in routes {
+            root.insert(route);
+        }
+        root.into_router(Router::new())
+    }
+}
+
+cfg_feature! {
+    #![feature = "route-registry"]
+    impl Router {
+        /// Create a new router with the registered routes declared in module `module` or its descendants, usually
+        /// `module_path!()`.
+        ///
+        /// Registration order is unspecified, so the routes are sorted by path: static segments first, then
+        /// parameters, then wildcards.
+        #[inline]
+        pub fn with_module_routes(module: &str) -> Self {
+            Router::with_sorted_routes(registered_routes().filter(|route| {
+                route.module == module
+                    || route
+                        .module
+                        .strip_prefix(module)
+                        .map(|rest| rest.starts_with("::"))
+                        .unwrap_or(false)
+            }))
+        }
+
+        /// Create a new router with all registered routes.
+        ///
+        /// Registration order is unspecified, so the routes are sorted by path: static segments first, then
+        /// parameters, then wildcards.
+        #[inline]
+        pub fn with_registered_routes() -> Self {
+            Router::with_sorted_routes(registered_routes())
+        }
+
+        fn with_sorted_routes(routes: impl Iterator<Item = &'static Route>) -> Self {
+            let mut routes = routes.collect::<Vec<_>>();
+            routes.sort_by_cached_key(|route| {
+                let segments = route
+                    .segments()
+                    .map(|segment| (segment_rank(segment), segment))
+                    .collect::<Vec<_>>();
+                (segments, route.method.to_string())
+            });
+            Router::with_routes(routes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::Method;
+    use crate::prelude::*;
+    #[cfg(feature = "route-registry")]
+    use crate::routing::Route;
+    #[cfg(feature = "route-registry")]
+    use crate::test::{ResponseExt, TestClient};
+
+    #[get("users", internal)]
+    async fn list_users() -> &'static str {
+        "list"
+    }
+    #[post("/users/", internal)]
+    async fn create_user() -> &'static str {
+        "create"
+    }
+    #[get("users/<id>", internal)]
+    async fn show_user(req: &mut Request) -> String {
+        format!("show {}", req.param::<String>("id").unwrap())
+    }
+    #[get("users/new", internal)]
+    async fn new_user() -> &'static str {
+        "new"
+    }
+
+    #[test]
+    fn test_route_tree() {
+        let routes = [&list_users::ROUTE, &create_user::ROUTE, &show_user::ROUTE];
+        let router = Router::with_routes(routes);
+        let expected = Router::new().push(
+            Router::with_path("users")
+                .push(Router::with_path("<id>").get(show_user))
+                .get(list_users)
+                .post(create_user),
+        );
+        assert_eq!(format!("{:?}", router), format!("{:?}", expected));
+        assert_eq!(show_user::ROUTE.method(), Method::GET);
+        assert_eq!(show_user::ROUTE.path(), "users/<id>");
+        assert_eq!(show_user::ROUTE.module(), module_path!());
+    }
+
+    #[cfg(feature = "test")]
+    mod posts {
+        use crate::prelude::*;
+
+        #[get(r"users/<id:/\d+/>/posts", internal)]
+        pub(super) async fn list_posts(req: &mut Request) -> String {
+            format!("posts {}", req.param::<String>("id").unwrap())
+        }
+    }
+
+    #[cfg(feature = "test")]
+    #[tokio::test]
+    async fn test_regex_param_route() {
+        use crate::test::{ResponseExt, TestClient};
+        use posts::list_posts;
+
+        let router = Router::with_routes([&list_posts::ROUTE]);
+        let expected = Router::new().push(
+            Router::with_path("users")
+                .push(Router::with_path(r"<id:/\d+/>").push(Router::with_path("posts").get(list_posts))),
+        );
+        assert_eq!(format!("{:?}", router), format!("{:?}", expected));
+
+        let service = Service::new(router);
+        let res = TestClient::get("http://127.0.0.1:7979/users/7/posts")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(res, "posts 7");
+        let res = TestClient::get("http://127.0.0.1:7979/users/new/posts")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[cfg(feature = "route-registry")]
+    mod files {
+        use crate::prelude::*;
+
+        #[get("files/<**rest>", internal)]
+        pub(super) async fn show_rest(req: &mut Request) -> String {
+            format!("rest {}", req.param::<String>("**rest").unwrap())
+        }
+        #[get("files/<id>", internal)]
+        pub(super) async fn show_file(req: &mut Request) -> String {
+            format!("file {}", req.param::<String>("id").unwrap())
+        }
+        #[get("files/readme", internal)]
+        pub(super) async fn readme() -> &'static str {
+            "readme"
+        }
+    }
+
+    #[cfg(feature = "route-registry")]
+    #[tokio::test]
+    async fn test_sorted_routes() {
+        use files::{readme, show_file, show_rest};
+
+        static ROUTES: [&Route; 3] = [&show_rest::ROUTE, &show_file::ROUTE, &readme::ROUTE];
+        let router = Router::with_sorted_routes(ROUTES.into_iter());
+        let expected = Router::new().push(
+            Router::with_path("files")
+                .push(Router::with_path("readme").get(readme))
+                .push(Router::with_path("<id>").get(show_file))
+                .push(Router::with_path("<**rest>").get(show_rest)),
+        );
+        assert_eq!(format!("{:?}", router), format!("{:?}", expected));
+
+        let service = Service::new(router);
+        for (path, content) in [("readme", "readme"), ("7", "file 7"), ("a/b", "rest a/b")] {
+            let url = format!("http://127.0.0.1:7979/files/{}", path);
+            let res = TestClient::get(url).send(&service).await.take_string().await.unwrap();
+            assert_eq!(res, content);
+        }
+    }
+
+    #[cfg(feature = "route-registry")]
+    #[tokio::test]
+    async fn test_module_routes() {
+        let routes = super::registered_routes()
+            .filter(|route| route.module() == module_path!())
+            .collect::<Vec<&Route>>();
+        assert_eq!(routes.len(), 4);
+        let service = Service::new(Router::with_module_routes("salvo_core::routing::route"));
+
+        async fn access(service: &Service, method: &str, path: &str) -> String {
+            let url = format!("http://127.0.0.1:7979/{}", path);
+            let req = if method == "POST" {
+                TestClient::post(url)
+            } else {
+                TestClient::get(url)
+            };
+            req.send(service).await.take_string().await.unwrap()
+        }
+        assert_eq!(access(&service, "GET", "users").await, "list");
+        assert_eq!(access(&service, "POST", "users").await, "create");
+        assert_eq!(access(&service, "GET", "users/new").await, "new");
+        assert_eq!(access(&service, "GET", "users/7").await, "show 7");
+        assert!(Router::with_module_routes("salvo_core::routing::rou")
+            .routers()
+            .is_empty());
+    }
+}
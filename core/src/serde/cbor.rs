@@ -0,0 +1,159 @@
+use ciborium::Value;
+use serde::de::value::{Error as ValError, MapDeserializer, SeqDeserializer};
+use serde::de::{
+    DeserializeSeed, Deserializer, EnumAccess, Error as DeError, IntoDeserializer, Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+/// Deserializer over a decoded CBOR value, `ciborium` doesn't expose one that accepts a seed.
+#[derive(Debug)]
+pub(crate) struct CborValue(pub(crate) Value);
+
+impl<'de> IntoDeserializer<'de, ValError> for CborValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for CborValue {
+    type Error = ValError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Integer(value) => {
+                let value = i128::from(value);
+                if let Ok(value) = u64::try_from(value) {
+                    visitor.visit_u64(value)
+                } else if let Ok(value) = i64::try_from(value) {
+                    visitor.visit_i64(value)
+                } else {
+                    visitor.visit_i128(value)
+                }
+            }
+            Value::Bytes(value) => visitor.visit_byte_buf(value),
+            Value::Float(value) => visitor.visit_f64(value),
+            Value::Text(value) => visitor.visit_string(value),
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::Null => visitor.visit_unit(),
+            Value::Tag(_, value) => CborValue(*value).deserialize_any(visitor),
+            Value::Array(values) => {
+                let mut seq = SeqDeserializer::new(values.into_iter().map(CborValue));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter().map(|(k, v)| (CborValue(k), CborValue(v))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            _ => Err(DeError::custom("unsupported cbor value")),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(CborValue(value)),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Text(variant) => visitor.visit_enum(CborEnumAccess(variant, None)),
+            Value::Map(mut entries) if entries.len() == 1 => match entries.pop() {
+                Some((Value::Text(variant), value)) => visitor.visit_enum(CborEnumAccess(variant, Some(value))),
+                _ => Err(DeError::custom("expected enum variant name")),
+            },
+            _ => Err(DeError::invalid_type(Unexpected::Other("cbor value"), &"enum")),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple_struct map seq tuple
+        struct identifier ignored_any
+    }
+}
+
+struct CborEnumAccess(String, Option<Value>);
+
+impl<'de> EnumAccess<'de> for CborEnumAccess {
+    type Error = ValError;
+    type Variant = CborVariantAccess;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.0.into_deserializer())?;
+        Ok((variant, CborVariantAccess(self.1)))
+    }
+}
+
+struct CborVariantAccess(Option<Value>);
+
+impl<'de> VariantAccess<'de> for CborVariantAccess {
+    type Error = ValError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.0 {
+            None | Some(Value::Null) => Ok(()),
+            Some(_) => Err(DeError::custom("expected unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0 {
+            Some(value) => seed.deserialize(CborValue(value)),
+            None => Err(DeError::custom("expected newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Some(value) => CborValue(value).deserialize_seq(visitor),
+            None => Err(DeError::custom("expected tuple variant")),
+        }
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Some(value) => CborValue(value).deserialize_map(visitor),
+            None => Err(DeError::custom("expected struct variant")),
+        }
+    }
+}
@@ -8,8 +8,16 @@ use serde::de::{
 };
 use serde::forward_to_deserialize_any;
 
+cfg_feature! {
+    #![feature = "cbor"]
+    mod cbor;
+    pub(crate) use cbor::CborValue;
+}
+mod nested;
+pub use nested::NestedConfig;
+pub(crate) use nested::{from_str_nested_map, Node};
 mod request;
-pub(crate) use request::from_request;
+pub(crate) use request::{from_request, is_absent};
 
 pub(crate) fn from_str_map<'de, I, T, K, V>(input: I) -> Result<T, ValError>
 where
@@ -0,0 +1,459 @@
+use std::borrow::Cow;
+use std::mem;
+
+use serde::de::value::{Error as ValError, MapDeserializer, SeqDeserializer};
+use serde::de::{Deserialize, Deserializer, Error as DeError, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::{CowValue, VecValue};
+
+/// Limits for decoding bracket and dot notation keys, like `filter[tags][]=a` or `sort[0].field=name`, into nested
+/// maps, sequences and structs.
+///
+/// Requests exceeding these limits fail to deserialize.
+#[derive(Clone, Copy, Debug)]
+pub struct NestedConfig {
+    max_depth: usize,
+    max_params: usize,
+}
+impl Default for NestedConfig {
+    /// Create a new `NestedConfig` allowing keys nested 5 levels deep and 1000 parameters.
+    #[inline]
+    fn default() -> Self {
+        NestedConfig {
+            max_depth: 5,
+            max_params: 1000,
+        }
+    }
+}
+impl NestedConfig {
+    /// Create a new `NestedConfig` with default limits.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Get the max nesting depth of keys, `a[b][c]` is 2 levels deep.
+    #[inline]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+    /// Set the max nesting depth of keys and returns a new `NestedConfig`.
+    #[inline]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// Get the max number of parameters.
+    #[inline]
+    pub fn max_params(&self) -> usize {
+        self.max_params
+    }
+    /// Set the max number of parameters and returns a new `NestedConfig`.
+    #[inline]
+    pub fn with_max_params(mut self, max_params: usize) -> Self {
+        self.max_params = max_params;
+        self
+    }
+}
+
+pub(crate) fn from_str_nested_map<'de, I, T, K, V>(input: I, config: &NestedConfig) -> Result<T, ValError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    T: Deserialize<'de>,
+    K: Into<Cow<'de, str>>,
+    V: Into<Cow<'de, str>>,
+{
+    T::deserialize(Node::from_pairs(input, config)?)
+}
+
+/// Split `key` into its segments, `None` is the empty `[]` segment appending to a sequence.
+///
+/// Malformed keys like `a[b` are not nested.
+fn split_key(key: &str) -> Vec<Option<&str>> {
+    let end = key.find(['[', '.']).unwrap_or(key.len());
+    if end == 0 {
+        return vec![Some(key)];
+    }
+    let mut segments = vec![Some(&key[..end])];
+    let mut rest = &key[end..];
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            match inner.find(']') {
+                Some(close) => {
+                    segments.push(Some(&inner[..close]).filter(|segment| !segment.is_empty()));
+                    rest = &inner[close + 1..];
+                }
+                None => return vec![Some(key)],
+            }
+        } else if let Some(inner) = rest.strip_prefix('.') {
+            let end = inner.find(['[', '.']).unwrap_or(inner.len());
+            if end == 0 {
+                return vec![Some(key)];
+            }
+            segments.push(Some(&inner[..end]));
+            rest = &inner[end..];
+        } else {
+            return vec![Some(key)];
+        }
+    }
+    segments
+}
+
+#[derive(Debug)]
+pub(crate) enum Node<'de> {
+    Leaf(Vec<Cow<'de, str>>),
+    Seq(Vec<Node<'de>>),
+    Map(Vec<(Cow<'de, str>, Node<'de>)>),
+}
+
+impl<'de> Node<'de> {
+    pub(crate) fn from_pairs<I, K, V>(input: I, config: &NestedConfig) -> Result<Self, ValError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'de, str>>,
+        V: Into<Cow<'de, str>>,
+    {
+        let mut root = Node::Map(vec![]);
+        for (count, (key, value)) in input.into_iter().enumerate() {
+            if count >= config.max_params {
+                return Err(DeError::custom(format!(
+                    "number of parameters exceeds the limit {}",
+                    config.max_params
+                )));
+            }
+            let key = key.into();
+            let inserted = match &key {
+                Cow::Borrowed(key) => {
+                    let segments = split_key(key);
+                    check_depth(key, segments.len(), config)?;
+                    root.insert(segments.into_iter().map(|s| s.map(Cow::Borrowed)), value.into())
+                }
+                Cow::Owned(key) => {
+                    let segments = split_key(key);
+                    check_depth(key, segments.len(), config)?;
+                    root.insert(
+                        segments.into_iter().map(|s| s.map(|s| Cow::Owned(s.to_owned()))),
+                        value.into(),
+                    )
+                }
+            };
+            if !inserted {
+                return Err(DeError::custom(format!("key `{}` conflicts with other keys", key)));
+            }
+        }
+        Ok(root)
+    }
+
+    /// Decode the pairs whose key is `name` or is nested in `name`, returns `None` if there is no such pair.
+    ///
+    /// Malformed keys like `name[a` or `name.` are not nested in `name`, so their pairs are skipped.
+    pub(crate) fn from_field_pairs<I>(input: I, name: &str, config: &NestedConfig) -> Option<Result<Self, ValError>>
+    where
+        I: IntoIterator<Item = (&'de str, &'de str)>,
+    {
+        let mut pairs = input
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(name) && split_key(key).first() == Some(&Some(name)))
+            .peekable();
+        pairs.peek()?;
+        let node = Node::from_pairs(pairs, config).and_then(|root| match root {
+            Node::Map(entries) => entries
+                .into_iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, node)| node)
+                .ok_or_else(|| DeError::custom(format!("field `{}` is not found", name))),
+            _ => Err(DeError::custom("nested pairs must be decoded to a map")),
+        });
+        Some(node)
+    }
+
+    fn insert<I>(&mut self, mut segments: I, value: Cow<'de, str>) -> bool
+    where
+        I: Iterator<Item = Option<Cow<'de, str>>>,
+    {
+        match segments.next() {
+            None => match self {
+                Node::Leaf(values) => values.push(value),
+                Node::Seq(items) => items.push(Node::Leaf(vec![value])),
+                Node::Map(_) => return false,
+            },
+            Some(None) => {
+                if let Node::Leaf(values) = self {
+                    *self = Node::Seq(mem::take(values).into_iter().map(|v| Node::Leaf(vec![v])).collect());
+                }
+                match self {
+                    Node::Seq(items) => {
+                        let mut item = Node::Leaf(vec![]);
+                        if !item.insert(segments, value) {
+                            return false;
+                        }
+                        items.push(item);
+                    }
+                    _ => return false,
+                }
+            }
+            Some(Some(key)) => {
+                if matches!(self, Node::Leaf(values) if values.is_empty()) {
+                    *self = Node::Map(vec![]);
+                }
+                match self {
+                    Node::Map(entries) => {
+                        let index = match entries.iter().position(|(k, _)| *k == key) {
+                            Some(index) => index,
+                            None => {
+                                entries.push((key, Node::Leaf(vec![])));
+                                entries.len() - 1
+                            }
+                        };
+                        return entries[index].1.insert(segments, value);
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Items of a sequence, a map is a sequence if all its keys are indexes like `sort[0]`.
+    fn into_items(self) -> Result<Vec<Node<'de>>, ValError> {
+        match self {
+            Node::Seq(items) => Ok(items),
+            Node::Map(entries) => {
+                let mut items = entries
+                    .into_iter()
+                    .map(|(key, node)| match key.parse::<usize>() {
+                        Ok(index) => Ok((index, node)),
+                        Err(_) => Err(DeError::custom(format!("expected sequence index, found `{}`", key))),
+                    })
+                    .collect::<Result<Vec<_>, ValError>>()?;
+                items.sort_by_key(|(index, _)| *index);
+                Ok(items.into_iter().map(|(_, node)| node).collect())
+            }
+            Node::Leaf(_) => unreachable!("leaf values are deserialized by `VecValue`"),
+        }
+    }
+}
+
+fn check_depth(key: &str, segments: usize, config: &NestedConfig) -> Result<(), ValError> {
+    if segments > config.max_depth + 1 {
+        Err(DeError::custom(format!(
+            "key `{}` exceeds the nesting depth limit {}",
+            key, config.max_depth
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+macro_rules! forward_leaf_value {
+    ($($method:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where V: Visitor<'de>
+            {
+                match self {
+                    Node::Leaf(values) => VecValue(values.into_iter().map(CowValue)).$method(visitor),
+                    node => node.deserialize_any(visitor),
+                }
+            }
+        )*
+    }
+}
+
+impl<'de> IntoDeserializer<'de> for Node<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Node<'de> {
+    type Error = ValError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Leaf(values) => VecValue(values.into_iter().map(CowValue)).deserialize_any(visitor),
+            Node::Seq(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            Node::Map(entries) => visitor.visit_map(MapDeserializer::new(
+                entries.into_iter().map(|(key, node)| (CowValue(key), node)),
+            )),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Leaf(values) => VecValue(values.into_iter().map(CowValue)).deserialize_enum(name, variants, visitor),
+            _ => Err(DeError::custom("expected unit variant")),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Leaf(values) => VecValue(values.into_iter().map(CowValue)).deserialize_seq(visitor),
+            node => visitor.visit_seq(SeqDeserializer::new(node.into_items()?.into_iter())),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char
+        str
+        string
+        unit
+        bytes
+        byte_buf
+        unit_struct
+        struct
+        identifier
+        ignored_any
+        map
+    }
+
+    forward_leaf_value! {
+        deserialize_bool,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_f32,
+        deserialize_f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{from_str_nested_map, NestedConfig, Node};
+
+    #[test]
+    fn test_de_nested_map() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Filter<'a> {
+            status: &'a str,
+            tags: Vec<String>,
+        }
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Sort {
+            field: String,
+            desc: Option<bool>,
+        }
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Query<'a> {
+            #[serde(borrow)]
+            filter: Filter<'a>,
+            sort: Vec<Sort>,
+            page: u32,
+            ids: Vec<i64>,
+        }
+
+        let pairs = [
+            ("filter[status]", "open"),
+            ("filter[tags][]", "a"),
+            ("filter[tags][]", "b"),
+            ("sort[1].field", "age"),
+            ("sort[1][desc]", "true"),
+            ("sort[0][field]", "name"),
+            ("page", "2"),
+            ("ids", "7"),
+            ("ids", "8"),
+        ];
+        let query: Query = from_str_nested_map(pairs, &NestedConfig::default()).unwrap();
+        assert_eq!(
+            query,
+            Query {
+                filter: Filter {
+                    status: "open",
+                    tags: vec!["a".into(), "b".into()],
+                },
+                sort: vec![
+                    Sort {
+                        field: "name".into(),
+                        desc: None,
+                    },
+                    Sort {
+                        field: "age".into(),
+                        desc: Some(true),
+                    },
+                ],
+                page: 2,
+                ids: vec![7, 8],
+            }
+        );
+    }
+
+    #[test]
+    fn test_de_nested_map_limits() {
+        #[derive(Deserialize, Debug)]
+        struct Data {
+            #[allow(dead_code)]
+            a: serde_json::Value,
+        }
+
+        let config = NestedConfig::new().with_max_depth(2).with_max_params(2);
+        assert!(from_str_nested_map::<_, Data, _, _>([("a[b][c]", "1")], &config).is_ok());
+        let err = from_str_nested_map::<_, Data, _, _>([("a[b][c][d]", "1")], &config).unwrap_err();
+        assert!(err.to_string().contains("nesting depth limit 2"));
+        let err =
+            from_str_nested_map::<_, Data, _, _>([("a[]", "1"), ("a[]", "2"), ("a[]", "3")], &config).unwrap_err();
+        assert!(err.to_string().contains("number of parameters exceeds the limit 2"));
+        let err = from_str_nested_map::<_, Data, _, _>([("a", "1"), ("a[b]", "2")], &config).unwrap_err();
+        assert!(err.to_string().contains("key `a[b]` conflicts"));
+    }
+
+    #[test]
+    fn test_de_nested_field_malformed_keys() {
+        let config = NestedConfig::default();
+        assert!(Node::from_field_pairs([("filter[status", "open")], "filter", &config).is_none());
+        assert!(Node::from_field_pairs([("filter.", "open")], "filter", &config).is_none());
+        assert!(Node::from_field_pairs([("filters[status]", "open")], "filter", &config).is_none());
+
+        let pairs = [("filter[status", "open"), ("filter.", "x"), ("filter[kind]", "bug")];
+        let node = Node::from_field_pairs(pairs, "filter", &config).unwrap().unwrap();
+        assert_eq!(format!("{:?}", node), r#"Map([("kind", Leaf(["bug"]))])"#);
+    }
+}
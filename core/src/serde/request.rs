@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::iter::Iterator;
+#[cfg(feature = "cbor")]
+use std::sync::Arc;
 
 use multimap::MultiMap;
 use serde::de::value::Error as ValError;
@@ -11,10 +13,18 @@ use serde_json::value::RawValue;
 use crate::extract::metadata::{Source, SourceFormat, SourceFrom};
 use crate::extract::Metadata;
 use crate::http::form::FormData;
+#[cfg(feature = "cbor")]
+use crate::http::request::is_cbor;
+#[cfg(feature = "msgpack")]
+use crate::http::request::is_msgpack;
+#[cfg(feature = "xml")]
+use crate::http::request::is_xml;
 use crate::http::ParseError;
 use crate::Request;
 
-use super::{CowValue, VecValue};
+#[cfg(feature = "cbor")]
+use super::CborValue;
+use super::{CowValue, NestedConfig, Node, VecValue};
 
 pub(crate) async fn from_request<'de, T>(req: &'de mut Request, metadata: &'de Metadata) -> Result<T, ParseError>
 where
@@ -26,11 +36,26 @@ where
     Ok(T::deserialize(RequestDeserializer::new(req, metadata)?)?)
 }
 
+/// Returns `true` if no field of `metadata` has a value in the request.
+pub(crate) fn is_absent<'de>(req: &'de mut Request, metadata: &'de Metadata) -> Result<bool, ParseError> {
+    Ok(RequestDeserializer::new(req, metadata)?.is_absent())
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum Payload<'a> {
     FormData(&'a FormData),
     JsonStr(&'a str),
     JsonMap(HashMap<&'a str, &'a RawValue>),
+    #[cfg(feature = "msgpack")]
+    MsgPack(HashMap<&'a str, rmpv::ValueRef<'a>>),
+    #[cfg(feature = "cbor")]
+    Cbor(Arc<HashMap<String, ciborium::Value>>),
+    /// Source slices of the root's child elements, grouped by element name.
+    #[cfg(feature = "xml")]
+    Xml(HashMap<&'a str, Vec<&'a str>>),
+    /// A MessagePack, CBOR or XML body that failed to decode, the error is raised when a field is read from it.
+    #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+    Malformed(SourceFormat, String),
 }
 
 #[derive(Debug)]
@@ -44,6 +69,10 @@ pub(crate) struct RequestDeserializer<'de> {
     field_source: Option<&'de Source>,
     field_str_value: Option<&'de str>,
     field_vec_value: Option<Vec<CowValue<'de>>>,
+    field_nested_value: Option<Result<Node<'de>, ValError>>,
+    field_error: Option<String>,
+    #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+    field_body_key: Option<Cow<'static, str>>,
 }
 
 impl<'de> RequestDeserializer<'de> {
@@ -68,6 +97,36 @@ impl<'de> RequestDeserializer<'de> {
                 }
                 _ => {}
             }
+            #[cfg(feature = "msgpack")]
+            if is_msgpack(&ctype) {
+                if let Some(data) = request.payload.get() {
+                    payload = Some(match rmp_serde::from_slice(data) {
+                        Ok(map) => Payload::MsgPack(map),
+                        Err(e) => Payload::Malformed(SourceFormat::MsgPack, ParseError::MsgPack(e).to_string()),
+                    });
+                }
+            }
+            #[cfg(feature = "cbor")]
+            if is_cbor(&ctype) {
+                if let Some(data) = request.payload.get() {
+                    payload = Some(match ciborium::from_reader(&data[..]) {
+                        Ok(map) => Payload::Cbor(Arc::new(map)),
+                        Err(e) => Payload::Malformed(SourceFormat::Cbor, ParseError::Cbor(e).to_string()),
+                    });
+                }
+            }
+            #[cfg(feature = "xml")]
+            if is_xml(&ctype) {
+                if let Some(data) = request.payload.get() {
+                    let elements = std::str::from_utf8(data)
+                        .map_err(ParseError::from)
+                        .and_then(|data| xml_child_elements(data).map_err(|e| ParseError::Xml(e.into())));
+                    payload = Some(match elements {
+                        Ok(elements) => Payload::Xml(elements),
+                        Err(e) => Payload::Malformed(SourceFormat::Xml, e.to_string()),
+                    });
+                }
+            }
         }
         Ok(RequestDeserializer {
             params: request.params(),
@@ -83,6 +142,10 @@ impl<'de> RequestDeserializer<'de> {
             field_source: None,
             field_str_value: None,
             field_vec_value: None,
+            field_nested_value: None,
+            field_error: None,
+            #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+            field_body_key: None,
         })
     }
     fn deserialize_value<T>(&mut self, seed: T) -> Result<T::Value, ValError>
@@ -93,6 +156,33 @@ impl<'de> RequestDeserializer<'de> {
             .field_source
             .take()
             .expect("MapAccess::next_value called before next_key");
+        if let Some(error) = self.field_error.take() {
+            return Err(ValError::custom(error));
+        }
+        #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+        if let Some(key) = self.field_body_key.take() {
+            match &self.payload {
+                #[cfg(feature = "msgpack")]
+                Some(Payload::MsgPack(map)) => {
+                    let value = map.get(&*key).expect("Field must exist in body").clone();
+                    return seed.deserialize(value).map_err(ValError::custom);
+                }
+                #[cfg(feature = "cbor")]
+                Some(Payload::Cbor(map)) => {
+                    let value = map.get(&*key).expect("Field must exist in body").clone();
+                    return seed.deserialize(CborValue(value));
+                }
+                #[cfg(feature = "xml")]
+                Some(Payload::Xml(map)) => {
+                    let elements = map.get(&*key).expect("Field must exist in body");
+                    let document = format!("<root>{}</root>", elements.concat());
+                    let mut value = quick_xml::de::Deserializer::from_reader(document.as_bytes());
+                    return de::Deserializer::deserialize_struct(&mut value, "root", &[], XmlElementVisitor(seed))
+                        .map_err(ValError::custom);
+                }
+                _ => {}
+            }
+        }
         if source.from == SourceFrom::Body && source.format == SourceFormat::Json {
             // Panic because this indicates a bug in the program rather than an expected failure.
             let value = self
@@ -108,18 +198,9 @@ impl<'de> RequestDeserializer<'de> {
                 .fields
                 .get(self.field_index as usize)
                 .expect("Field must exist");
-            let metadata = field.metadata.expect("Field's metadata must exist");
-            seed.deserialize(RequestDeserializer {
-                params: self.params,
-                queries: self.queries,
-                headers: self.headers.clone(),
-                payload: self.payload.clone(),
-                metadata,
-                field_index: -1,
-                field_source: None,
-                field_str_value: None,
-                field_vec_value: None,
-            })
+            seed.deserialize(self.nested(field.metadata.expect("Field's metadata must exist")))
+        } else if let Some(value) = self.field_nested_value.take() {
+            seed.deserialize(value?)
         } else if let Some(value) = self.field_str_value.take() {
             seed.deserialize(CowValue(value.into()))
         } else if let Some(value) = self.field_vec_value.take() {
@@ -128,6 +209,41 @@ impl<'de> RequestDeserializer<'de> {
             Err(ValError::custom("parse value error"))
         }
     }
+    fn nested(&self, metadata: &'de Metadata) -> RequestDeserializer<'de> {
+        RequestDeserializer {
+            params: self.params,
+            queries: self.queries,
+            headers: self.headers.clone(),
+            payload: self.payload.clone(),
+            metadata,
+            field_index: -1,
+            field_source: None,
+            field_str_value: None,
+            field_vec_value: None,
+            field_nested_value: None,
+            field_error: None,
+            #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+            field_body_key: None,
+        }
+    }
+    fn is_absent(&mut self) -> bool {
+        while self.field_index < self.metadata.fields.len() as isize - 1 {
+            if self.next().is_none() {
+                continue;
+            }
+            match self.field_source.take() {
+                Some(source) if source.from == SourceFrom::Request => {
+                    let field = &self.metadata.fields[self.field_index as usize];
+                    let metadata = field.metadata.expect("Field's metadata must exist");
+                    if !self.nested(metadata).is_absent() {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
     fn next(&mut self) -> Option<Cow<'_, str>> {
         if self.field_index < self.metadata.fields.len() as isize - 1 {
             self.field_index += 1;
@@ -142,7 +258,13 @@ impl<'de> RequestDeserializer<'de> {
             };
             self.field_str_value = None;
             self.field_vec_value = None;
-            let field_name: Cow<'_, str> = if let Some(rename_all) = self.metadata.rename_all {
+            self.field_nested_value = None;
+            self.field_error = None;
+            #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+            {
+                self.field_body_key = None;
+            }
+            let field_name: Cow<'static, str> = if let Some(rename_all) = self.metadata.rename_all {
                 if let Some(rename) = field.rename {
                     Cow::from(rename)
                 } else {
@@ -178,6 +300,17 @@ impl<'de> RequestDeserializer<'de> {
                             return Some(Cow::from(field.name));
                         }
                     }
+                    SourceFrom::Query if source.format == SourceFormat::Nested => {
+                        let queries = self
+                            .queries
+                            .iter_all()
+                            .flat_map(|(key, values)| values.iter().map(move |value| (&**key, &**value)));
+                        if let Some(value) = nested_field_value(queries, &field_name, &field.aliases) {
+                            self.field_nested_value = Some(value);
+                            self.field_source = Some(source);
+                            return Some(Cow::from(field.name));
+                        }
+                    }
                     SourceFrom::Query => {
                         let mut value = self.queries.get_vec(field_name.as_ref());
                         if value.is_none() {
@@ -255,6 +388,8 @@ impl<'de> RequestDeserializer<'de> {
                                         self.field_source = Some(source);
                                         return Some(Cow::from(field.name));
                                     }
+                                    #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+                                    _ => return None,
                                 }
                             } else {
                                 return None;
@@ -282,9 +417,88 @@ impl<'de> RequestDeserializer<'de> {
                                 return None;
                             }
                         }
-                        _ => {
+                        SourceFormat::Nested => {
+                            if let Some(Payload::FormData(form_data)) = self.payload {
+                                let fields = form_data
+                                    .fields
+                                    .iter_all()
+                                    .flat_map(|(key, values)| values.iter().map(move |value| (&**key, &**value)));
+                                if let Some(value) = nested_field_value(fields, &field_name, &field.aliases) {
+                                    self.field_nested_value = Some(value);
+                                    self.field_source = Some(source);
+                                    return Some(Cow::from(field.name));
+                                }
+                            }
+                        }
+                        #[cfg(feature = "msgpack")]
+                        SourceFormat::MsgPack => match &self.payload {
+                            Some(Payload::MsgPack(map)) => {
+                                if let Some(key) =
+                                    body_field_key(field_name.clone(), &field.aliases, |key| map.contains_key(key))
+                                {
+                                    self.field_body_key = Some(key);
+                                    self.field_source = Some(source);
+                                    return Some(Cow::from(field.name));
+                                }
+                            }
+                            Some(Payload::Malformed(SourceFormat::MsgPack, error)) => {
+                                self.field_error = Some(error.clone());
+                                self.field_source = Some(source);
+                                return Some(Cow::from(field.name));
+                            }
+                            _ => {}
+                        },
+                        #[cfg(feature = "cbor")]
+                        SourceFormat::Cbor => match &self.payload {
+                            Some(Payload::Cbor(map)) => {
+                                if let Some(key) =
+                                    body_field_key(field_name.clone(), &field.aliases, |key| map.contains_key(key))
+                                {
+                                    self.field_body_key = Some(key);
+                                    self.field_source = Some(source);
+                                    return Some(Cow::from(field.name));
+                                }
+                            }
+                            Some(Payload::Malformed(SourceFormat::Cbor, error)) => {
+                                self.field_error = Some(error.clone());
+                                self.field_source = Some(source);
+                                return Some(Cow::from(field.name));
+                            }
+                            _ => {}
+                        },
+                        #[cfg(feature = "xml")]
+                        SourceFormat::Xml => match &self.payload {
+                            Some(Payload::Xml(map)) => {
+                                if let Some(key) =
+                                    body_field_key(field_name.clone(), &field.aliases, |key| map.contains_key(key))
+                                {
+                                    self.field_body_key = Some(key);
+                                    self.field_source = Some(source);
+                                    return Some(Cow::from(field.name));
+                                }
+                            }
+                            Some(Payload::Malformed(SourceFormat::Xml, error)) => {
+                                self.field_error = Some(error.clone());
+                                self.field_source = Some(source);
+                                return Some(Cow::from(field.name));
+                            }
+                            _ => {}
+                        },
+                        SourceFormat::Request => {
                             panic!("Unsupported source format: {:?}", source.format);
                         }
+                        // Formats whose feature is disabled can't be read from the body.
+                        #[allow(unreachable_patterns)]
+                        format => {
+                            let feature = match format {
+                                SourceFormat::MsgPack => "msgpack",
+                                SourceFormat::Cbor => "cbor",
+                                _ => "xml",
+                            };
+                            self.field_error = Some(format!("feature `{}` is not enabled", feature));
+                            self.field_source = Some(source);
+                            return Some(Cow::from(field.name));
+                        }
                     },
                 }
             }
@@ -293,6 +507,97 @@ impl<'de> RequestDeserializer<'de> {
     }
 }
 
+/// Returns the name or alias under which the field is stored in a decoded body map.
+#[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+fn body_field_key<F>(name: Cow<'static, str>, aliases: &[&'static str], contains: F) -> Option<Cow<'static, str>>
+where
+    F: Fn(&str) -> bool,
+{
+    if contains(&name) {
+        Some(name)
+    } else {
+        aliases
+            .iter()
+            .find(|alias| contains(alias))
+            .map(|alias| Cow::Borrowed(*alias))
+    }
+}
+
+/// Splits an XML document into the source slices of its root's child elements, grouped by element name.
+#[cfg(feature = "xml")]
+fn xml_child_elements(data: &str) -> Result<HashMap<&str, Vec<&str>>, quick_xml::Error> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(data);
+    let mut elements: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    loop {
+        let position = reader.buffer_position();
+        let element = match reader.read_event()? {
+            Event::Start(_) => {
+                depth += 1;
+                if depth == 2 {
+                    start = position;
+                }
+                None
+            }
+            Event::End(_) => {
+                depth = depth.saturating_sub(1);
+                (depth == 1).then(|| &data[start..reader.buffer_position()])
+            }
+            Event::Empty(_) => (depth == 1).then(|| &data[position..reader.buffer_position()]),
+            Event::Eof => break,
+            _ => None,
+        };
+        if let Some(element) = element {
+            let name_end = element[1..]
+                .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .map_or(element.len(), |end| end + 1);
+            elements.entry(&element[1..name_end]).or_default().push(element);
+        }
+    }
+    Ok(elements)
+}
+
+/// Visits the single element wrapped in a synthetic root and deserializes it with the seed.
+#[cfg(feature = "xml")]
+struct XmlElementVisitor<T>(T);
+
+#[cfg(feature = "xml")]
+impl<'de, T> de::Visitor<'de> for XmlElementVisitor<T>
+where
+    T: de::DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("an xml element")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        match map.next_key::<de::IgnoredAny>()? {
+            Some(_) => map.next_value_seed(self.0),
+            None => Err(DeError::custom("missing xml element")),
+        }
+    }
+}
+
+fn nested_field_value<'de, I>(pairs: I, name: &str, aliases: &[&'static str]) -> Option<Result<Node<'de>, ValError>>
+where
+    I: Iterator<Item = (&'de str, &'de str)> + Clone,
+{
+    let config = NestedConfig::default();
+    Node::from_field_pairs(pairs.clone(), name, &config).or_else(|| {
+        aliases
+            .iter()
+            .find_map(|alias| Node::from_field_pairs(pairs.clone(), alias, &config))
+    })
+}
+
 impl<'de> de::Deserializer<'de> for RequestDeserializer<'de> {
     type Error = ValError;
 
@@ -570,4 +875,239 @@ mod tests {
             }
         );
     }
+
+    #[tokio::test]
+    async fn test_de_request_with_nested() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Filter {
+            status: String,
+            tags: Vec<String>,
+        }
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct Sort {
+            field: String,
+        }
+        #[derive(Deserialize, Extractible, Eq, PartialEq, Debug)]
+        #[extract(internal, default_source(from = "query"))]
+        struct RequestData {
+            #[extract(source(from = "query", format = "nested"))]
+            filter: Filter,
+            #[extract(source(from = "body", format = "nested"), alias = "order")]
+            sort: Vec<Sort>,
+            page: u32,
+        }
+
+        let mut req =
+            TestClient::post("http://127.0.0.1:7878/test?filter[status]=open&filter[tags][]=a&filter[tags][]=b&page=2")
+                .raw_form("order[1].field=age&order[0].field=name")
+                .build();
+        let data: RequestData = req.extract().await.unwrap();
+        assert_eq!(
+            data,
+            RequestData {
+                filter: Filter {
+                    status: "open".into(),
+                    tags: vec!["a".into(), "b".into()],
+                },
+                sort: vec![Sort { field: "name".into() }, Sort { field: "age".into() }],
+                page: 2,
+            }
+        );
+
+        let mut req = TestClient::post("http://127.0.0.1:7878/test?filter[status=open&filter.=x&page=2")
+            .raw_form("order[0].field=name")
+            .build();
+        assert!(req.extract::<RequestData>().await.is_err());
+
+        let mut req = TestClient::post(
+            "http://127.0.0.1:7878/test?filter[status=x&filter.=x&filter[status]=open&filter[tags][]=a&page=2",
+        )
+        .raw_form("order[0].field=name")
+        .build();
+        let data: RequestData = req.extract().await.unwrap();
+        assert_eq!(data.filter.status, "open");
+        assert_eq!(data.filter.tags, vec!["a".to_owned()]);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_de_request_with_msgpack() {
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        struct User<'a> {
+            id: i64,
+            name: &'a str,
+        }
+        #[derive(Serialize)]
+        struct Body<'a> {
+            user: User<'a>,
+            count: u8,
+        }
+        #[derive(Deserialize, Extractible, Eq, PartialEq, Debug)]
+        #[extract(internal)]
+        struct RequestData<'a> {
+            #[extract(source(from = "param"))]
+            p2: &'a str,
+            #[serde(borrow)]
+            #[extract(source(from = "body", format = "msgpack"))]
+            user: User<'a>,
+            #[extract(source(from = "body", format = "msgpack"), alias = "count")]
+            total: u8,
+            #[extract(source(from = "body", format = "msgpack"))]
+            missing: Option<u8>,
+        }
+
+        let body = Body {
+            user: User { id: 1, name: "chris" },
+            count: 3,
+        };
+        let mut req = TestClient::post("http://127.0.0.1:7878/test/1234/param2v")
+            .insert_header("content-type", "application/msgpack")
+            .bytes(rmp_serde::to_vec_named(&body).unwrap())
+            .build();
+        req.params.insert("p2".into(), "921".into());
+        let data: RequestData = req.extract().await.unwrap();
+        assert_eq!(
+            data,
+            RequestData {
+                p2: "921",
+                user: User { id: 1, name: "chris" },
+                total: 3,
+                missing: None,
+            }
+        );
+
+        let mut req = TestClient::post("http://127.0.0.1:7878/test/1234/param2v")
+            .insert_header("content-type", "application/msgpack")
+            .bytes(rmp_serde::to_vec_named(&(1, 2)).unwrap())
+            .build();
+        req.params.insert("p2".into(), "921".into());
+        assert!(req.extract::<RequestData>().await.is_err());
+
+        // A malformed body only fails the fields read from it.
+        #[derive(Deserialize, Extractible, Eq, PartialEq, Debug)]
+        #[extract(internal)]
+        struct ParamData<'a> {
+            #[extract(source(from = "param"))]
+            p2: &'a str,
+        }
+        let mut req = TestClient::post("http://127.0.0.1:7878/test/1234/param2v")
+            .insert_header("content-type", "application/msgpack")
+            .bytes(vec![0xc1])
+            .build();
+        req.params.insert("p2".into(), "921".into());
+        assert_eq!(req.extract::<ParamData>().await.unwrap(), ParamData { p2: "921" });
+        assert!(req.extract::<RequestData>().await.is_err());
+    }
+    #[cfg(not(feature = "cbor"))]
+    #[tokio::test]
+    async fn test_de_request_with_disabled_format() {
+        #[derive(Deserialize, Extractible, Debug)]
+        #[extract(internal)]
+        struct RequestData {
+            #[extract(source(from = "body", format = "cbor"))]
+            #[allow(dead_code)]
+            count: u8,
+        }
+
+        let mut req = TestClient::post("http://127.0.0.1:7878/test")
+            .insert_header("content-type", "application/cbor")
+            .bytes(vec![0x01])
+            .build();
+        match req.extract::<RequestData>().await {
+            Err(crate::http::ParseError::Deserialize(e)) => assert_eq!(e.to_string(), "feature `cbor` is not enabled"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_de_request_with_cbor() {
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        enum Role {
+            Admin,
+            Guest(String),
+        }
+        #[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+        struct User {
+            id: i64,
+            name: String,
+            roles: Vec<Role>,
+        }
+        #[derive(Serialize)]
+        struct Body {
+            user: User,
+            count: u8,
+        }
+        #[derive(Deserialize, Extractible, Eq, PartialEq, Debug)]
+        #[extract(internal, default_source(from = "body", format = "cbor"))]
+        struct RequestData {
+            #[extract(source(from = "query"))]
+            q1: String,
+            user: User,
+            count: u8,
+        }
+
+        let body = Body {
+            user: User {
+                id: -7,
+                name: "young".into(),
+                roles: vec![Role::Admin, Role::Guest("home".into())],
+            },
+            count: 3,
+        };
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&body, &mut bytes).unwrap();
+        let mut req = TestClient::post("http://127.0.0.1:7878/test?q1=q1v")
+            .insert_header("content-type", "application/cbor")
+            .bytes(bytes)
+            .build();
+        let data: RequestData = req.extract().await.unwrap();
+        assert_eq!(
+            data,
+            RequestData {
+                q1: "q1v".into(),
+                user: User {
+                    id: -7,
+                    name: "young".into(),
+                    roles: vec![Role::Admin, Role::Guest("home".into())],
+                },
+                count: 3,
+            }
+        );
+    }
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn test_de_request_with_xml() {
+        #[derive(Deserialize, Eq, PartialEq, Debug)]
+        struct User {
+            id: i64,
+            name: String,
+        }
+        #[derive(Deserialize, Extractible, Eq, PartialEq, Debug)]
+        #[extract(internal, default_source(from = "body", format = "xml"))]
+        struct RequestData {
+            #[extract(source(from = "query"))]
+            q1: String,
+            user: User,
+            tag: Vec<String>,
+            active: Option<String>,
+        }
+
+        let mut req = TestClient::post("http://127.0.0.1:7878/test?q1=q1v")
+            .insert_header("content-type", "text/xml")
+            .text("<data><user><id>7</id><name>a &amp; b</name></user><tag>x</tag><tag>y</tag></data>")
+            .build();
+        let data: RequestData = req.extract().await.unwrap();
+        assert_eq!(
+            data,
+            RequestData {
+                q1: "q1v".into(),
+                user: User {
+                    id: 7,
+                    name: "a & b".into()
+                },
+                tag: vec!["x".into(), "y".into()],
+                active: None,
+            }
+        );
+    }
 }
@@ -4,12 +4,16 @@ use std::pin::Pin;
 use std::sync::Arc;
 
 use futures_util::future;
+use http::uri::Scheme;
 
 use crate::addr::SocketAddr;
 use crate::catcher::CatcherImpl;
+use crate::http::cookie::CookieJar;
 use crate::http::header::CONTENT_TYPE;
+use crate::http::response::Body;
 use crate::http::{Mime, Request, Response, StatusCode};
 use crate::routing::{FlowCtrl, PathState, Router};
+use crate::state::{LazyState, ScopedState, SingletonProvider, StateProvider};
 use crate::transport::Transport;
 use crate::{Catcher, Depot};
 
@@ -18,6 +22,7 @@ pub struct Service {
     pub(crate) router: Arc<Router>,
     pub(crate) catchers: Arc<Vec<Box<dyn Catcher>>>,
     pub(crate) allowed_media_types: Arc<Vec<Mime>>,
+    pub(crate) states: Arc<Vec<Arc<dyn StateProvider>>>,
 }
 
 impl Service {
@@ -31,6 +36,7 @@ impl Service {
             router: router.into(),
             catchers: Arc::new(vec![]),
             allowed_media_types: Arc::new(vec![]),
+            states: Arc::new(vec![]),
         }
     }
 
@@ -109,6 +115,62 @@ impl Service {
         self.allowed_media_types.clone()
     }
 
+    /// Register a singleton state shared by all requests and returns `Self` for write code chained.
+    ///
+    /// Handlers get it as a [`State<T>`](crate::state::State) parameter, or with
+    /// [`Depot::obtain::<Arc<T>>`](Depot::obtain).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use salvo_core::prelude::*;
+    /// struct Config {
+    ///     name: String,
+    /// }
+    ///
+    /// #[handler]
+    /// async fn hello(config: State<Config>) -> String {
+    ///     format!("Hello {}", config.name)
+    /// }
+    ///
+    /// let service = Service::new(Router::new().get(hello)).with_state(Config { name: "salvo".into() });
+    /// ```
+    #[inline]
+    pub fn with_state<T>(self, value: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.with_state_provider(SingletonProvider(Arc::new(value)))
+    }
+
+    /// Register a [`LazyState`] initialized when it is first used and returns `Self` for write code chained.
+    ///
+    /// Handlers get it as a [`State<T>`](crate::state::State) parameter.
+    #[inline]
+    pub fn with_lazy_state<T>(self, state: LazyState<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.with_state_provider(state)
+    }
+
+    /// Register a [`ScopedState`] created per request and returns `Self` for write code chained.
+    ///
+    /// Handlers get it as a [`Scoped<T>`](crate::state::Scoped) parameter.
+    #[inline]
+    pub fn with_scoped_state<T>(self, state: ScopedState<T>) -> Self
+    where
+        T: Send + 'static,
+    {
+        self.with_state_provider(state)
+    }
+
+    #[inline]
+    fn with_state_provider(mut self, provider: impl StateProvider + 'static) -> Self {
+        Arc::make_mut(&mut self.states).push(Arc::new(provider));
+        self
+    }
+
     /// Handle [`Request`] and returns [`Response`].
     ///
     /// This function is useful for testing application.
@@ -132,13 +194,16 @@ impl Service {
     /// ```
     #[inline]
     pub async fn handle(&self, request: impl Into<Request>) -> Response {
+        let request = request.into();
         let handler = HyperHandler {
-            remote_addr: None,
+            remote_addr: request.remote_addr().cloned(),
+            scheme: request.scheme().clone(),
             router: self.router.clone(),
             catchers: self.catchers.clone(),
             allowed_media_types: self.allowed_media_types.clone(),
+            states: self.states.clone(),
         };
-        handler.handle(request.into()).await
+        handler.handle(request).await
     }
 }
 impl<'t, T> hyper::service::Service<&'t T> for Service
@@ -159,11 +224,14 @@ where
     #[inline]
     fn call(&mut self, target: &T) -> Self::Future {
         let remote_addr = target.remote_addr();
+        let scheme = target.scheme();
         future::ok(HyperHandler {
             remote_addr,
+            scheme,
             router: self.router.clone(),
             catchers: self.catchers.clone(),
             allowed_media_types: self.allowed_media_types.clone(),
+            states: self.states.clone(),
         })
     }
 }
@@ -181,17 +249,24 @@ where
 #[doc(hidden)]
 pub struct HyperHandler {
     pub(crate) remote_addr: Option<SocketAddr>,
+    pub(crate) scheme: Scheme,
     pub(crate) router: Arc<Router>,
     pub(crate) catchers: Arc<Vec<Box<dyn Catcher>>>,
     pub(crate) allowed_media_types: Arc<Vec<Mime>>,
+    pub(crate) states: Arc<Vec<Arc<dyn StateProvider>>>,
 }
 impl HyperHandler {
     pub fn handle(&self, mut req: Request) -> impl Future<Output = Response> {
         let catchers = self.catchers.clone();
         let allowed_media_types = self.allowed_media_types.clone();
+        let states = self.states.clone();
         req.remote_addr = self.remote_addr.clone();
+        req.scheme = self.scheme.clone();
         let mut res = Response::new();
         let mut depot = Depot::new();
+        for state in states.iter() {
+            state.attach(&mut depot);
+        }
         let mut path_state = PathState::new(req.uri().path());
         res.cookies = req.cookies().clone();
         let router = self.router.clone();
@@ -214,7 +289,7 @@ impl HyperHandler {
             }
 
             let status = res.status_code().unwrap();
-            let has_error = status.is_client_error() || status.is_server_error();
+            let mut has_error = status.is_client_error() || status.is_server_error();
             if let Some(value) = res.headers().get(CONTENT_TYPE) {
                 let mut is_allowed = false;
                 if let Ok(value) = value.to_str() {
@@ -243,6 +318,19 @@ impl HyperHandler {
                     "Http response content type header not set"
                 );
             }
+
+            // Finalize scoped state once the status is settled, a failure discards the handler's response.
+            for state in states.iter() {
+                if let Err(e) = state.finalize(&mut depot, res.status_code().unwrap()).await {
+                    tracing::error!(error = ?e, "finalize state failed");
+                    res.set_body(Body::None);
+                    res.headers_mut().clear();
+                    res.cookies = CookieJar::new();
+                    res.set_status_error(crate::http::StatusError::internal_server_error());
+                    has_error = true;
+                }
+            }
+
             if res.body.is_none() && has_error {
                 let mut catched = false;
                 for catcher in catchers.iter() {
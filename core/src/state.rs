@@ -0,0 +1,574 @@
+//! Application state registered on [`Service`](crate::Service) and shared with handlers.
+//!
+//! Three kinds of state are supported:
+//!
+//! * singleton values registered with [`Service::with_state`](crate::Service::with_state), shared by all
+//!   requests through an [`Arc`];
+//! * [`LazyState`] registered with [`Service::with_lazy_state`](crate::Service::with_lazy_state), initialized
+//!   by an async function the first time a request uses it, like a database pool;
+//! * [`ScopedState`] registered with [`Service::with_scoped_state`](crate::Service::with_scoped_state), a value
+//!   created at most once per request and finalized with the response status when the request is handled, like
+//!   a database transaction.
+//!
+//! Singleton and lazy states are extracted as [`State<T>`] handler parameters, scoped states as [`Scoped<T>`].
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! use salvo_core::prelude::*;
+//! use salvo_core::test::{ResponseExt, TestClient};
+//!
+//! struct Counter(AtomicUsize);
+//!
+//! #[handler]
+//! async fn hello(counter: State<Counter>) -> String {
+//!     format!("visit {}", counter.0.fetch_add(1, Ordering::Relaxed) + 1)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let service = Service::new(Router::new().get(hello)).with_state(Counter(AtomicUsize::new(0)));
+//!     let mut res = TestClient::get("http://127.0.0.1:7878").send(&service).await;
+//!     assert_eq!(res.take_string().await.unwrap(), "visit 1");
+//! }
+//! ```
+use std::any::type_name;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use tokio::sync::{Mutex, MutexGuard, OnceCell};
+
+use crate::extract::FromRequest;
+use crate::http::StatusCode;
+use crate::{Depot, Error, Request, Result};
+
+type InitFn<T> = dyn Fn() -> BoxFuture<'static, Result<T>> + Send + Sync;
+type FinalizeFn<T> = dyn Fn(T, StatusCode) -> BoxFuture<'static, Result<()>> + Send + Sync;
+
+/// Provides state to the [`Depot`] of every request handled by a [`Service`](crate::Service).
+#[async_trait]
+pub(crate) trait StateProvider: Send + Sync {
+    /// Attach state to `depot` before the request is routed.
+    fn attach(&self, depot: &mut Depot);
+    /// Release state attached to `depot` after the request is handled.
+    async fn finalize(&self, _depot: &mut Depot, _status: StatusCode) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) struct SingletonProvider<T>(pub(crate) Arc<T>);
+#[async_trait]
+impl<T> StateProvider for SingletonProvider<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn attach(&self, depot: &mut Depot) {
+        depot.inject(self.0.clone());
+    }
+}
+
+fn not_registered<T>() -> Error {
+    Error::other(format!("state `{}` is not registered", type_name::<T>()))
+}
+
+/// A singleton or lazy state, extracted as a handler parameter.
+///
+/// The state is also available with [`Depot::obtain::<Arc<T>>`](Depot::obtain) if it is a singleton.
+pub struct State<T>(Arc<T>);
+impl<T> State<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Get the state from `depot`, initializing it if it is a [`LazyState`] used for the first time.
+    pub async fn from_depot(depot: &mut Depot) -> Result<Self> {
+        if let Some(value) = depot.obtain::<Arc<T>>() {
+            return Ok(State(value.clone()));
+        }
+        match depot.obtain::<LazyState<T>>().cloned() {
+            Some(lazy) => lazy.get().await.map(State),
+            None => Err(not_registered::<T>()),
+        }
+    }
+    /// Consumes self and returns the inner [`Arc`].
+    #[inline]
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+#[async_trait]
+impl<T> FromRequest for State<T>
+where
+    T: Send + Sync + 'static,
+{
+    type Rejection = Error;
+
+    #[inline]
+    async fn from_request(_req: &mut Request, depot: &mut Depot) -> Result<Self> {
+        Self::from_depot(depot).await
+    }
+}
+impl<T> Clone for State<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        State(self.0.clone())
+    }
+}
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T: Debug> Debug for State<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("State").field(&self.0).finish()
+    }
+}
+
+/// A state initialized by an async function the first time it is used.
+///
+/// If initialization fails, the error is returned to the current user and the next use tries again. Cloned
+/// `LazyState`s share the value, so a clone can be captured by a [`ScopedState`] which needs it.
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::prelude::*;
+/// use salvo_core::state::LazyState;
+///
+/// struct Pool;
+/// impl Pool {
+///     async fn connect(_url: &str) -> salvo_core::Result<Pool> {
+///         Ok(Pool)
+///     }
+/// }
+///
+/// let pool = LazyState::new(|| Pool::connect("postgres://localhost/app"));
+/// let service = Service::new(Router::new()).with_lazy_state(pool);
+/// ```
+pub struct LazyState<T> {
+    cell: Arc<OnceCell<Arc<T>>>,
+    init: Arc<InitFn<T>>,
+}
+impl<T> LazyState<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Create a new `LazyState` initialized by `init`.
+    #[inline]
+    pub fn new<F, Fut>(init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        LazyState {
+            cell: Arc::new(OnceCell::new()),
+            init: Arc::new(move || Box::pin(init())),
+        }
+    }
+    /// Get the value, initializing it if needed.
+    pub async fn get(&self) -> Result<Arc<T>> {
+        self.cell
+            .get_or_try_init(|| async { (self.init)().await.map(Arc::new) })
+            .await
+            .map(Clone::clone)
+    }
+    /// Returns `true` if the value is initialized.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.cell.initialized()
+    }
+}
+impl<T> Clone for LazyState<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        LazyState {
+            cell: self.cell.clone(),
+            init: self.init.clone(),
+        }
+    }
+}
+impl<T> Debug for LazyState<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyState")
+            .field("type", &type_name::<T>())
+            .field("initialized", &self.cell.initialized())
+            .finish()
+    }
+}
+#[async_trait]
+impl<T> StateProvider for LazyState<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn attach(&self, depot: &mut Depot) {
+        depot.inject(self.clone());
+    }
+}
+
+/// A state created at most once per request, when it is first used, and finalized when the request is handled.
+///
+/// The finalizer receives the value and the final response status, after the allowed media types are checked and
+/// before catchers run. If it fails, the body, headers and cookies set by handlers are discarded and the response is
+/// replaced by an internal server error. Without a finalizer, the value is just dropped.
+///
+/// If the request is cancelled before it is handled, for example because the client disconnects, the finalizer
+/// is spawned on the current runtime with status `499 Client Closed Request`, so it still runs but can not change
+/// the response. Failures of a spawned finalizer are only logged.
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::prelude::*;
+/// use salvo_core::state::ScopedState;
+///
+/// struct Transaction;
+/// impl Transaction {
+///     async fn commit(self) -> salvo_core::Result<()> {
+///         Ok(())
+///     }
+///     async fn rollback(self) -> salvo_core::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// #[handler]
+/// async fn create_user(tx: Scoped<Transaction>) -> &'static str {
+///     let _tx = tx.lock().await;
+///     "created"
+/// }
+///
+/// let tx = ScopedState::new(|| async { Ok(Transaction) }).with_finalizer(|tx, status| async move {
+///     if status.is_success() {
+///         tx.commit().await
+///     } else {
+///         tx.rollback().await
+///     }
+/// });
+/// let service = Service::new(Router::new().post(create_user)).with_scoped_state(tx);
+/// ```
+pub struct ScopedState<T> {
+    create: Arc<InitFn<T>>,
+    finalize: Option<Arc<FinalizeFn<T>>>,
+}
+impl<T> ScopedState<T>
+where
+    T: Send + 'static,
+{
+    /// Create a new `ScopedState` whose value is created by `create`.
+    #[inline]
+    pub fn new<F, Fut>(create: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        ScopedState {
+            create: Arc::new(move || Box::pin(create())),
+            finalize: None,
+        }
+    }
+    /// Set the finalizer and returns `Self`.
+    #[inline]
+    pub fn with_finalizer<F, Fut>(mut self, finalize: F) -> Self
+    where
+        F: Fn(T, StatusCode) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.finalize = Some(Arc::new(move |value, status| Box::pin(finalize(value, status))));
+        self
+    }
+}
+impl<T> Debug for ScopedState<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedState")
+            .field("type", &type_name::<T>())
+            .field("has_finalizer", &self.finalize.is_some())
+            .finish()
+    }
+}
+#[async_trait]
+impl<T> StateProvider for ScopedState<T>
+where
+    T: Send + 'static,
+{
+    fn attach(&self, depot: &mut Depot) {
+        depot.inject(Scoped(Arc::new(ScopedSlot {
+            create: self.create.clone(),
+            finalize: self.finalize.clone(),
+            value: Mutex::new(None),
+        })));
+    }
+    async fn finalize(&self, depot: &mut Depot, status: StatusCode) -> Result<()> {
+        let value = match depot.eject::<Scoped<T>>() {
+            Some(scoped) => scoped.0.value.lock().await.take(),
+            None => None,
+        };
+        match (value, &self.finalize) {
+            (Some(value), Some(finalize)) => finalize(value, status).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+struct ScopedSlot<T> {
+    create: Arc<InitFn<T>>,
+    finalize: Option<Arc<FinalizeFn<T>>>,
+    value: Mutex<Option<T>>,
+}
+impl<T> Drop for ScopedSlot<T> {
+    fn drop(&mut self) {
+        // The value is still here only if the request is cancelled before `StateProvider::finalize` is called.
+        let (value, finalize) = match (self.value.get_mut().take(), self.finalize.take()) {
+            (Some(value), Some(finalize)) => (value, finalize),
+            _ => return,
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                let status = StatusCode::from_u16(499).expect("499 is a valid status code");
+                let finalizing = finalize(value, status);
+                handle.spawn(async move {
+                    if let Err(e) = finalizing.await {
+                        tracing::error!(error = ?e, "finalize cancelled scoped state failed");
+                    }
+                });
+            }
+            Err(_) => {
+                tracing::error!(
+                    "scoped state `{}` dropped outside of a runtime, finalizer is skipped",
+                    type_name::<T>()
+                );
+            }
+        }
+    }
+}
+
+/// The value of a [`ScopedState`] in the current request, extracted as a handler parameter.
+///
+/// Hoops and handlers of the same request share the value.
+pub struct Scoped<T>(Arc<ScopedSlot<T>>);
+impl<T> Scoped<T>
+where
+    T: Send + 'static,
+{
+    /// Get the value from `depot`, creating it if it is used for the first time in this request.
+    pub async fn from_depot(depot: &mut Depot) -> Result<Self> {
+        let scoped = depot.obtain::<Scoped<T>>().cloned().ok_or_else(not_registered::<T>)?;
+        {
+            let mut value = scoped.0.value.lock().await;
+            if value.is_none() {
+                *value = Some((scoped.0.create)().await?);
+            }
+        }
+        Ok(scoped)
+    }
+    /// Locks the value, waiting until it is not used by others.
+    #[inline]
+    pub async fn lock(&self) -> ScopedGuard<'_, T> {
+        ScopedGuard(self.0.value.lock().await)
+    }
+}
+#[async_trait]
+impl<T> FromRequest for Scoped<T>
+where
+    T: Send + 'static,
+{
+    type Rejection = Error;
+
+    #[inline]
+    async fn from_request(_req: &mut Request, depot: &mut Depot) -> Result<Self> {
+        Self::from_depot(depot).await
+    }
+}
+impl<T> Clone for Scoped<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Scoped(self.0.clone())
+    }
+}
+impl<T> Debug for Scoped<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Scoped").field(&type_name::<T>()).finish()
+    }
+}
+
+/// Locked value of [`Scoped`].
+pub struct ScopedGuard<'a, T>(MutexGuard<'a, Option<T>>);
+impl<T> Deref for ScopedGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.0.as_ref().expect("scoped value is already finalized")
+    }
+}
+impl<T> DerefMut for ScopedGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.0.as_mut().expect("scoped value is already finalized")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex as StdMutex;
+
+    use crate::prelude::*;
+    use crate::state::{LazyState, ScopedState};
+    use crate::test::{ResponseExt, TestClient};
+
+    struct Pool {
+        id: usize,
+    }
+
+    #[tokio::test]
+    async fn test_lazy_state() {
+        #[handler(internal)]
+        async fn hello(pool: State<Pool>, name: State<String>) -> String {
+            format!("{}:{}", pool.id, *name)
+        }
+        static INITS: AtomicUsize = AtomicUsize::new(0);
+        let pool = LazyState::new(|| async {
+            Ok(Pool {
+                id: INITS.fetch_add(1, Ordering::SeqCst) + 1,
+            })
+        });
+        let service = Service::new(Router::new().get(hello))
+            .with_state("salvo".to_owned())
+            .with_lazy_state(pool.clone());
+        assert!(!pool.is_initialized());
+        for _ in 0..2 {
+            let content = TestClient::get("http://127.0.0.1:7979/")
+                .send(&service)
+                .await
+                .take_string()
+                .await
+                .unwrap();
+            assert_eq!(content, "1:salvo");
+        }
+        assert!(pool.is_initialized());
+        assert_eq!(INITS.load(Ordering::SeqCst), 1);
+
+        let res = TestClient::get("http://127.0.0.1:7979/")
+            .send(&Service::new(Router::new().get(hello)))
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[tokio::test]
+    async fn test_scoped_state() {
+        struct Tx {
+            ops: Vec<&'static str>,
+        }
+        #[handler(internal)]
+        async fn add(tx: Scoped<Tx>, res: &mut Response) {
+            tx.lock().await.ops.push("add");
+            res.render("added");
+        }
+        #[handler(internal)]
+        async fn fail(tx: Scoped<Tx>, res: &mut Response) {
+            tx.lock().await.ops.push("fail");
+            res.set_status_code(StatusCode::CONFLICT);
+        }
+        #[handler(internal)]
+        async fn skip() -> &'static str {
+            "skipped"
+        }
+        static LOG: StdMutex<Vec<String>> = StdMutex::new(Vec::new());
+        let tx = ScopedState::new(|| async { Ok(Tx { ops: vec![] }) }).with_finalizer(|tx, status| async move {
+            let action = if status.is_success() { "commit" } else { "rollback" };
+            LOG.lock().unwrap().push(format!("{} {:?}", action, tx.ops));
+            Ok(())
+        });
+        let router = Router::new()
+            .push(Router::with_path("add").get(add))
+            .push(Router::with_path("fail").get(fail))
+            .push(Router::with_path("skip").get(skip));
+        let service = Service::new(router).with_scoped_state(tx);
+        for path in ["add", "fail", "skip"] {
+            TestClient::get(format!("http://127.0.0.1:7979/{}", path))
+                .send(&service)
+                .await;
+        }
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            vec!["commit [\"add\"]".to_owned(), "rollback [\"fail\"]".to_owned()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scoped_state_final_response() {
+        struct Tx;
+        #[handler(internal)]
+        async fn login(_tx: Scoped<Tx>, res: &mut Response) {
+            res.add_cookie(cookie::Cookie::new("session", "1"));
+            res.redirect_found("/home");
+        }
+        #[handler(internal)]
+        async fn text(_tx: Scoped<Tx>) -> &'static str {
+            "text"
+        }
+        static LOG: StdMutex<Vec<u16>> = StdMutex::new(Vec::new());
+        let tx = || {
+            ScopedState::new(|| async { Ok(Tx) }).with_finalizer(|_tx, status| async move {
+                LOG.lock().unwrap().push(status.as_u16());
+                if status.is_redirection() {
+                    Err(crate::Error::other("commit failed"))
+                } else {
+                    Ok(())
+                }
+            })
+        };
+        let service = Service::new(Router::new().get(text))
+            .with_allowed_media_types(vec![mime::APPLICATION_JSON])
+            .with_scoped_state(tx());
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+
+        let service = Service::new(Router::new().get(login)).with_scoped_state(tx());
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(res.headers().get("location").is_none());
+        assert!(res.headers().get("set-cookie").is_none());
+        assert_eq!(*LOG.lock().unwrap(), vec![415, 302]);
+    }
+
+    #[tokio::test]
+    async fn test_scoped_state_cancelled() {
+        use futures_util::FutureExt;
+
+        struct Tx;
+        #[handler(internal)]
+        async fn hang(tx: Scoped<Tx>) {
+            let _tx = tx.lock().await;
+            std::future::pending::<()>().await;
+        }
+        static LOG: StdMutex<Vec<u16>> = StdMutex::new(Vec::new());
+        let tx = ScopedState::new(|| async { Ok(Tx) }).with_finalizer(|_tx, status| async move {
+            LOG.lock().unwrap().push(status.as_u16());
+            Ok(())
+        });
+        let service = Service::new(Router::new().get(hang)).with_scoped_state(tx);
+        let request = TestClient::get("http://127.0.0.1:7979/").send(&service);
+        assert!(request.now_or_never().is_none());
+        for _ in 0..10 {
+            if !LOG.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(*LOG.lock().unwrap(), vec![499]);
+    }
+}
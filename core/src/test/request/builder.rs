@@ -9,6 +9,7 @@ use http::Method;
 use hyper::Body;
 use url::Url;
 
+use crate::addr::SocketAddr;
 use crate::routing::FlowCtrl;
 use crate::{Depot, Error, Handler, Request, Response, Router, Service};
 
@@ -24,6 +25,7 @@ pub struct RequestBuilder {
     headers: HeaderMap,
     // params: HashMap<String, String>,
     body: Body,
+    remote_addr: Option<SocketAddr>,
 }
 
 impl RequestBuilder {
@@ -42,6 +44,7 @@ impl RequestBuilder {
             headers: HeaderMap::new(),
             // params: HeaderMap::new(),
             body: Body::default(),
+            remote_addr: None,
         }
     }
 }
@@ -106,7 +109,6 @@ impl RequestBuilder {
     //     self
     // }
 
-
     /// Enable HTTP basic authentication.
     pub fn basic_auth(self, username: impl std::fmt::Display, password: Option<impl std::fmt::Display>) -> Self {
         let auth = match password {
@@ -226,6 +228,12 @@ impl RequestBuilder {
         self
     }
 
+    /// Set the remote address of this request, like the address of the peer which sent it.
+    pub fn remote_addr(mut self, addr: impl Into<SocketAddr>) -> Self {
+        self.remote_addr = Some(addr.into());
+        self
+    }
+
     /// Build final request.
     pub fn build(self) -> Request {
         let Self {
@@ -233,10 +241,13 @@ impl RequestBuilder {
             method,
             headers,
             body,
+            remote_addr,
         } = self;
         let mut req = hyper::Request::builder().method(method).uri(url.to_string());
         (*req.headers_mut().unwrap()) = headers;
-        req.body(body).unwrap().into()
+        let mut req: Request = req.body(body).unwrap().into();
+        req.remote_addr = remote_addr;
+        req
     }
 
     /// Send request to target, such as [`Router`], [`Service`], [`Handler`].
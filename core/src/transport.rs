@@ -1,3 +1,4 @@
+use http::uri::Scheme;
 use hyper::server::conn::AddrStream;
 use tokio::io::{AsyncRead, AsyncWrite};
 
@@ -5,6 +6,10 @@ use crate::addr::SocketAddr;
 
 pub trait Transport: AsyncRead + AsyncWrite {
     fn remote_addr(&self) -> Option<SocketAddr>;
+    #[inline]
+    fn scheme(&self) -> Scheme {
+        Scheme::HTTP
+    }
 }
 
 impl Transport for AddrStream {
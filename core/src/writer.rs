@@ -161,6 +161,103 @@ where
     }
 }
 
+cfg_feature! {
+    #![feature = "msgpack"]
+    /// Write serializable content to response as MessagePack content. It will set ```content-type``` to ```application/msgpack```.
+    ///
+    /// Structs are written as maps with field names.
+    pub struct MsgPack<T>(pub T);
+    impl<T> Piece for MsgPack<T>
+    where
+        T: Serialize + Send,
+    {
+        #[inline]
+        fn render(self, res: &mut Response) {
+            match rmp_serde::to_vec_named(&self.0) {
+                Ok(bytes) => {
+                    res.headers_mut()
+                        .insert(CONTENT_TYPE, HeaderValue::from_static("application/msgpack"));
+                    res.write_body(bytes).ok();
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "MsgPack write error");
+                    res.set_status_error(StatusError::internal_server_error());
+                }
+            }
+        }
+    }
+}
+
+cfg_feature! {
+    #![feature = "cbor"]
+    /// Write serializable content to response as CBOR content. It will set ```content-type``` to ```application/cbor```.
+    pub struct Cbor<T>(pub T);
+    impl<T> Piece for Cbor<T>
+    where
+        T: Serialize + Send,
+    {
+        #[inline]
+        fn render(self, res: &mut Response) {
+            let mut bytes = Vec::new();
+            match ciborium::into_writer(&self.0, &mut bytes) {
+                Ok(()) => {
+                    res.headers_mut()
+                        .insert(CONTENT_TYPE, HeaderValue::from_static("application/cbor"));
+                    res.write_body(bytes).ok();
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "Cbor write error");
+                    res.set_status_error(StatusError::internal_server_error());
+                }
+            }
+        }
+    }
+}
+
+cfg_feature! {
+    #![feature = "xml"]
+    /// Write serializable content to response as XML content. It will set ```content-type``` to ```application/xml; charset=utf-8```.
+    ///
+    /// Use [`Text::Xml`] to write XML which is already serialized.
+    pub struct Xml<T>(pub T);
+    impl<T> Piece for Xml<T>
+    where
+        T: Serialize + Send,
+    {
+        #[inline]
+        fn render(self, res: &mut Response) {
+            match quick_xml::se::to_string(&self.0) {
+                Ok(content) => {
+                    res.headers_mut()
+                        .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml; charset=utf-8"));
+                    res.write_body(content).ok();
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "Xml write error");
+                    res.set_status_error(StatusError::internal_server_error());
+                }
+            }
+        }
+    }
+}
+
+cfg_feature! {
+    #![feature = "protobuf"]
+    /// Write protobuf message to response. It will set ```content-type``` to ```application/x-protobuf```.
+    pub struct Protobuf<T>(pub T);
+    impl<T> Piece for Protobuf<T>
+    where
+        T: prost::Message,
+    {
+        #[inline]
+        fn render(self, res: &mut Response) {
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/x-protobuf"));
+            res.write_body(self.0.encode_to_vec()).ok();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::prelude::*;
@@ -245,6 +342,94 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_write_msgpack_content() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct User {
+            name: String,
+        }
+        #[handler(internal)]
+        async fn test() -> MsgPack<User> {
+            MsgPack(User { name: "jobs".into() })
+        }
+
+        let router = Router::new().push(Router::with_path("test").get(test));
+        let mut res = TestClient::get("http://127.0.0.1:7878/test").send(router).await;
+        assert_eq!(res.headers().get("content-type").unwrap(), "application/msgpack");
+        let bytes = res.take_bytes().await.unwrap();
+        assert_eq!(
+            rmp_serde::from_slice::<User>(&bytes).unwrap(),
+            User { name: "jobs".into() }
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_write_cbor_content() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct User {
+            name: String,
+        }
+        #[handler(internal)]
+        async fn test() -> Cbor<User> {
+            Cbor(User { name: "jobs".into() })
+        }
+
+        let router = Router::new().push(Router::with_path("test").get(test));
+        let mut res = TestClient::get("http://127.0.0.1:7878/test").send(router).await;
+        assert_eq!(res.headers().get("content-type").unwrap(), "application/cbor");
+        let bytes = res.take_bytes().await.unwrap();
+        assert_eq!(
+            ciborium::from_reader::<User, _>(&bytes[..]).unwrap(),
+            User { name: "jobs".into() }
+        );
+    }
+
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn test_write_xml_content() {
+        #[derive(Serialize, Debug)]
+        struct User {
+            name: String,
+        }
+        #[handler(internal)]
+        async fn test() -> Xml<User> {
+            Xml(User { name: "jobs".into() })
+        }
+
+        let router = Router::new().push(Router::with_path("test").get(test));
+        let mut res = TestClient::get("http://127.0.0.1:7878/test").send(router).await;
+        assert_eq!(res.take_string().await.unwrap(), "<User><name>jobs</name></User>");
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "application/xml; charset=utf-8"
+        );
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[tokio::test]
+    async fn test_write_protobuf_content() {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct User {
+            #[prost(string, tag = "1")]
+            name: String,
+        }
+        #[handler(internal)]
+        async fn test() -> Protobuf<User> {
+            Protobuf(User { name: "jobs".into() })
+        }
+
+        let router = Router::new().push(Router::with_path("test").get(test));
+        let mut res = TestClient::get("http://127.0.0.1:7878/test").send(router).await;
+        assert_eq!(res.headers().get("content-type").unwrap(), "application/x-protobuf");
+        let bytes = res.take_bytes().await.unwrap();
+        assert_eq!(
+            <User as prost::Message>::decode(bytes).unwrap(),
+            User { name: "jobs".into() }
+        );
+    }
+
     #[tokio::test]
     async fn test_write_html_text() {
         #[handler(internal)]
@@ -7,11 +7,7 @@ async fn main() {
 
     let router = Router::with_path("<**path>").get(DirHandler::width_options(
         vec!["examples/file-list/static/boy", "examples/file-list/static/girl"],
-        Options {
-            dot_files: false,
-            listing: true,
-            defaults: vec!["index.html".to_owned()],
-        },
+        Options::new().with_listing(true).with_defaults(["index.html"]),
     ));
     tracing::info!("Listening on http://127.0.0.1:7878");
     Server::new(TcpListener::bind("127.0.0.1:7878")).serve(router).await;
@@ -1,7 +1,5 @@
 //! affix middleware is used to add any data to depot.
 
-use std::any::TypeId;
-
 use salvo_core::handler;
 use salvo_core::prelude::*;
 
@@ -23,10 +21,20 @@ where
     }
 }
 
+struct InjectCell<V>(V);
+impl<T> Affix for InjectCell<T>
+where
+    T: Send + Sync + Clone + 'static,
+{
+    fn attach(&self, depot: &mut Depot) {
+        depot.inject(self.0.clone());
+    }
+}
+
 /// Inject a value into depot.
 #[inline]
 pub fn inject<V: Send + Sync + Clone + 'static>(value: V) -> AffixList {
-    insert(format!("{:?}", TypeId::of::<V>()), value)
+    AffixList::new().inject(value)
 }
 
 /// Insert a key-value pair into depot.
@@ -50,8 +58,9 @@ impl AffixList {
         AffixList(Vec::new())
     }
     /// Inject a value into depot.
-    pub fn inject<V: Send + Sync + Clone + 'static>(self, value: V) -> Self {
-        self.insert(format!("{:?}", TypeId::of::<V>()), value)
+    pub fn inject<V: Send + Sync + Clone + 'static>(mut self, value: V) -> Self {
+        self.0.push(Box::new(InjectCell(value)));
+        self
     }
 
     /// Insert a key-value pair into depot.
@@ -70,3 +79,35 @@ impl AffixList {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::prelude::*;
+    use salvo_core::test::{ResponseExt, TestClient};
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct User {
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn test_affix() {
+        #[handler]
+        async fn hello(depot: &mut Depot) -> String {
+            let user = depot.obtain::<User>().unwrap();
+            format!("{}:{}", user.name, depot.get::<&str>("key").copied().unwrap())
+        }
+        let router = Router::new()
+            .hoop(inject(User { name: "salvo".into() }).insert("key", "value"))
+            .get(hello);
+        let content = TestClient::get("http://127.0.0.1:7979/")
+            .send(&Service::new(router))
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "salvo:value");
+    }
+}
@@ -122,6 +122,7 @@ impl Handler for CompressionHandler {
             .unwrap_or_default();
         if content_type.is_empty()
             || res.body().is_none()
+            || res.headers().contains_key(CONTENT_ENCODING)
             || !self.content_types.iter().any(|c| content_type.starts_with(&**c))
         {
             return;
@@ -0,0 +1,223 @@
+//! Concurrency limiter middleware, caps the number of in-flight requests.
+//!
+//! Each `ConcurrencyLimiter` owns its own permits, clones share them. Add one instance to several
+//! routers to limit them together, or create one per router to limit them separately.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use salvo_core::prelude::*;
+//! use salvo_extra::concurrency_limiter::ConcurrencyLimiter;
+//!
+//! #[handler]
+//! async fn query() -> &'static str {
+//!     "rows"
+//! }
+//!
+//! let limiter = ConcurrencyLimiter::new(64)
+//!     .with_max_waiters(128)
+//!     .with_max_wait(Duration::from_millis(500));
+//! let router = Router::with_path("query").hoop(limiter).get(query);
+//! ```
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use salvo_core::async_trait;
+use salvo_core::http::header::{HeaderValue, RETRY_AFTER};
+use salvo_core::http::{Request, Response, StatusError};
+use salvo_core::routing::FlowCtrl;
+use salvo_core::{Depot, Handler};
+
+/// ConcurrencyLimiter
+///
+/// Requests over the limit wait in a queue of at most `max_waiters` entries for at most `max_wait`,
+/// others are rejected with `503 Service Unavailable` and a `Retry-After` header.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+    waiters: Arc<AtomicUsize>,
+    max_concurrency: usize,
+    max_waiters: usize,
+    max_wait: Option<Duration>,
+    retry_after: Duration,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a new `ConcurrencyLimiter` which allows `max_concurrency` in-flight requests and no waiters.
+    #[inline]
+    pub fn new(max_concurrency: usize) -> Self {
+        ConcurrencyLimiter {
+            semaphore: Arc::new(Semaphore::new(max_concurrency)),
+            waiters: Arc::new(AtomicUsize::new(0)),
+            max_concurrency,
+            max_waiters: 0,
+            max_wait: None,
+            retry_after: Duration::from_secs(1),
+        }
+    }
+
+    /// Get max concurrency.
+    #[inline]
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+    /// Get the number of requests currently being handled.
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrency - self.semaphore.available_permits()
+    }
+    /// Get the number of requests currently waiting.
+    #[inline]
+    pub fn waiting(&self) -> usize {
+        self.waiters.load(Ordering::Acquire)
+    }
+
+    /// Get max waiters.
+    #[inline]
+    pub fn max_waiters(&self) -> usize {
+        self.max_waiters
+    }
+    /// Sets how many requests may wait for a permit when all are taken, default is 0.
+    #[inline]
+    pub fn with_max_waiters(mut self, max_waiters: usize) -> Self {
+        self.max_waiters = max_waiters;
+        self
+    }
+
+    /// Get max wait time.
+    #[inline]
+    pub fn max_wait(&self) -> Option<Duration> {
+        self.max_wait
+    }
+    /// Sets how long a request waits for a permit, `None` means wait until one is available.
+    #[inline]
+    pub fn with_max_wait(mut self, max_wait: impl Into<Option<Duration>>) -> Self {
+        self.max_wait = max_wait.into();
+        self
+    }
+
+    /// Get retry after duration.
+    #[inline]
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+    /// Sets value of `Retry-After` header of rejected requests, rounded up to seconds, default is 1 second.
+    #[inline]
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+        let max_waiters = self.max_waiters;
+        self.waiters
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                if n < max_waiters {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()?;
+        let _guard = WaiterGuard(&self.waiters);
+        let acquire = self.semaphore.clone().acquire_owned();
+        match self.max_wait {
+            Some(max_wait) => tokio::time::timeout(max_wait, acquire).await.ok()?.ok(),
+            None => acquire.await.ok(),
+        }
+    }
+}
+
+struct WaiterGuard<'a>(&'a AtomicUsize);
+impl Drop for WaiterGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[async_trait]
+impl Handler for ConcurrencyLimiter {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        match self.acquire().await {
+            Some(_permit) => {
+                ctrl.call_next(req, depot, res).await;
+            }
+            None => {
+                let mut secs = self.retry_after.as_secs();
+                if self.retry_after.subsec_nanos() > 0 {
+                    secs += 1;
+                }
+                res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(secs));
+                res.set_status_error(StatusError::service_unavailable());
+                ctrl.skip_rest();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::prelude::*;
+    use salvo_core::test::TestClient;
+
+    use super::*;
+
+    #[handler]
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "hello"
+    }
+
+    async fn statuses(limiter: ConcurrencyLimiter, count: usize) -> (usize, usize) {
+        let router = Router::new().hoop(limiter).get(slow);
+        let service = Arc::new(Service::new(router));
+        let handles = (0..count)
+            .map(|_| {
+                let service = service.clone();
+                tokio::spawn(async move { TestClient::get("http://127.0.0.1:7979/").send(&*service).await })
+            })
+            .collect::<Vec<_>>();
+        let mut responses = Vec::with_capacity(count);
+        for handle in handles {
+            responses.push(handle.await.unwrap());
+        }
+        let ok = responses
+            .iter()
+            .filter(|res| res.status_code() == Some(StatusCode::OK))
+            .count();
+        let rejected = responses
+            .iter()
+            .filter(|res| {
+                res.status_code() == Some(StatusCode::SERVICE_UNAVAILABLE)
+                    && res.headers().get(RETRY_AFTER).map(|v| v == "1").unwrap_or(false)
+            })
+            .count();
+        (ok, rejected)
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limiter() {
+        assert_eq!(statuses(ConcurrencyLimiter::new(2), 5).await, (2, 3));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limiter_waiters() {
+        let limiter = ConcurrencyLimiter::new(2).with_max_waiters(2);
+        assert_eq!(statuses(limiter.clone(), 5).await, (4, 1));
+        assert_eq!(limiter.in_flight(), 0);
+        assert_eq!(limiter.waiting(), 0);
+
+        let limiter = ConcurrencyLimiter::new(1)
+            .with_max_waiters(2)
+            .with_max_wait(Duration::from_millis(50));
+        assert_eq!(statuses(limiter, 3).await, (1, 2));
+    }
+}
@@ -16,10 +16,10 @@ use salvo_core::http::headers::HeaderName;
 use salvo_core::http::uri::Scheme;
 use salvo_core::http::{Method, StatusCode};
 use salvo_core::prelude::*;
-use salvo_core::Error;
+use salvo_core::{DepotError, Error};
 
 /// key used to save csrf data to depot.
-pub const DATA_KEY: &str = "::salvo::extra::csrf::data";
+const DATA_KEY: DepotKey<CsrfData> = DepotKey::new("::salvo::extra::csrf::data");
 
 struct CsrfData {
     token: String,
@@ -31,40 +31,40 @@ struct CsrfData {
 pub trait CsrfDepotExt {
     /// Gets the CSRF token for inclusion in an HTTP request header,
     /// a query parameter, or a form field.
-    fn csrf_token(&self) -> Option<&str>;
+    fn csrf_token(&self) -> Result<&str, DepotError>;
 
     /// Gets the name of the header in which to returns the CSRF token,
     /// if the CSRF token is being returned in a header.
-    fn csrf_header_name(&self) -> Option<&str>;
+    fn csrf_header_name(&self) -> Result<&str, DepotError>;
 
     /// Gets the name of the query param in which to returns the CSRF
     /// token, if the CSRF token is being returned in a query param.
-    fn csrf_query_param(&self) -> Option<&str>;
+    fn csrf_query_param(&self) -> Result<&str, DepotError>;
 
     /// Gets the name of the form field in which to returns the CSRF
     /// token, if the CSRF token is being returned in a form field.
-    fn csrf_field_name(&self) -> Option<&str>;
+    fn csrf_field_name(&self) -> Result<&str, DepotError>;
 }
 
 impl CsrfDepotExt for Depot {
     #[inline]
-    fn csrf_token(&self) -> Option<&str> {
-        self.get::<CsrfData>(DATA_KEY).map(|d| &*d.token)
+    fn csrf_token(&self) -> Result<&str, DepotError> {
+        self.fetch(DATA_KEY).map(|d| &*d.token)
     }
 
     #[inline]
-    fn csrf_header_name(&self) -> Option<&str> {
-        self.get::<CsrfData>(DATA_KEY).map(|d| d.header_name.as_str())
+    fn csrf_header_name(&self) -> Result<&str, DepotError> {
+        self.fetch(DATA_KEY).map(|d| d.header_name.as_str())
     }
 
     #[inline]
-    fn csrf_query_param(&self) -> Option<&str> {
-        self.get::<CsrfData>(DATA_KEY).map(|d| &*d.query_param)
+    fn csrf_query_param(&self) -> Result<&str, DepotError> {
+        self.fetch(DATA_KEY).map(|d| &*d.query_param)
     }
 
     #[inline]
-    fn csrf_field_name(&self) -> Option<&str> {
-        self.get::<CsrfData>(DATA_KEY).map(|d| &*d.field_name)
+    fn csrf_field_name(&self) -> Result<&str, DepotError> {
+        self.fetch(DATA_KEY).map(|d| &*d.field_name)
     }
 }
 
@@ -328,15 +328,18 @@ impl Handler for CsrfHandler {
 
         // Add the token to the request for use by the application.
         let secure_cookie = req.uri().scheme() == Some(&Scheme::HTTPS);
-        depot.insert(
-            DATA_KEY,
-            CsrfData {
-                token: token.b64_url_string(),
-                header_name: self.header_name.clone(),
-                query_param: self.query_param.clone(),
-                field_name: self.form_field.clone(),
-            },
-        );
+        let data = CsrfData {
+            token: token.b64_url_string(),
+            header_name: self.header_name.clone(),
+            query_param: self.query_param.clone(),
+            field_name: self.form_field.clone(),
+        };
+        if let Err(e) = depot.put(DATA_KEY, data) {
+            tracing::error!(error = ?e, "unable to put CSRF data to depot");
+            res.set_status_error(StatusError::internal_server_error());
+            ctrl.skip_rest();
+            return;
+        }
 
         // Add the CSRF cookie to the response.
         let cookie = self.build_cookie(secure_cookie, cookie.b64_string());
@@ -0,0 +1,425 @@
+//! Force https middleware, redirects plain http requests to https and normalizes the host name.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use salvo_core::prelude::*;
+//! use salvo_extra::force_https::{CanonicalHost, ForceHttps};
+//!
+//! #[handler]
+//! async fn hello() -> &'static str {
+//!     "hello"
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let router = Router::new()
+//!         .hoop(ForceHttps::new().with_canonical_host(CanonicalHost::Apex))
+//!         .get(hello);
+//!     Server::new(TcpListener::bind("0.0.0.0:80")).serve(router).await;
+//! }
+//! ```
+use std::net::IpAddr;
+
+use salvo_core::addr::SocketAddr;
+use salvo_core::async_trait;
+use salvo_core::http::header::{HeaderValue, FORWARDED, HOST, LOCATION};
+use salvo_core::http::uri::Scheme;
+use salvo_core::http::{Method, Request, Response, StatusCode};
+use salvo_core::routing::FlowCtrl;
+use salvo_core::{Depot, Handler};
+
+use crate::ip_filter::{collect_ip_nets, IntoIpNet, IpNet, IpNetParseError};
+
+/// Path used by `AcmeListener` to answer HTTP-01 challenges, it is excluded by default.
+pub const ACME_CHALLENGE_PATH: &str = "/.well-known/acme-challenge";
+
+/// Host name form requests are redirected to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanonicalHost {
+    /// Prefix the host with `www.`, `example.com` is redirected to `www.example.com`.
+    Www,
+    /// Strip the `www.` prefix, `www.example.com` is redirected to `example.com`.
+    Apex,
+    /// Redirect every other host to this one.
+    Exact(String),
+}
+
+/// ForceHttps
+///
+/// Requests which are not secure are redirected to https, `GET` and `HEAD` requests with
+/// `301 Moved Permanently`, others with `308 Permanent Redirect` so the method and body are kept.
+///
+/// A request is secure if it was accepted by a TLS listener, or if it comes from a trusted proxy
+/// which sets `X-Forwarded-Proto: https` or `Forwarded: proto=https`. Proxies append to these headers,
+/// so only the last element, added by the trusted proxy itself, is checked.
+#[derive(Clone, Debug)]
+pub struct ForceHttps {
+    https: bool,
+    https_port: Option<u16>,
+    canonical_host: Option<CanonicalHost>,
+    trusted_proxies: Vec<IpNet>,
+    exclusions: Vec<String>,
+}
+
+impl Default for ForceHttps {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForceHttps {
+    /// Create a new `ForceHttps`, the [`ACME_CHALLENGE_PATH`] is excluded.
+    #[inline]
+    pub fn new() -> Self {
+        ForceHttps {
+            https: true,
+            https_port: None,
+            canonical_host: None,
+            trusted_proxies: vec![],
+            exclusions: vec![ACME_CHALLENGE_PATH.into()],
+        }
+    }
+
+    /// Get whether plain http requests are redirected to https.
+    #[inline]
+    pub fn https(&self) -> bool {
+        self.https
+    }
+    /// Sets whether plain http requests are redirected to https, disable it to only normalize host names.
+    #[inline]
+    pub fn with_https(mut self, https: bool) -> Self {
+        self.https = https;
+        self
+    }
+
+    /// Get https port.
+    #[inline]
+    pub fn https_port(&self) -> Option<u16> {
+        self.https_port
+    }
+    /// Sets port used in redirect location when upgrading to https, `None` means the default port 443.
+    #[inline]
+    pub fn with_https_port(mut self, port: impl Into<Option<u16>>) -> Self {
+        self.https_port = port.into();
+        self
+    }
+
+    /// Get canonical host.
+    #[inline]
+    pub fn canonical_host(&self) -> Option<&CanonicalHost> {
+        self.canonical_host.as_ref()
+    }
+    /// Sets canonical host, requests with other host names are redirected.
+    #[inline]
+    pub fn with_canonical_host(mut self, host: impl Into<Option<CanonicalHost>>) -> Self {
+        self.canonical_host = host.into();
+        self
+    }
+
+    /// Get trusted proxies.
+    #[inline]
+    pub fn trusted_proxies(&self) -> &Vec<IpNet> {
+        &self.trusted_proxies
+    }
+    /// Sets networks of the proxies whose forwarded proto headers are trusted.
+    ///
+    /// Returns an error if any of `nets` is not a valid network.
+    #[inline]
+    pub fn with_trusted_proxies<I>(mut self, nets: I) -> Result<Self, IpNetParseError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIpNet,
+    {
+        self.trusted_proxies = collect_ip_nets(nets)?;
+        Ok(self)
+    }
+
+    /// Get excluded path prefixes.
+    #[inline]
+    pub fn exclusions(&self) -> &Vec<String> {
+        &self.exclusions
+    }
+    /// Get excluded path prefixes mutable reference.
+    #[inline]
+    pub fn exclusions_mut(&mut self) -> &mut Vec<String> {
+        &mut self.exclusions
+    }
+    /// Adds a path prefix which is never redirected.
+    ///
+    /// Prefixes match whole path segments, `/static` excludes `/static` and `/static/app.js` but not `/statics`.
+    #[inline]
+    pub fn with_exclusion(mut self, path: impl Into<String>) -> Self {
+        self.exclusions.push(path.into());
+        self
+    }
+
+    fn is_trusted_proxy(&self, req: &Request) -> bool {
+        let ip = match req.remote_addr() {
+            Some(SocketAddr::IPv4(addr)) => IpAddr::V4(*addr.ip()),
+            Some(SocketAddr::IPv6(addr)) => IpAddr::V6(*addr.ip()),
+            _ => return false,
+        };
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    fn is_secure(&self, req: &Request) -> bool {
+        if *req.scheme() == Scheme::HTTPS {
+            return true;
+        }
+        if !self.is_trusted_proxy(req) {
+            return false;
+        }
+        // Earlier elements were sent by the client or by untrusted hops, only the last one is reliable.
+        if let Some(proto) = last_header_element(req, "x-forwarded-proto") {
+            return proto.eq_ignore_ascii_case("https");
+        }
+        if let Some(forwarded) = last_header_element(req, FORWARDED.as_str()) {
+            return forwarded
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .any(|(k, v)| k.eq_ignore_ascii_case("proto") && v.trim_matches('"').eq_ignore_ascii_case("https"));
+        }
+        false
+    }
+
+    fn canonicalize(&self, host: &str) -> Option<String> {
+        if host.parse::<IpAddr>().is_ok() || host.starts_with('[') {
+            return None;
+        }
+        match self.canonical_host.as_ref()? {
+            CanonicalHost::Www if !host.starts_with("www.") => Some(format!("www.{}", host)),
+            CanonicalHost::Apex => host.strip_prefix("www.").map(ToOwned::to_owned),
+            CanonicalHost::Exact(expected) if !expected.eq_ignore_ascii_case(host) => Some(expected.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the redirect location for the request, or `None` if no redirect is needed.
+    pub fn location(&self, req: &Request) -> Option<String> {
+        let path = req.uri().path();
+        if self.exclusions.iter().any(|prefix| is_path_prefix(prefix, path)) {
+            return None;
+        }
+        let authority = req
+            .headers()
+            .get(HOST)
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| req.uri().authority().map(|a| a.as_str()))?;
+        let (host, port) = split_authority(authority);
+
+        let upgrade = self.https && !self.is_secure(req);
+        let canonical = self.canonicalize(host);
+        if !upgrade && canonical.is_none() {
+            return None;
+        }
+        let (scheme, port) = if upgrade {
+            ("https", self.https_port.filter(|p| *p != 443))
+        } else if self.https && *req.scheme() != Scheme::HTTPS {
+            // Secure behind a trusted proxy, the port of the plain connection is meaningless to clients.
+            ("https", None)
+        } else {
+            (req.scheme().as_str(), port)
+        };
+        let host = canonical.as_deref().unwrap_or(host);
+        let path_and_query = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        Some(match port {
+            Some(port) => format!("{}://{}:{}{}", scheme, host, port, path_and_query),
+            None => format!("{}://{}{}", scheme, host, path_and_query),
+        })
+    }
+}
+
+/// Last comma separated element of the last `name` header.
+fn last_header_element<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+    let value = req.headers().get_all(name).iter().next_back()?.to_str().ok()?;
+    value.rsplit(',').next().map(str::trim)
+}
+
+fn split_authority(authority: &str) -> (&str, Option<u16>) {
+    if let Some(rest) = authority.strip_prefix('[') {
+        if let Some((ip, port)) = rest.split_once(']') {
+            let port = port.strip_prefix(':').and_then(|p| p.parse().ok());
+            return (&authority[..ip.len() + 2], port);
+        }
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()),
+        None => (authority, None),
+    }
+}
+
+#[async_trait]
+impl Handler for ForceHttps {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        match self.location(req).and_then(|l| HeaderValue::from_str(&l).ok()) {
+            Some(location) => {
+                let status = if *req.method() == Method::GET || *req.method() == Method::HEAD {
+                    StatusCode::MOVED_PERMANENTLY
+                } else {
+                    StatusCode::PERMANENT_REDIRECT
+                };
+                res.set_status_code(status);
+                res.headers_mut().insert(LOCATION, location);
+                ctrl.skip_rest();
+            }
+            None => {
+                ctrl.call_next(req, depot, res).await;
+            }
+        }
+    }
+}
+
+/// Returns `true` if `path` is `prefix` or a path below it.
+fn is_path_prefix(prefix: &str, path: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::prelude::*;
+    use salvo_core::test::TestClient;
+
+    use super::*;
+
+    #[handler]
+    async fn hello() -> &'static str {
+        "hello"
+    }
+
+    async fn location(handler: ForceHttps, client: salvo_core::test::RequestBuilder) -> (StatusCode, Option<String>) {
+        let router = Router::new()
+            .hoop(handler)
+            .push(Router::with_path("<**>").handle(hello));
+        let res = client.send(router).await;
+        let location = res.headers().get(LOCATION).map(|v| v.to_str().unwrap().to_owned());
+        (res.status_code().unwrap(), location)
+    }
+
+    #[tokio::test]
+    async fn test_force_https() {
+        let (status, loc) = location(ForceHttps::new(), TestClient::get("http://example.com:8080/a/b?c=d")).await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(loc.as_deref(), Some("https://example.com/a/b?c=d"));
+
+        let (status, loc) = location(
+            ForceHttps::new().with_https_port(8443),
+            TestClient::post("http://example.com/form"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(loc.as_deref(), Some("https://example.com:8443/form"));
+
+        let (status, loc) = location(ForceHttps::new(), TestClient::get("https://example.com/")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(loc, None);
+
+        let (status, _) = location(
+            ForceHttps::new(),
+            TestClient::get("http://example.com/.well-known/acme-challenge/token"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = location(
+            ForceHttps::new(),
+            TestClient::get("http://example.com/.well-known/acme-challengeX/token"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_proto() {
+        // Requests built by `TestClient` have no remote address, so no proxy is trusted.
+        let (status, _) = location(
+            ForceHttps::new().with_trusted_proxies(["0.0.0.0/0"]).unwrap(),
+            TestClient::get("http://example.com/").insert_header("x-forwarded-proto", "https"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+
+        let proxy = ([10, 0, 0, 2], 43210);
+        let trusted = || ForceHttps::new().with_trusted_proxies(["10.0.0.0/8"]).unwrap();
+        // The last element is set by the trusted proxy.
+        let (status, _) = location(
+            trusted(),
+            TestClient::get("http://example.com/")
+                .remote_addr(std::net::SocketAddr::from(proxy))
+                .insert_header("x-forwarded-proto", "http, https"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = location(
+            trusted(),
+            TestClient::get("http://example.com/")
+                .remote_addr(std::net::SocketAddr::from(proxy))
+                .insert_header("forwarded", "for=192.0.2.60;proto=http, for=198.51.100.17;proto=https"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        // A client spoofing the header is not trusted, the proxy appends the real proto.
+        let (status, _) = location(
+            trusted(),
+            TestClient::get("http://example.com/")
+                .remote_addr(std::net::SocketAddr::from(proxy))
+                .insert_header("x-forwarded-proto", "https, http"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+        let (status, _) = location(
+            trusted(),
+            TestClient::get("http://example.com/")
+                .remote_addr(std::net::SocketAddr::from(([192, 0, 2, 1], 43210)))
+                .insert_header("x-forwarded-proto", "https"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+    }
+
+    #[tokio::test]
+    async fn test_canonical_host() {
+        let (_, loc) = location(
+            ForceHttps::new().with_canonical_host(CanonicalHost::Apex),
+            TestClient::get("http://www.example.com/x"),
+        )
+        .await;
+        assert_eq!(loc.as_deref(), Some("https://example.com/x"));
+
+        let (_, loc) = location(
+            ForceHttps::new()
+                .with_https(false)
+                .with_canonical_host(CanonicalHost::Www),
+            TestClient::get("http://example.com:8080/x"),
+        )
+        .await;
+        assert_eq!(loc.as_deref(), Some("http://www.example.com:8080/x"));
+
+        let (status, _) = location(
+            ForceHttps::new().with_canonical_host(CanonicalHost::Exact("example.com".into())),
+            TestClient::get("https://example.com/x"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = location(
+            ForceHttps::new()
+                .with_https(false)
+                .with_canonical_host(CanonicalHost::Www),
+            TestClient::get("http://127.0.0.1/x"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_split_authority() {
+        assert_eq!(split_authority("example.com"), ("example.com", None));
+        assert_eq!(split_authority("example.com:8080"), ("example.com", Some(8080)));
+        assert_eq!(split_authority("[::1]:8080"), ("[::1]", Some(8080)));
+        assert_eq!(split_authority("[::1]"), ("[::1]", None));
+    }
+}
@@ -0,0 +1,476 @@
+//! Outbound HTTP client shared by handlers and the proxy.
+//!
+//! `HttpClient` is cheap to clone, clones share the connection pool. Inject it with
+//! [`affix`](crate::affix) and call [`HttpClient::for_request`] in handlers so that request id and
+//! trace headers of the inbound request are propagated to outbound requests.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use salvo_core::prelude::*;
+//! use salvo_extra::affix;
+//! use salvo_extra::http_client::HttpClient;
+//!
+//! #[handler]
+//! async fn profile(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+//!     let client = depot.obtain::<HttpClient>().unwrap().for_request(req);
+//!     match client.get("http://users-svc/profile").await {
+//!         Ok(upstream) => res.set_status_code(upstream.status()),
+//!         Err(_) => res.set_status_error(StatusError::bad_gateway()),
+//!     }
+//! }
+//!
+//! let client = HttpClient::builder().with_timeout(Duration::from_secs(5)).build().unwrap();
+//! let router = Router::new().hoop(affix::inject(client)).push(Router::with_path("profile").get(profile));
+//! ```
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use rustls::{OwnedTrustAnchor, RootCertStore};
+use salvo_core::http::header::{HeaderName, HeaderValue};
+use salvo_core::http::HeaderMap;
+#[cfg(feature = "http-client-rustls")]
+use salvo_core::listener::rustls::RustlsConfig;
+use salvo_core::{Error, Request};
+use tracing::{Instrument, Level};
+
+/// Headers propagated by [`HttpClient::for_request`] by default.
+pub const DEFAULT_PROPAGATE_HEADERS: [&str; 5] = [
+    "x-request-id",
+    "x-correlation-id",
+    "traceparent",
+    "tracestate",
+    "baggage",
+];
+
+/// Error of outbound requests.
+#[derive(Debug)]
+pub enum HttpClientError {
+    /// The request could not be built, e.g. the uri is invalid.
+    Http(hyper::http::Error),
+    /// Connecting to the server or transferring the request failed.
+    Hyper(hyper::Error),
+    /// The response headers were not received in time.
+    Timeout,
+}
+impl Display for HttpClientError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            HttpClientError::Http(e) => write!(f, "invalid request: {}", e),
+            HttpClientError::Hyper(e) => write!(f, "http client error: {}", e),
+            HttpClientError::Timeout => write!(f, "http client timeout"),
+        }
+    }
+}
+impl StdError for HttpClientError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            HttpClientError::Http(e) => Some(e),
+            HttpClientError::Hyper(e) => Some(e),
+            HttpClientError::Timeout => None,
+        }
+    }
+}
+
+/// Builder of [`HttpClient`].
+#[derive(Debug)]
+pub struct HttpClientBuilder {
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    http2: bool,
+    #[cfg(feature = "http-client-rustls")]
+    rustls_config: Option<RustlsConfig>,
+    propagate_headers: Vec<HeaderName>,
+    default_headers: HeaderMap,
+}
+impl Default for HttpClientBuilder {
+    #[inline]
+    fn default() -> Self {
+        HttpClientBuilder {
+            connect_timeout: None,
+            timeout: None,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: usize::MAX,
+            http2: true,
+            #[cfg(feature = "http-client-rustls")]
+            rustls_config: None,
+            propagate_headers: DEFAULT_PROPAGATE_HEADERS
+                .iter()
+                .map(|name| HeaderName::from_static(name))
+                .collect(),
+            default_headers: HeaderMap::new(),
+        }
+    }
+}
+impl HttpClientBuilder {
+    /// Create new `HttpClientBuilder`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Sets timeout of connecting to servers, default is no timeout.
+    #[inline]
+    pub fn with_connect_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.connect_timeout = timeout.into();
+        self
+    }
+    /// Sets how long to wait for response headers, default is no timeout.
+    #[inline]
+    pub fn with_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+    /// Sets how long idle pooled connections are kept, default is 90 seconds.
+    #[inline]
+    pub fn with_pool_idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.pool_idle_timeout = timeout.into();
+        self
+    }
+    /// Sets max idle pooled connections per host, default is unlimited.
+    #[inline]
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+    /// Sets whether HTTP/2 is negotiated with TLS servers, default is true.
+    #[inline]
+    pub fn with_http2(mut self, http2: bool) -> Self {
+        self.http2 = http2;
+        self
+    }
+    /// Sets TLS config of outbound connections, built with [`RustlsConfig::build_client_config`].
+    ///
+    /// Its client authentication trust anchor is trusted besides the webpki roots, and its certificate is
+    /// presented to servers which ask for one.
+    #[cfg(feature = "http-client-rustls")]
+    #[inline]
+    pub fn with_rustls_config(mut self, config: RustlsConfig) -> Self {
+        self.rustls_config = Some(config);
+        self
+    }
+    /// Sets headers copied from the inbound request by [`HttpClient::for_request`].
+    ///
+    /// Default is [`DEFAULT_PROPAGATE_HEADERS`].
+    #[inline]
+    pub fn with_propagate_headers<I>(mut self, names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<HeaderName>,
+    {
+        self.propagate_headers = names.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Adds a header sent with every request unless the request sets it.
+    #[inline]
+    pub fn with_default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Build [`HttpClient`], fails if the rustls config is invalid.
+    #[cfg(feature = "http-client-rustls")]
+    pub fn build(mut self) -> Result<HttpClient, Error> {
+        let tls_config = match self.rustls_config.take() {
+            Some(config) => config.build_client_config(webpki_roots()).map_err(Error::other)?,
+            None => default_tls_config(),
+        };
+        Ok(self.build_with(tls_config))
+    }
+    /// Build [`HttpClient`].
+    #[cfg(not(feature = "http-client-rustls"))]
+    pub fn build(self) -> Result<HttpClient, Error> {
+        Ok(self.build_with(default_tls_config()))
+    }
+
+    fn build_with(self, tls_config: rustls::ClientConfig) -> HttpClient {
+        let transport = Transport {
+            tls_config,
+            connect_timeout: self.connect_timeout,
+            pool_idle_timeout: self.pool_idle_timeout,
+            pool_max_idle_per_host: self.pool_max_idle_per_host,
+            http2: self.http2,
+        };
+        HttpClient {
+            inner: Arc::new(ClientInner {
+                client: transport.client(),
+                transport,
+                timeout: self.timeout,
+                propagate_headers: self.propagate_headers,
+            }),
+            headers: self.default_headers,
+        }
+    }
+}
+
+fn webpki_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.add_server_trust_anchors(
+        webpki_roots::TLS_SERVER_ROOTS
+            .0
+            .iter()
+            .map(|ta| OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)),
+    );
+    roots
+}
+
+fn default_tls_config() -> rustls::ClientConfig {
+    rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(webpki_roots())
+        .with_no_client_auth()
+}
+
+/// Connection settings, kept to create clients which differ from a built one.
+#[derive(Clone)]
+struct Transport {
+    tls_config: rustls::ClientConfig,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    http2: bool,
+}
+impl Transport {
+    fn client(&self) -> Client<HttpsConnector<HttpConnector>> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(self.connect_timeout);
+        let builder = HttpsConnectorBuilder::new()
+            .with_tls_config(self.tls_config.clone())
+            .https_or_http()
+            .enable_http1();
+        let connector = if self.http2 {
+            builder.enable_http2().wrap_connector(http)
+        } else {
+            builder.wrap_connector(http)
+        };
+        Client::builder()
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .build(connector)
+    }
+}
+
+struct ClientInner {
+    client: Client<HttpsConnector<HttpConnector>>,
+    transport: Transport,
+    timeout: Option<Duration>,
+    propagate_headers: Vec<HeaderName>,
+}
+
+/// Outbound HTTP client with connection pooling, timeouts and header propagation.
+///
+/// Every request is logged in a span like inbound requests of [`LogHandler`](crate::logging::LogHandler).
+#[derive(Clone)]
+pub struct HttpClient {
+    inner: Arc<ClientInner>,
+    headers: HeaderMap,
+}
+impl fmt::Debug for HttpClient {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("connect_timeout", &self.inner.transport.connect_timeout)
+            .field("timeout", &self.inner.timeout)
+            .field("propagate_headers", &self.inner.propagate_headers)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+impl Default for HttpClient {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl HttpClient {
+    /// Create new `HttpClient` with default settings.
+    ///
+    /// It can not fail because only a rustls config set on the builder may be invalid.
+    #[inline]
+    pub fn new() -> Self {
+        HttpClientBuilder::new().build_with(default_tls_config())
+    }
+    /// Create new [`HttpClientBuilder`].
+    #[inline]
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::new()
+    }
+
+    /// Get the underlying hyper client.
+    #[inline]
+    pub fn hyper_client(&self) -> &Client<HttpsConnector<HttpConnector>> {
+        &self.inner.client
+    }
+    /// Get connect timeout.
+    #[inline]
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.inner.transport.connect_timeout
+    }
+    /// Get response timeout.
+    #[inline]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout
+    }
+    /// Get headers added to every request of this client.
+    #[inline]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns a client with the same settings and headers which uses `timeout` to connect to servers.
+    ///
+    /// The returned client has its own connection pool.
+    pub fn cloned_with_connect_timeout(&self, timeout: impl Into<Option<Duration>>) -> HttpClient {
+        let mut transport = self.inner.transport.clone();
+        transport.connect_timeout = timeout.into();
+        HttpClient {
+            inner: Arc::new(ClientInner {
+                client: transport.client(),
+                transport,
+                timeout: self.inner.timeout,
+                propagate_headers: self.inner.propagate_headers.clone(),
+            }),
+            headers: self.headers.clone(),
+        }
+    }
+
+    /// Returns a client sharing the connection pool which sends the propagated headers of `req`
+    /// with every request.
+    pub fn for_request(&self, req: &Request) -> HttpClient {
+        let mut client = self.clone();
+        for name in &self.inner.propagate_headers {
+            if let Some(value) = req.headers().get(name) {
+                client.headers.insert(name.clone(), value.clone());
+            }
+        }
+        client
+    }
+
+    /// Send a `GET` request.
+    pub async fn get<U>(&self, uri: U) -> Result<hyper::Response<Body>, HttpClientError>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<hyper::http::Error>,
+    {
+        let req = hyper::Request::get(uri)
+            .body(Body::empty())
+            .map_err(HttpClientError::Http)?;
+        self.request(req).await
+    }
+
+    /// Send a request.
+    pub async fn request(&self, mut req: hyper::Request<Body>) -> Result<hyper::Response<Body>, HttpClientError> {
+        for (name, value) in &self.headers {
+            if !req.headers().contains_key(name) {
+                req.headers_mut().insert(name.clone(), value.clone());
+            }
+        }
+        let span = tracing::span!(
+            Level::DEBUG,
+            "OutboundRequest",
+            method = %req.method(),
+            uri = %req.uri(),
+        );
+        let client = &self.inner.client;
+        let timeout = self.inner.timeout;
+        async move {
+            let now = Instant::now();
+            let result = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, client.request(req)).await {
+                    Ok(result) => result.map_err(HttpClientError::Hyper),
+                    Err(_) => Err(HttpClientError::Timeout),
+                },
+                None => client.request(req).await.map_err(HttpClientError::Hyper),
+            };
+            let duration = now.elapsed();
+            match &result {
+                Ok(res) => tracing::debug!(status = %res.status(), duration = ?duration, "Response"),
+                Err(e) => tracing::warn!(error = %e, duration = ?duration, "Request failed"),
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use hyper::service::{make_service_fn, service_fn};
+    use salvo_core::http::header::HeaderValue;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_http_client() {
+        // Echoes the request id header.
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: hyper::Request<Body>| async move {
+                let id = req
+                    .headers()
+                    .get("x-request-id")
+                    .map(|v| v.to_str().unwrap().to_owned())
+                    .unwrap_or_default();
+                Ok::<_, Infallible>(hyper::Response::new(Body::from(id)))
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}/", server.local_addr());
+        tokio::spawn(server);
+
+        let client = HttpClient::builder()
+            .with_timeout(Duration::from_secs(5))
+            .with_default_header(HeaderName::from_static("user-agent"), HeaderValue::from_static("salvo"))
+            .build()
+            .unwrap();
+        let mut req = Request::new();
+        req.headers_mut()
+            .insert("x-request-id", HeaderValue::from_static("abc"));
+        req.headers_mut().insert("x-other", HeaderValue::from_static("1"));
+        let scoped = client.for_request(&req);
+        assert_eq!(scoped.headers().len(), 2);
+        assert!(client.headers().get("x-request-id").is_none());
+
+        let res = scoped.get(url.as_str()).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"abc");
+    }
+
+    #[tokio::test]
+    async fn test_http_client_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        let client = HttpClient::builder()
+            .with_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        assert!(matches!(client.get(url.as_str()).await, Err(HttpClientError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_http_client_invalid_uri() {
+        let client = HttpClient::new();
+        assert!(matches!(
+            client.get("http://invalid host/").await,
+            Err(HttpClientError::Http(_))
+        ));
+    }
+}
@@ -0,0 +1,458 @@
+//! IP filter middleware, allow or deny requests by client address.
+//!
+//! # Example
+//!
+//! ```
+//! use salvo_core::prelude::*;
+//! use salvo_extra::ip_filter::IpFilterHandler;
+//!
+//! #[handler]
+//! async fn admin() -> &'static str {
+//!     "admin"
+//! }
+//!
+//! # fn main() -> Result<(), salvo_extra::ip_filter::IpNetParseError> {
+//! let filter = IpFilterHandler::new()
+//!     .with_allow(["10.8.0.0/16", "fd00::/8"])?
+//!     .with_deny(["10.8.13.0/24"])?;
+//! let router = Router::with_path("admin").hoop(filter.clone()).get(admin);
+//!
+//! // Later, e.g. from an admin endpoint, swap the lists without a redeploy.
+//! // An invalid network is reported and the current list is kept.
+//! filter.set_allow(["10.9.0.0/16"])?;
+//! assert!(filter.set_allow(["10.9.0.0/33"]).is_err());
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt::{self, Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use salvo_core::addr::SocketAddr;
+use salvo_core::async_trait;
+use salvo_core::http::{Request, Response, StatusError};
+use salvo_core::routing::FlowCtrl;
+use salvo_core::{Depot, Handler};
+
+/// An IPv4 or IPv6 network in CIDR notation, such as `192.168.0.0/16` or `fe80::/10`.
+///
+/// A bare address is parsed as a network with the full prefix length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Create a new `IpNet`, host bits of `addr` are cleared.
+    ///
+    /// IPv4-mapped IPv6 networks (`::ffff:a.b.c.d/96` and longer) become the matching IPv4 network, like addresses
+    /// in [`IpNet::contains`]. Returns `None` if `prefix` is longer than the address.
+    #[inline]
+    pub fn new(addr: IpAddr, prefix: u8) -> Option<Self> {
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix > max {
+            return None;
+        }
+        let (addr, prefix) = match normalize(addr) {
+            IpAddr::V4(v4) if addr.is_ipv6() && prefix >= 96 => (IpAddr::V4(v4), prefix - 96),
+            _ => (addr, prefix),
+        };
+        let addr = match addr {
+            IpAddr::V4(addr) => IpAddr::V4(Ipv4Addr::from(u32::from(addr) & v4_mask(prefix))),
+            IpAddr::V6(addr) => IpAddr::V6(Ipv6Addr::from(u128::from(addr) & v6_mask(prefix))),
+        };
+        Some(IpNet { addr, prefix })
+    }
+    /// Get network address.
+    #[inline]
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+    /// Get prefix length.
+    #[inline]
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+    /// Returns `true` if `ip` is inside this network.
+    ///
+    /// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) are matched against IPv4 networks.
+    #[inline]
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, normalize(*ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => u32::from(ip) & v4_mask(self.prefix) == u32::from(net),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => u128::from(ip) & v6_mask(self.prefix) == u128::from(net),
+            _ => false,
+        }
+    }
+}
+
+#[inline]
+fn v4_mask(prefix: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0)
+}
+#[inline]
+fn v6_mask(prefix: u8) -> u128 {
+    u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0)
+}
+#[inline]
+fn normalize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => IpAddr::V6(v6),
+        },
+        ip => ip,
+    }
+}
+
+/// Error returned when parsing an [`IpNet`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpNetParseError(String);
+
+impl Display for IpNetParseError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid ip network: {}", self.0)
+    }
+}
+impl std::error::Error for IpNetParseError {}
+
+impl FromStr for IpNet {
+    type Err = IpNetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || IpNetParseError(s.to_owned());
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr.trim().parse::<IpAddr>().map_err(|_| err())?;
+                let prefix = prefix.trim().parse::<u8>().map_err(|_| err())?;
+                IpNet::new(addr, prefix).ok_or_else(err)
+            }
+            None => {
+                let addr = s.trim().parse::<IpAddr>().map_err(|_| err())?;
+                let prefix = if addr.is_ipv4() { 32 } else { 128 };
+                IpNet::new(addr, prefix).ok_or_else(err)
+            }
+        }
+    }
+}
+impl Display for IpNet {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+impl From<IpAddr> for IpNet {
+    #[inline]
+    fn from(addr: IpAddr) -> Self {
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        IpNet { addr, prefix }
+    }
+}
+
+/// Conversion into an [`IpNet`], used by the list setters of [`IpFilterHandler`].
+pub trait IntoIpNet {
+    /// Convert into `IpNet`, returns an error if the value is not a valid network.
+    fn into_ip_net(self) -> Result<IpNet, IpNetParseError>;
+}
+impl IntoIpNet for IpNet {
+    #[inline]
+    fn into_ip_net(self) -> Result<IpNet, IpNetParseError> {
+        Ok(self)
+    }
+}
+impl IntoIpNet for IpAddr {
+    #[inline]
+    fn into_ip_net(self) -> Result<IpNet, IpNetParseError> {
+        Ok(self.into())
+    }
+}
+impl IntoIpNet for &str {
+    #[inline]
+    fn into_ip_net(self) -> Result<IpNet, IpNetParseError> {
+        self.parse()
+    }
+}
+impl IntoIpNet for String {
+    #[inline]
+    fn into_ip_net(self) -> Result<IpNet, IpNetParseError> {
+        self.parse()
+    }
+}
+
+/// Converts all `nets`, returns the first error if any of them is not a valid network.
+pub(crate) fn collect_ip_nets<I>(nets: I) -> Result<Vec<IpNet>, IpNetParseError>
+where
+    I: IntoIterator,
+    I::Item: IntoIpNet,
+{
+    nets.into_iter().map(IntoIpNet::into_ip_net).collect()
+}
+
+/// Which list wins when an address matches both the allow and the deny list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precedence {
+    /// A match in the deny list rejects the request even if the allow list matches too.
+    DenyFirst,
+    /// A match in the allow list accepts the request even if the deny list matches too.
+    AllowFirst,
+}
+impl Default for Precedence {
+    #[inline]
+    fn default() -> Self {
+        Precedence::DenyFirst
+    }
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+    precedence: Precedence,
+    allow_unknown: bool,
+}
+
+impl Rules {
+    fn is_allowed(&self, ip: &IpAddr) -> bool {
+        let allowed = || self.allow.iter().any(|net| net.contains(ip));
+        let denied = || self.deny.iter().any(|net| net.contains(ip));
+        match self.precedence {
+            Precedence::DenyFirst => !denied() && (self.allow.is_empty() || allowed()),
+            Precedence::AllowFirst => allowed() || (!denied() && self.allow.is_empty()),
+        }
+    }
+}
+
+/// IpFilterHandler
+///
+/// Checks `Request::remote_addr` against the allow and deny lists and responds with
+/// `403 Forbidden` when the address is rejected. An empty allow list allows every address
+/// which is not denied.
+///
+/// Clones share the same lists, so keep a clone around and call the `set_*` methods to
+/// swap the lists while the server is running.
+#[derive(Clone, Debug, Default)]
+pub struct IpFilterHandler {
+    rules: Arc<RwLock<Rules>>,
+}
+
+impl IpFilterHandler {
+    /// Create a new `IpFilterHandler` which allows every IP address.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Get allow list.
+    #[inline]
+    pub fn allow(&self) -> Vec<IpNet> {
+        self.rules.read().allow.clone()
+    }
+    /// Replace allow list.
+    ///
+    /// Returns an error and keeps the current list if any of `nets` is not a valid network.
+    #[inline]
+    pub fn set_allow<I>(&self, nets: I) -> Result<(), IpNetParseError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIpNet,
+    {
+        let nets = collect_ip_nets(nets)?;
+        self.rules.write().allow = nets;
+        Ok(())
+    }
+    /// Sets allow list and returns Self.
+    ///
+    /// Returns an error if any of `nets` is not a valid network.
+    #[inline]
+    pub fn with_allow<I>(self, nets: I) -> Result<Self, IpNetParseError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIpNet,
+    {
+        self.set_allow(nets)?;
+        Ok(self)
+    }
+
+    /// Get deny list.
+    #[inline]
+    pub fn deny(&self) -> Vec<IpNet> {
+        self.rules.read().deny.clone()
+    }
+    /// Replace deny list.
+    ///
+    /// Returns an error and keeps the current list if any of `nets` is not a valid network.
+    #[inline]
+    pub fn set_deny<I>(&self, nets: I) -> Result<(), IpNetParseError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIpNet,
+    {
+        let nets = collect_ip_nets(nets)?;
+        self.rules.write().deny = nets;
+        Ok(())
+    }
+    /// Sets deny list and returns Self.
+    ///
+    /// Returns an error if any of `nets` is not a valid network.
+    #[inline]
+    pub fn with_deny<I>(self, nets: I) -> Result<Self, IpNetParseError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIpNet,
+    {
+        self.set_deny(nets)?;
+        Ok(self)
+    }
+
+    /// Get precedence, default is [`Precedence::DenyFirst`].
+    #[inline]
+    pub fn precedence(&self) -> Precedence {
+        self.rules.read().precedence
+    }
+    /// Set precedence.
+    #[inline]
+    pub fn set_precedence(&self, precedence: Precedence) {
+        self.rules.write().precedence = precedence;
+    }
+    /// Sets precedence and returns Self.
+    #[inline]
+    pub fn with_precedence(self, precedence: Precedence) -> Self {
+        self.set_precedence(precedence);
+        self
+    }
+
+    /// Get whether requests without an IP address are allowed, default is `false`.
+    ///
+    /// This covers connections accepted by a `UnixListener` and requests without remote address.
+    #[inline]
+    pub fn allow_unknown(&self) -> bool {
+        self.rules.read().allow_unknown
+    }
+    /// Set whether requests without an IP address are allowed.
+    #[inline]
+    pub fn set_allow_unknown(&self, allow: bool) {
+        self.rules.write().allow_unknown = allow;
+    }
+    /// Sets whether requests without an IP address are allowed and returns Self.
+    #[inline]
+    pub fn with_allow_unknown(self, allow: bool) -> Self {
+        self.set_allow_unknown(allow);
+        self
+    }
+
+    /// Returns `true` if a request from `ip` passes the filter.
+    #[inline]
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        self.rules.read().is_allowed(ip)
+    }
+}
+
+#[async_trait]
+impl Handler for IpFilterHandler {
+    #[inline]
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let allowed = match req.remote_addr() {
+            Some(SocketAddr::IPv4(addr)) => self.is_allowed(&IpAddr::V4(*addr.ip())),
+            Some(SocketAddr::IPv6(addr)) => self.is_allowed(&IpAddr::V6(*addr.ip())),
+            #[cfg(unix)]
+            Some(SocketAddr::Unix(_)) => self.allow_unknown(),
+            None => self.allow_unknown(),
+        };
+        if allowed {
+            ctrl.call_next(req, depot, res).await;
+        } else {
+            res.set_status_error(StatusError::forbidden());
+            ctrl.skip_rest();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::prelude::*;
+    use salvo_core::test::TestClient;
+
+    use super::*;
+
+    #[test]
+    fn test_ip_net_contains() {
+        let net: IpNet = "10.1.2.3/16".parse().unwrap();
+        assert_eq!(net.to_string(), "10.1.0.0/16");
+        assert!(net.contains(&"10.1.255.1".parse().unwrap()));
+        assert!(!net.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(net.contains(&"::ffff:10.1.0.9".parse().unwrap()));
+
+        let net: IpNet = "fd00::/8".parse().unwrap();
+        assert!(net.contains(&"fd12:3456::1".parse().unwrap()));
+        assert!(!net.contains(&"fe80::1".parse().unwrap()));
+
+        let any: IpNet = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(&"8.8.8.8".parse().unwrap()));
+        let host: IpNet = "127.0.0.1".parse().unwrap();
+        assert_eq!(host.prefix(), 32);
+
+        let mapped: IpNet = "::ffff:10.0.0.0/104".parse().unwrap();
+        assert_eq!(mapped.to_string(), "10.0.0.0/8");
+        assert!(mapped.contains(&"10.3.0.1".parse().unwrap()));
+        assert!(mapped.contains(&"::ffff:10.3.0.1".parse().unwrap()));
+        assert!(!mapped.contains(&"11.0.0.1".parse().unwrap()));
+        let host: IpNet = "::ffff:127.0.0.1".parse().unwrap();
+        assert_eq!(host.to_string(), "127.0.0.1/32");
+
+        assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+        assert!("10.0.0/8".parse::<IpNet>().is_err());
+    }
+
+    #[test]
+    fn test_precedence() {
+        let filter = IpFilterHandler::new()
+            .with_allow(["10.0.0.0/8"])
+            .unwrap()
+            .with_deny(["10.0.13.0/24"])
+            .unwrap();
+        assert!(filter.is_allowed(&"10.0.0.1".parse().unwrap()));
+        assert!(!filter.is_allowed(&"10.0.13.1".parse().unwrap()));
+        assert!(!filter.is_allowed(&"192.168.0.1".parse().unwrap()));
+
+        filter.set_precedence(Precedence::AllowFirst);
+        assert!(filter.is_allowed(&"10.0.13.1".parse().unwrap()));
+        assert!(!filter.is_allowed(&"192.168.0.1".parse().unwrap()));
+
+        filter.set_allow(Vec::<IpNet>::new()).unwrap();
+        assert!(!filter.is_allowed(&"10.0.13.1".parse().unwrap()));
+        assert!(filter.is_allowed(&"192.168.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_invalid_network() {
+        let filter = IpFilterHandler::new().with_deny(["10.0.13.0/24"]).unwrap();
+        let err = filter.set_deny(["10.0.0.0/8", "10.0.0.0/33"]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid ip network: 10.0.0.0/33");
+        assert!(filter.set_allow(vec!["not an ip".to_owned()]).is_err());
+        assert_eq!(filter.deny(), vec!["10.0.13.0/24".parse::<IpNet>().unwrap()]);
+        assert!(filter.allow().is_empty());
+        assert!(IpFilterHandler::new().with_allow(["fd00::/129"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ip_filter_handler() {
+        #[handler]
+        async fn hello() -> &'static str {
+            "hello"
+        }
+        let filter = IpFilterHandler::new().with_allow(["127.0.0.0/8"]).unwrap();
+        let router = Router::new().hoop(filter.clone()).get(hello);
+        let service = Service::new(router);
+
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::FORBIDDEN);
+
+        filter.set_allow_unknown(true);
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::OK);
+    }
+}
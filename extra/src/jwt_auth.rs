@@ -11,14 +11,18 @@ use salvo_core::async_trait;
 use salvo_core::http::header::AUTHORIZATION;
 use salvo_core::http::{Method, Request, Response, StatusError};
 use salvo_core::routing::FlowCtrl;
-use salvo_core::{Depot, Handler};
+use salvo_core::{Depot, DepotError, DepotKey, Handler};
 
-/// key used to insert auth decoded data to depot.
-pub const AUTH_DATA_KEY: &str = "::salvo::extra::jwt_auth::auth_data";
 /// key used to insert auth state data to depot.
-pub const AUTH_STATE_KEY: &str = "::salvo::extra::jwt_auth::auth_state";
+pub const AUTH_STATE_KEY: DepotKey<JwtAuthState> = DepotKey::new("::salvo::extra::jwt_auth::auth_state");
 /// key used to insert auth token data to depot.
-pub const AUTH_TOKEN_KEY: &str = "::salvo::extra::jwt_auth::auth_token";
+pub const AUTH_TOKEN_KEY: DepotKey<String> = DepotKey::new("::salvo::extra::jwt_auth::auth_token");
+
+/// Returns the key used to insert auth decoded data with claims `C` to depot.
+#[inline]
+pub const fn auth_data_key<C>() -> DepotKey<TokenData<C>> {
+    DepotKey::new("::salvo::extra::jwt_auth::auth_data")
+}
 
 static ALL_METHODS: Lazy<Vec<Method>> = Lazy::new(|| {
     vec![
@@ -256,32 +260,36 @@ pub enum JwtAuthState {
 /// JwtAuthDepotExt
 pub trait JwtAuthDepotExt {
     /// get jwt auth token reference from depot.
-    fn jwt_auth_token(&self) -> Option<&String>;
+    fn jwt_auth_token(&self) -> Result<&String, DepotError>;
     /// get jwt auth decoded data from depot.
-    fn jwt_auth_data<C>(&self) -> Option<&TokenData<C>>
+    ///
+    /// Returns [`DepotError::TypeMismatch`] if the data is decoded with claims of another type.
+    fn jwt_auth_data<C>(&self) -> Result<&TokenData<C>, DepotError>
     where
         C: DeserializeOwned + Send + Sync + 'static;
-    /// get jwt auth state from depot.
+    /// get jwt auth state from depot, returns [`JwtAuthState::Unauthorized`] if there is no state.
     fn jwt_auth_state(&self) -> JwtAuthState;
 }
 
 impl JwtAuthDepotExt for Depot {
     #[inline]
-    fn jwt_auth_token(&self) -> Option<&String> {
-        self.get(AUTH_TOKEN_KEY)
+    fn jwt_auth_token(&self) -> Result<&String, DepotError> {
+        self.fetch(AUTH_TOKEN_KEY)
     }
 
     #[inline]
-    fn jwt_auth_data<C>(&self) -> Option<&TokenData<C>>
+    fn jwt_auth_data<C>(&self) -> Result<&TokenData<C>, DepotError>
     where
         C: DeserializeOwned + Send + Sync + 'static,
     {
-        self.get(AUTH_DATA_KEY)
+        self.fetch(auth_data_key::<C>())
     }
 
     #[inline]
     fn jwt_auth_state(&self) -> JwtAuthState {
-        self.get(AUTH_STATE_KEY).cloned().unwrap_or(JwtAuthState::Unauthorized)
+        self.fetch(AUTH_STATE_KEY)
+            .cloned()
+            .unwrap_or(JwtAuthState::Unauthorized)
     }
 }
 
@@ -374,23 +382,36 @@ where
     async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
         for extractor in &self.extractors {
             if let Some(token) = extractor.token(req).await {
-                if let Ok(data) = self.decode(&token) {
-                    depot.insert(AUTH_DATA_KEY, data);
-                    depot.insert(AUTH_STATE_KEY, JwtAuthState::Authorized);
-                } else {
-                    depot.insert(AUTH_STATE_KEY, JwtAuthState::Forbidden);
-                    if self.response_error {
+                let state = match self.decode(&token) {
+                    Ok(data) => depot.put(auth_data_key::<C>(), data).map(|_| JwtAuthState::Authorized),
+                    Err(_) => Ok(JwtAuthState::Forbidden),
+                };
+                let stored = state.and_then(|state| {
+                    depot.put(AUTH_STATE_KEY, state)?;
+                    depot.put(AUTH_TOKEN_KEY, token)?;
+                    Ok(state)
+                });
+                match stored {
+                    Ok(JwtAuthState::Forbidden) if self.response_error => {
                         res.set_status_error(StatusError::forbidden());
                         ctrl.skip_rest();
                     }
+                    Err(e) => {
+                        tracing::error!(error = ?e, "unable to put jwt auth data to depot");
+                        res.set_status_error(StatusError::internal_server_error());
+                        ctrl.skip_rest();
+                    }
+                    _ => {}
                 }
-                depot.insert(AUTH_TOKEN_KEY, token);
                 ctrl.call_next(req, depot, res).await;
                 return;
             }
         }
-        depot.insert(AUTH_STATE_KEY, JwtAuthState::Unauthorized);
-        if self.response_error {
+        if let Err(e) = depot.put(AUTH_STATE_KEY, JwtAuthState::Unauthorized) {
+            tracing::error!(error = ?e, "unable to put jwt auth state to depot");
+            res.set_status_error(StatusError::internal_server_error());
+            ctrl.skip_rest();
+        } else if self.response_error {
             res.set_status_error(StatusError::unauthorized());
             ctrl.skip_rest();
         } else {
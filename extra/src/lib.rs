@@ -28,6 +28,10 @@ cfg_feature! {
     #![feature = "compression"]
     pub mod compression;
 }
+cfg_feature! {
+    #![feature = "concurrency-limiter"]
+    pub mod concurrency_limiter;
+}
 cfg_feature! {
     #![feature = "cors"]
     pub mod cors;
@@ -36,6 +40,18 @@ cfg_feature! {
     #![feature = "csrf"]
     pub mod csrf;
 }
+cfg_feature! {
+    #![feature = "force-https"]
+    pub mod force_https;
+}
+cfg_feature! {
+    #![feature = "http-client"]
+    pub mod http_client;
+}
+cfg_feature! {
+    #![feature = "ip-filter"]
+    pub mod ip_filter;
+}
 cfg_feature! {
     #![feature = "logging"]
     pub mod logging;
@@ -44,6 +60,10 @@ cfg_feature! {
     #![feature = "proxy"]
     pub mod proxy;
 }
+cfg_feature! {
+    #![feature = "security-headers"]
+    pub mod security_headers;
+}
 cfg_feature! {
     #![feature = "serve-static"]
     pub mod serve_static;
@@ -0,0 +1,224 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// State of an upstream circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent to the upstream.
+    Closed,
+    /// The upstream is failing, requests are rejected without contacting it.
+    Open,
+    /// The open timeout passed, a limited number of trial requests are sent to the upstream.
+    HalfOpen,
+}
+
+/// Circuit breaker settings of an [`UpstreamPool`](super::UpstreamPool).
+///
+/// The circuit of an upstream opens after `failure_threshold` consecutive failed requests. After
+/// `open_timeout` it becomes half-open and lets `half_open_requests` trial requests through: it
+/// closes again if they succeed and opens again if one of them fails.
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: usize,
+    open_timeout: Duration,
+    half_open_requests: usize,
+}
+impl Default for CircuitBreaker {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl CircuitBreaker {
+    /// Create new `CircuitBreaker` which opens after 5 failures for 30 seconds and allows 1 trial request.
+    #[inline]
+    pub fn new() -> Self {
+        CircuitBreaker {
+            failure_threshold: 5,
+            open_timeout: Duration::from_secs(30),
+            half_open_requests: 1,
+        }
+    }
+    /// Get failure threshold.
+    #[inline]
+    pub fn failure_threshold(&self) -> usize {
+        self.failure_threshold
+    }
+    /// Sets how many consecutive failures open the circuit and returns Self.
+    #[inline]
+    pub fn with_failure_threshold(mut self, failure_threshold: usize) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+    /// Get open timeout.
+    #[inline]
+    pub fn open_timeout(&self) -> Duration {
+        self.open_timeout
+    }
+    /// Sets how long the circuit stays open before trial requests are allowed and returns Self.
+    #[inline]
+    pub fn with_open_timeout(mut self, open_timeout: Duration) -> Self {
+        self.open_timeout = open_timeout;
+        self
+    }
+    /// Get max concurrent trial requests in half-open state.
+    #[inline]
+    pub fn half_open_requests(&self) -> usize {
+        self.half_open_requests
+    }
+    /// Sets max concurrent trial requests in half-open state and returns Self.
+    #[inline]
+    pub fn with_half_open_requests(mut self, half_open_requests: usize) -> Self {
+        self.half_open_requests = half_open_requests.max(1);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct CircuitInner {
+    state: CircuitState,
+    failures: usize,
+    opened_at: Option<Instant>,
+    trials: usize,
+}
+
+/// Circuit of a single upstream.
+#[derive(Debug)]
+pub(crate) struct Circuit {
+    inner: Mutex<CircuitInner>,
+}
+impl Default for Circuit {
+    #[inline]
+    fn default() -> Self {
+        Circuit {
+            inner: Mutex::new(CircuitInner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: None,
+                trials: 0,
+            }),
+        }
+    }
+}
+impl Circuit {
+    #[inline]
+    pub(crate) fn state(&self) -> CircuitState {
+        self.inner.lock().state
+    }
+
+    /// Returns `true` if a request may be sent, without taking a trial slot.
+    pub(crate) fn is_allowed(&self, breaker: &CircuitBreaker) -> bool {
+        let inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => inner
+                .opened_at
+                .map(|at| at.elapsed() >= breaker.open_timeout)
+                .unwrap_or(true),
+            CircuitState::HalfOpen => inner.trials < breaker.half_open_requests,
+        }
+    }
+
+    /// Takes permission to send a request, every successful call must be followed by [`Circuit::record`]
+    /// or [`Circuit::release`].
+    pub(crate) fn acquire(&self, breaker: &CircuitBreaker) -> bool {
+        let mut inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => {
+                if inner
+                    .opened_at
+                    .map(|at| at.elapsed() >= breaker.open_timeout)
+                    .unwrap_or(true)
+                {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.trials = 1;
+                    true
+                } else {
+                    false
+                }
+            }
+            CircuitState::HalfOpen => {
+                if inner.trials < breaker.half_open_requests {
+                    inner.trials += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Gives back permission taken by [`Circuit::acquire`] when no request was sent.
+    pub(crate) fn release(&self) {
+        let mut inner = self.inner.lock();
+        if inner.state == CircuitState::HalfOpen {
+            inner.trials = inner.trials.saturating_sub(1);
+        }
+    }
+
+    /// Records result of a request, returns the new state if it changed.
+    pub(crate) fn record(&self, breaker: &CircuitBreaker, success: bool) -> Option<CircuitState> {
+        let mut inner = self.inner.lock();
+        let old_state = inner.state;
+        match (inner.state, success) {
+            (CircuitState::Closed, true) => inner.failures = 0,
+            (CircuitState::Closed, false) => {
+                inner.failures += 1;
+                if inner.failures >= breaker.failure_threshold {
+                    inner.state = CircuitState::Open;
+                    inner.opened_at = Some(Instant::now());
+                }
+            }
+            (CircuitState::HalfOpen, true) => {
+                inner.state = CircuitState::Closed;
+                inner.failures = 0;
+                inner.trials = 0;
+            }
+            (CircuitState::HalfOpen, false) => {
+                inner.state = CircuitState::Open;
+                inner.opened_at = Some(Instant::now());
+                inner.trials = 0;
+            }
+            // Late results of requests sent before the circuit opened.
+            (CircuitState::Open, _) => {}
+        }
+        if inner.state != old_state {
+            Some(inner.state)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(2)
+            .with_open_timeout(Duration::from_millis(20));
+        let circuit = Circuit::default();
+        assert!(circuit.acquire(&breaker));
+        assert_eq!(circuit.record(&breaker, false), None);
+        assert!(circuit.acquire(&breaker));
+        assert_eq!(circuit.record(&breaker, false), Some(CircuitState::Open));
+        assert!(!circuit.is_allowed(&breaker));
+        assert!(!circuit.acquire(&breaker));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(circuit.is_allowed(&breaker));
+        assert!(circuit.acquire(&breaker));
+        assert_eq!(circuit.state(), CircuitState::HalfOpen);
+        assert!(!circuit.acquire(&breaker));
+        assert_eq!(circuit.record(&breaker, false), Some(CircuitState::Open));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(circuit.acquire(&breaker));
+        assert_eq!(circuit.record(&breaker, true), Some(CircuitState::Closed));
+        assert!(circuit.acquire(&breaker));
+    }
+}
@@ -0,0 +1,198 @@
+use salvo_core::addr::SocketAddr;
+use salvo_core::http::header::{self, HeaderName, HeaderValue};
+use salvo_core::http::version::Version;
+use salvo_core::http::HeaderMap;
+use salvo_core::Request;
+
+/// Headers which are meaningful only for a single transport-level connection, see RFC 7230 section 6.1.
+pub(crate) const HOP_BY_HOP_HEADERS: [HeaderName; 9] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    HeaderName::from_static("proxy-connection"),
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Which forwarding headers [`ProxyHandler`](super::ProxyHandler) adds to proxied requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForwardedHeaders {
+    /// Adds no forwarding header.
+    None,
+    /// Adds `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host`.
+    XForwarded,
+    /// Adds the standard `Forwarded` header of RFC 7239.
+    Forwarded,
+    /// Adds both `Forwarded` and `X-Forwarded-*` headers.
+    Both,
+}
+impl Default for ForwardedHeaders {
+    #[inline]
+    fn default() -> Self {
+        ForwardedHeaders::None
+    }
+}
+impl ForwardedHeaders {
+    #[inline]
+    fn x_forwarded(self) -> bool {
+        matches!(self, ForwardedHeaders::XForwarded | ForwardedHeaders::Both)
+    }
+    #[inline]
+    fn forwarded(self) -> bool {
+        matches!(self, ForwardedHeaders::Forwarded | ForwardedHeaders::Both)
+    }
+}
+
+/// Removes hop-by-hop headers, including those listed in the `Connection` header.
+pub(crate) fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let listed = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+    for name in listed.iter().chain(HOP_BY_HOP_HEADERS.iter()) {
+        headers.remove(name);
+    }
+}
+
+/// Returns value of the `Upgrade` header if the `Connection` header asks for an upgrade.
+pub(crate) fn upgrade_protocol(headers: &HeaderMap) -> Option<HeaderValue> {
+    let upgrade = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if upgrade {
+        headers.get(header::UPGRADE).cloned()
+    } else {
+        None
+    }
+}
+
+/// Restores the upgrade headers removed as hop-by-hop headers.
+pub(crate) fn set_upgrade(headers: &mut HeaderMap, protocol: HeaderValue) {
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(header::UPGRADE, protocol);
+}
+
+/// Appends `value` to the header as a new list member.
+fn append_list(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+    let value = match headers.get(&name).and_then(|v| v.to_str().ok()) {
+        Some(existing) if !existing.is_empty() => format!("{}, {}", existing, value),
+        _ => value.to_owned(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.insert(name, value);
+    }
+}
+
+/// Adds forwarding headers describing the client request.
+pub(crate) fn add_forwarded(headers: &mut HeaderMap, req: &Request, mode: ForwardedHeaders) {
+    if mode == ForwardedHeaders::None {
+        return;
+    }
+    let client_ip = match req.remote_addr() {
+        Some(SocketAddr::IPv4(addr)) => Some(addr.ip().to_string()),
+        Some(SocketAddr::IPv6(addr)) => Some(addr.ip().to_string()),
+        _ => None,
+    };
+    let proto = req.scheme().as_str();
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .map(ToOwned::to_owned)
+        .or_else(|| req.uri().authority().map(|a| a.to_string()));
+
+    if mode.x_forwarded() {
+        if let Some(client_ip) = &client_ip {
+            append_list(headers, HeaderName::from_static("x-forwarded-for"), client_ip);
+        }
+        if let Ok(proto) = HeaderValue::from_str(proto) {
+            headers.insert(HeaderName::from_static("x-forwarded-proto"), proto);
+        }
+        if let Some(host) = host.as_deref().and_then(|h| HeaderValue::from_str(h).ok()) {
+            headers.insert(HeaderName::from_static("x-forwarded-host"), host);
+        }
+    }
+    if mode.forwarded() {
+        let node = match &client_ip {
+            Some(ip) if ip.contains(':') => format!("\"[{}]\"", ip),
+            Some(ip) => ip.clone(),
+            None => "unknown".into(),
+        };
+        let mut element = format!("for={};proto={}", node, proto);
+        if let Some(host) = &host {
+            element.push_str(";host=\"");
+            element.push_str(host);
+            element.push('"');
+        }
+        append_list(headers, header::FORWARDED, &element);
+    }
+}
+
+/// Appends a `Via` entry for this proxy.
+pub(crate) fn add_via(headers: &mut HeaderMap, version: Version, pseudonym: &str) {
+    let protocol = match version {
+        Version::HTTP_09 => "0.9",
+        Version::HTTP_10 => "1.0",
+        Version::HTTP_2 => "2",
+        Version::HTTP_3 => "3",
+        _ => "1.1",
+    };
+    append_list(headers, header::VIA, &format!("{} {}", protocol, pseudonym));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_hop_by_hop() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONNECTION, "keep-alive, x-internal".parse().unwrap());
+        headers.insert("keep-alive", "timeout=5".parse().unwrap());
+        headers.insert("x-internal", "1".parse().unwrap());
+        headers.insert(header::TE, "trailers".parse().unwrap());
+        headers.insert(header::PROXY_AUTHORIZATION, "Basic Zm9v".parse().unwrap());
+        headers.insert(header::ACCEPT, "*/*".parse().unwrap());
+        strip_hop_by_hop(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key(header::ACCEPT));
+    }
+
+    #[test]
+    fn test_upgrade_protocol() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::UPGRADE, "websocket".parse().unwrap());
+        assert_eq!(upgrade_protocol(&headers), None);
+        headers.insert(header::CONNECTION, "keep-alive, Upgrade".parse().unwrap());
+        assert_eq!(upgrade_protocol(&headers).unwrap(), "websocket");
+    }
+
+    #[test]
+    fn test_add_forwarded() {
+        let mut req = Request::new();
+        req.headers_mut().insert(header::HOST, "example.com".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "10.0.0.1".parse().unwrap());
+        add_forwarded(&mut headers, &req, ForwardedHeaders::Both);
+        assert_eq!(headers["x-forwarded-for"], "10.0.0.1");
+        assert_eq!(headers["x-forwarded-proto"], "http");
+        assert_eq!(headers["x-forwarded-host"], "example.com");
+        assert_eq!(
+            headers[header::FORWARDED],
+            "for=unknown;proto=http;host=\"example.com\""
+        );
+
+        add_via(&mut headers, Version::HTTP_11, "salvo");
+        add_via(&mut headers, Version::HTTP_2, "edge");
+        assert_eq!(headers[header::VIA], "1.1 salvo, 2 edge");
+    }
+}
@@ -0,0 +1,970 @@
+//! ProxyHandler.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use salvo_core::prelude::*;
+//! use salvo_extra::proxy::{CircuitBreaker, ConsistentHash, HealthCheck, ProxyHandler, Upstream, UpstreamPool};
+//!
+//! let pool = UpstreamPool::new([
+//!     Upstream::new("http://10.0.0.1:8080").with_weight(3),
+//!     Upstream::new("http://10.0.0.2:8080"),
+//! ])
+//! .with_strategy(ConsistentHash::cookie("session"))
+//! .with_health_check(HealthCheck::new("/healthz").with_interval(Duration::from_secs(5)))
+//! .with_circuit_breaker(CircuitBreaker::new());
+//! let proxy = ProxyHandler::from_pool(pool)
+//!     .with_connect_timeout(Duration::from_secs(1))
+//!     .with_response_timeout(Duration::from_secs(10))
+//!     .with_retries(1);
+//! let router = Router::with_path("api/<**rest>").handle(proxy);
+//! ```
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use hyper::body::{Bytes, HttpBody};
+use hyper::upgrade::OnUpgrade;
+use hyper::{Method, Uri};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use salvo_core::async_trait;
+use salvo_core::http::header::{HeaderValue, HOST};
+use salvo_core::http::response::Body;
+use salvo_core::http::HeaderMap;
+use salvo_core::prelude::*;
+use salvo_core::{Error, Result};
+
+use crate::http_client::{HttpClient, HttpClientError};
+
+mod circuit_breaker;
+mod headers;
+mod rewrite;
+mod strategy;
+mod upstream;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use headers::ForwardedHeaders;
+pub use rewrite::{PathRewrite, QueryRewrite};
+pub use strategy::{
+    ConsistentHash, HashKey, LeastConnections, RandomTwoChoices, RoundRobin, Strategy, WeightedRoundRobin,
+};
+use upstream::ConnectionGuard;
+pub use upstream::{HealthCheck, Upstream, UpstreamPermit, UpstreamPool};
+
+type HeadersRewriter = Box<dyn Fn(&Request, &mut HeaderMap) + Send + Sync>;
+type RoutePredicate = Box<dyn Fn(&Request) -> bool + Send + Sync>;
+
+/// ProxyHandler
+///
+/// Hop-by-hop headers are removed from both proxied requests and responses.
+///
+/// Upgrade requests such as WebSocket handshakes are forwarded with their upgrade headers. When the
+/// upstream switches protocols, both upgraded connections are spliced together until either side
+/// closes. Upstreams must speak HTTP/1.1 for upgrades to work.
+///
+/// Connection failures answer with `502 Bad Gateway` and response timeouts with `504 Gateway Timeout`.
+/// Idempotent requests are retried on another upstream when retries are enabled and their body is
+/// not larger than `max_retry_body_size`.
+pub struct ProxyHandler {
+    pool: Arc<UpstreamPool>,
+    client: HttpClient,
+    response_timeout: Option<Duration>,
+    retries: usize,
+    max_retry_body_size: u64,
+    forwarded: ForwardedHeaders,
+    via: Option<String>,
+    preserve_host: bool,
+    request_headers_rewriter: Option<HeadersRewriter>,
+    response_headers_rewriter: Option<HeadersRewriter>,
+    path_rewrites: Vec<PathRewrite>,
+    query_rewrites: Vec<QueryRewrite>,
+    routes: Vec<(RoutePredicate, Arc<UpstreamPool>)>,
+}
+
+impl ProxyHandler {
+    /// Create new `ProxyHandler` with upstreams list, requests are balanced with [`RoundRobin`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `upstreams` is empty.
+    #[inline]
+    pub fn new(upstreams: Vec<String>) -> Self {
+        Self::from_pool(UpstreamPool::new(upstreams))
+    }
+    /// Create new `ProxyHandler` with an [`UpstreamPool`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool has no upstream.
+    pub fn from_pool(pool: UpstreamPool) -> Self {
+        if pool.upstreams().is_empty() {
+            panic!("proxy upstreams is empty");
+        }
+        ProxyHandler {
+            pool: Arc::new(pool),
+            client: HttpClient::new(),
+            response_timeout: None,
+            retries: 0,
+            max_retry_body_size: 64 * 1024,
+            forwarded: ForwardedHeaders::None,
+            via: None,
+            preserve_host: false,
+            request_headers_rewriter: None,
+            response_headers_rewriter: None,
+            path_rewrites: Vec::new(),
+            query_rewrites: Vec::new(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Get upstream pool.
+    #[inline]
+    pub fn pool(&self) -> &UpstreamPool {
+        &self.pool
+    }
+    /// Get upstreams list of the default pool.
+    ///
+    /// Use [`ProxyHandler::with_upstreams`] or [`ProxyHandler::with_pool`] to change upstreams.
+    #[inline]
+    pub fn upstreams(&self) -> &[Arc<Upstream>] {
+        self.pool.upstreams()
+    }
+    /// Replace upstream pool with a [`RoundRobin`] pool of `upstreams` and return Self.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `upstreams` is empty.
+    #[inline]
+    pub fn with_upstreams(self, upstreams: Vec<String>) -> Self {
+        self.with_pool(UpstreamPool::new(upstreams))
+    }
+    /// Replace upstream pool and return Self.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool has no upstream.
+    #[inline]
+    pub fn with_pool(mut self, pool: UpstreamPool) -> Self {
+        if pool.upstreams().is_empty() {
+            panic!("proxy upstreams is empty");
+        }
+        self.pool = Arc::new(pool);
+        self
+    }
+
+    /// Get path rewrite rules.
+    #[inline]
+    pub fn path_rewrites(&self) -> &[PathRewrite] {
+        &self.path_rewrites
+    }
+    /// Adds a path rewrite rule and returns Self.
+    ///
+    /// Without rules, the `<**rest>` param is appended to the upstream url. With rules, they are
+    /// applied in order to the full request path and the result is appended instead.
+    #[inline]
+    pub fn with_path_rewrite(mut self, rule: PathRewrite) -> Self {
+        self.path_rewrites.push(rule);
+        self
+    }
+    /// Get query rewrite rules.
+    #[inline]
+    pub fn query_rewrites(&self) -> &[QueryRewrite] {
+        &self.query_rewrites
+    }
+    /// Adds a query rewrite rule and returns Self, rules are applied in order.
+    #[inline]
+    pub fn with_query_rewrite(mut self, rule: QueryRewrite) -> Self {
+        self.query_rewrites.push(rule);
+        self
+    }
+    /// Sends requests matching `predicate` to `pool` instead of the default pool and returns Self.
+    ///
+    /// Routes are checked in the order they are added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool has no upstream.
+    #[inline]
+    pub fn with_route<F>(mut self, predicate: F, pool: UpstreamPool) -> Self
+    where
+        F: Fn(&Request) -> bool + Send + Sync + 'static,
+    {
+        if pool.upstreams().is_empty() {
+            panic!("proxy upstreams is empty");
+        }
+        self.routes.push((Box::new(predicate), Arc::new(pool)));
+        self
+    }
+
+    /// Get the client used to send proxied requests.
+    #[inline]
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+    /// Sets the client used to send proxied requests and health check probes and returns Self.
+    #[inline]
+    pub fn with_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+    /// Get connect timeout.
+    #[inline]
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.client.connect_timeout()
+    }
+    /// Sets timeout of connecting to upstreams and returns Self, default is the client connect timeout.
+    ///
+    /// It applies to the current client, see [`HttpClient::cloned_with_connect_timeout`]. A client set by
+    /// [`ProxyHandler::with_client`] afterwards replaces it with its own connect timeout.
+    #[inline]
+    pub fn with_connect_timeout(mut self, connect_timeout: impl Into<Option<Duration>>) -> Self {
+        self.client = self.client.cloned_with_connect_timeout(connect_timeout);
+        self
+    }
+    /// Get response timeout.
+    #[inline]
+    pub fn response_timeout(&self) -> Option<Duration> {
+        self.response_timeout
+    }
+    /// Sets how long to wait for upstream response headers and returns Self, default is no timeout.
+    #[inline]
+    pub fn with_response_timeout(mut self, response_timeout: impl Into<Option<Duration>>) -> Self {
+        self.response_timeout = response_timeout.into();
+        self
+    }
+    /// Get max retries.
+    #[inline]
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+    /// Sets how many times an idempotent request is retried on another upstream after a connection
+    /// failure or response timeout and returns Self, default is 0.
+    #[inline]
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+    /// Get max retry body size.
+    #[inline]
+    pub fn max_retry_body_size(&self) -> u64 {
+        self.max_retry_body_size
+    }
+    /// Sets max size of request body buffered for retries and returns Self, default is 64 KiB.
+    ///
+    /// Requests with a larger body or a body of unknown size are not retried.
+    #[inline]
+    pub fn with_max_retry_body_size(mut self, max_retry_body_size: u64) -> Self {
+        self.max_retry_body_size = max_retry_body_size;
+        self
+    }
+
+    /// Get which forwarding headers are added to proxied requests.
+    #[inline]
+    pub fn forwarded(&self) -> ForwardedHeaders {
+        self.forwarded
+    }
+    /// Sets which forwarding headers are added to proxied requests and returns Self, default is none.
+    #[inline]
+    pub fn with_forwarded(mut self, forwarded: ForwardedHeaders) -> Self {
+        self.forwarded = forwarded;
+        self
+    }
+    /// Get pseudonym used in `Via` headers.
+    #[inline]
+    pub fn via(&self) -> Option<&str> {
+        self.via.as_deref()
+    }
+    /// Adds a `Via` entry with `pseudonym` to proxied requests and responses and returns Self.
+    #[inline]
+    pub fn with_via(mut self, pseudonym: impl Into<String>) -> Self {
+        self.via = Some(pseudonym.into());
+        self
+    }
+    /// Get whether the `Host` header of the client request is sent to upstreams.
+    #[inline]
+    pub fn preserve_host(&self) -> bool {
+        self.preserve_host
+    }
+    /// Sets whether the `Host` header of the client request is sent to upstreams instead of the
+    /// upstream host and returns Self, default is false.
+    #[inline]
+    pub fn with_preserve_host(mut self, preserve_host: bool) -> Self {
+        self.preserve_host = preserve_host;
+        self
+    }
+    /// Sets a function which rewrites headers of proxied requests and returns Self.
+    ///
+    /// It is called after hop-by-hop headers are removed and forwarding headers are added.
+    #[inline]
+    pub fn with_request_headers_rewriter<F>(mut self, rewriter: F) -> Self
+    where
+        F: Fn(&Request, &mut HeaderMap) + Send + Sync + 'static,
+    {
+        self.request_headers_rewriter = Some(Box::new(rewriter));
+        self
+    }
+    /// Sets a function which rewrites headers of upstream responses and returns Self.
+    ///
+    /// It is called with the client request after hop-by-hop headers are removed.
+    #[inline]
+    pub fn with_response_headers_rewriter<F>(mut self, rewriter: F) -> Self
+    where
+        F: Fn(&Request, &mut HeaderMap) + Send + Sync + 'static,
+    {
+        self.response_headers_rewriter = Some(Box::new(rewriter));
+        self
+    }
+}
+impl From<UpstreamPool> for ProxyHandler {
+    #[inline]
+    fn from(pool: UpstreamPool) -> Self {
+        ProxyHandler::from_pool(pool)
+    }
+}
+impl ProxyHandler {
+    fn build_proxied_request(
+        &self,
+        req: &mut Request,
+        upstream: &str,
+        body: hyper::Body,
+    ) -> Result<hyper::Request<hyper::body::Body>> {
+        if upstream.is_empty() {
+            tracing::error!("upstreams is empty");
+            return Err(Error::other("upstreams is empty"));
+        }
+
+        // An upstream url with `<name>` placeholders is a template, the path is not appended to it.
+        let templated = upstream.contains('<');
+        let base = if templated {
+            rewrite::render_template(upstream, req.params())
+        } else {
+            upstream.to_owned()
+        };
+        let path = if !self.path_rewrites.is_empty() {
+            self.path_rewrites
+                .iter()
+                .fold(req.uri().path().to_owned(), |path, rule| {
+                    rule.apply(&path, req.params())
+                })
+        } else if templated {
+            String::new()
+        } else {
+            let param = req.params().iter().find(|(key, _)| key.starts_with('*'));
+            let rest = if let Some((_, rest)) = param { rest } else { "" }.trim_start_matches('/');
+            encode_url_path(rest)
+        };
+        let path = path.trim_start_matches('/');
+        let mut forward_url = if path.is_empty() {
+            base
+        } else {
+            format!("{}/{}", base.trim_end_matches('/'), path)
+        };
+        if let Some(query) = rewrite::rewrite_query(req.uri().query(), &self.query_rewrites) {
+            forward_url.push('?');
+            forward_url.push_str(&query);
+        }
+        let forward_url: Uri = TryFrom::try_from(forward_url).map_err(Error::other)?;
+
+        let mut headers = req.headers().clone();
+        headers::strip_hop_by_hop(&mut headers);
+        // Upgrades are only tunnelled when the client connection can be upgraded.
+        if req.extensions().get::<OnUpgrade>().is_some() {
+            if let Some(protocol) = headers::upgrade_protocol(req.headers()) {
+                headers::set_upgrade(&mut headers, protocol);
+            }
+        }
+        if !self.preserve_host || !headers.contains_key(HOST) {
+            headers.remove(HOST);
+            if let Some(authority) = forward_url.authority() {
+                if let Ok(host) = HeaderValue::from_str(authority.as_str()) {
+                    headers.insert(HOST, host);
+                }
+            }
+        }
+        headers::add_forwarded(&mut headers, req, self.forwarded);
+        if let Some(via) = &self.via {
+            headers::add_via(&mut headers, req.version(), via);
+        }
+        if let Some(rewriter) = &self.request_headers_rewriter {
+            rewriter(req, &mut headers);
+        }
+
+        let mut proxied_request = hyper::Request::builder()
+            .method(req.method())
+            .uri(&forward_url)
+            .body(body)
+            .map_err(Error::other)?;
+        *proxied_request.headers_mut() = headers;
+        Ok(proxied_request)
+    }
+}
+
+impl ProxyHandler {
+    /// Buffers request body so that the request can be retried, returns `None` if it can not be retried.
+    async fn retry_body(&self, req: &mut Request) -> std::result::Result<Option<Bytes>, hyper::Error> {
+        if self.retries == 0 || !is_idempotent(req.method()) {
+            return Ok(None);
+        }
+        let size = match req.body() {
+            Some(body) => body.size_hint().exact(),
+            None => Some(0),
+        };
+        match size {
+            Some(size) if size <= self.max_retry_body_size => match req.take_body() {
+                Some(body) => hyper::body::to_bytes(body).await.map(Some),
+                None => Ok(Some(Bytes::new())),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the pool of the first route matching the request, or the default pool.
+    fn pool_for(&self, req: &Request) -> &Arc<UpstreamPool> {
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(req))
+            .map(|(_, pool)| pool)
+            .unwrap_or(&self.pool)
+    }
+
+    async fn proxy(&self, req: &mut Request, res: &mut Response) {
+        let pool = self.pool_for(req);
+        let buffered = match self.retry_body(req).await {
+            Ok(buffered) => buffered,
+            Err(e) => {
+                tracing::error!(error = ?e, "read request body failed");
+                res.set_status_error(StatusError::bad_request());
+                return;
+            }
+        };
+        let mut tried = Vec::new();
+        let mut last_error = None;
+        loop {
+            let permit = match pool.select_except(req, &tried) {
+                Some(permit) => permit,
+                None => {
+                    res.set_status_error(last_error.unwrap_or_else(|| {
+                        tracing::error!("no upstream is selected");
+                        StatusError::service_unavailable()
+                    }));
+                    return;
+                }
+            };
+            let body = match &buffered {
+                Some(bytes) => hyper::Body::from(bytes.clone()),
+                None => req.take_body().unwrap_or_default(),
+            };
+            let upstream = permit.upstream().clone();
+            let proxied_request = match self.build_proxied_request(req, upstream.url(), body) {
+                Ok(proxied_request) => proxied_request,
+                Err(e) => {
+                    tracing::error!("error when build proxied request: {}", e);
+                    res.set_status_error(StatusError::bad_gateway());
+                    return;
+                }
+            };
+            let guard = upstream.connect();
+            let response = match self.response_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, self.client.request(proxied_request)).await {
+                    Ok(response) => response,
+                    Err(_) => Err(HttpClientError::Timeout),
+                },
+                None => self.client.request(proxied_request).await,
+            };
+            permit.report(response.is_ok());
+            match response {
+                Ok(response) => {
+                    self.write_response(req, res, response, guard);
+                    return;
+                }
+                Err(e) => {
+                    drop(guard);
+                    last_error = Some(match e {
+                        HttpClientError::Http(e) => {
+                            tracing::error!(upstream = %upstream.url(), error = ?e, "invalid upstream request");
+                            StatusError::bad_gateway()
+                        }
+                        HttpClientError::Hyper(e) => {
+                            tracing::error!(upstream = %upstream.url(), error = ?e, "upstream request failed");
+                            StatusError::bad_gateway()
+                        }
+                        HttpClientError::Timeout => {
+                            tracing::error!(upstream = %upstream.url(), "upstream response timeout");
+                            StatusError::gateway_timeout()
+                        }
+                    });
+                    if buffered.is_none() || tried.len() >= self.retries {
+                        res.set_status_error(last_error.unwrap_or_else(StatusError::bad_gateway));
+                        return;
+                    }
+                    tried.push(upstream);
+                }
+            }
+        }
+    }
+
+    fn write_response(
+        &self,
+        req: &mut Request,
+        res: &mut Response,
+        mut response: hyper::Response<hyper::Body>,
+        guard: ConnectionGuard,
+    ) {
+        let upstream_upgrade = if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            headers::upgrade_protocol(response.headers()).map(|protocol| (protocol, hyper::upgrade::on(&mut response)))
+        } else {
+            None
+        };
+        let (
+            salvo_core::http::response::Parts {
+                status,
+                version,
+                headers,
+                // extensions,
+                ..
+            },
+            body,
+        ) = response.into_parts();
+        let mut headers = headers;
+        headers::strip_hop_by_hop(&mut headers);
+        if let Some(via) = &self.via {
+            headers::add_via(&mut headers, version, via);
+        }
+        if let Some(rewriter) = &self.response_headers_rewriter {
+            rewriter(req, &mut headers);
+        }
+        let client_upgrade = req.extensions_mut().remove::<OnUpgrade>();
+        match (upstream_upgrade, client_upgrade) {
+            (Some((protocol, upstream_upgrade)), Some(client_upgrade)) => {
+                headers::set_upgrade(&mut headers, protocol);
+                res.set_status_code(status);
+                res.set_headers(headers);
+                tokio::spawn(async move {
+                    // Keep the upstream connection counted until the tunnel is closed.
+                    let _guard = guard;
+                    match tokio::try_join!(client_upgrade, upstream_upgrade) {
+                        Ok((mut client, mut upstream)) => {
+                            if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+                                tracing::debug!(error = ?e, "upgraded connection closed with error");
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!(error = ?e, "upgrade connection failed");
+                        }
+                    }
+                });
+            }
+            (Some(_), None) => {
+                tracing::error!("upstream switched protocols but client connection can not be upgraded");
+                res.set_status_error(StatusError::bad_gateway());
+            }
+            (None, _) => {
+                res.set_status_code(status);
+                res.set_headers(headers);
+                // Keep the upstream connection counted until the body is fully sent.
+                let body = Body::from(body).map(move |chunk| {
+                    let _ = &guard;
+                    chunk
+                });
+                res.set_body(Body::Stream(Box::pin(body)));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Handler for ProxyHandler {
+    async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        self.pool.start_health_check(self.client.clone());
+        for (_, pool) in &self.routes {
+            pool.start_health_check(self.client.clone());
+        }
+        self.proxy(req, res).await;
+        if ctrl.has_next() {
+            tracing::error!("all handlers after ProxyHandler will skipped");
+            ctrl.skip_rest();
+        }
+    }
+}
+
+#[inline]
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+#[inline]
+fn encode_url_path(path: &str) -> String {
+    path.split('/')
+        .map(|s| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::prelude::*;
+    use salvo_core::test::{ResponseExt, TestClient};
+
+    use super::*;
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_proxy_painc() {
+        ProxyHandler::new(vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        let router = Router::new()
+            .push(Router::with_path("baidu/<**rest>").handle(ProxyHandler::new(vec!["https://www.baidu.com".into()])));
+
+        let content = TestClient::get("http://127.0.0.1:7979/baidu?wd=rust")
+            .send(router)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert!(content.contains("baidu"));
+    }
+    #[tokio::test]
+    async fn test_proxy_invalid_upstream() {
+        let router = Router::with_path("<**rest>").handle(ProxyHandler::new(vec!["http://invalid host".into()]));
+        let res = TestClient::get("http://127.0.0.1:7979/").send(router).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[test]
+    fn test_others() {
+        let handler = ProxyHandler::new(vec!["https://www.baidu.com".into()]);
+        assert_eq!(handler.upstreams().len(), 1);
+        assert_eq!(handler.pool().upstreams().len(), 1);
+        let handler = handler.with_upstreams(vec!["https://www.baidu.com".into(), "https://www.baidu.com".into()]);
+        assert_eq!(handler.upstreams().len(), 2);
+
+        let client = HttpClient::builder()
+            .with_timeout(Duration::from_secs(3))
+            .build()
+            .unwrap();
+        let handler = handler.with_client(client).with_connect_timeout(Duration::from_secs(1));
+        assert_eq!(handler.connect_timeout(), Some(Duration::from_secs(1)));
+        assert_eq!(handler.client().timeout(), Some(Duration::from_secs(3)));
+    }
+
+    async fn spawn_upstream(name: &'static str, fail: bool) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+        use std::convert::Infallible;
+
+        let make_svc = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |_req| async move {
+                let status = if fail {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    StatusCode::OK
+                };
+                Ok::<_, Infallible>(
+                    hyper::Response::builder()
+                        .status(status)
+                        .body(hyper::Body::from(name))
+                        .unwrap(),
+                )
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
+
+    fn dead_upstream() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_passive_health_check() {
+        let alive = spawn_upstream("alive", false).await;
+        let pool = UpstreamPool::new(vec![dead_upstream(), alive]).with_max_failures(1);
+        let router = Router::with_path("<**rest>").handle(ProxyHandler::from_pool(pool));
+        let service = Service::new(router);
+
+        let mut bodies = vec![];
+        for _ in 0..4 {
+            let mut res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+            bodies.push(res.take_string().await.unwrap());
+        }
+        // The dead upstream fails once, then is skipped.
+        assert_eq!(bodies.iter().filter(|b| *b == "alive").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_active_health_check() {
+        let sick = spawn_upstream("sick", true).await;
+        let alive = spawn_upstream("alive", false).await;
+        let pool = UpstreamPool::new(vec![sick, alive])
+            .with_health_check(HealthCheck::new("/").with_interval(std::time::Duration::from_millis(50)));
+        let handler = ProxyHandler::from_pool(pool);
+        let upstreams = handler.upstreams().to_vec();
+        let router = Router::with_path("<**rest>").handle(handler);
+        let service = Service::new(router);
+
+        TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!upstreams[0].is_healthy());
+        assert!(upstreams[1].is_healthy());
+        for _ in 0..4 {
+            let mut res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+            assert_eq!(res.take_string().await.unwrap(), "alive");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_headers() {
+        use hyper::service::{make_service_fn, service_fn};
+        use std::convert::Infallible;
+
+        // Echoes request headers in the body, and answers with some hop-by-hop headers.
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: hyper::Request<hyper::Body>| async move {
+                let mut lines = req
+                    .headers()
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v.to_str().unwrap()))
+                    .collect::<Vec<_>>();
+                lines.sort();
+                Ok::<_, Infallible>(
+                    hyper::Response::builder()
+                        .header("connection", "x-hop")
+                        .header("x-hop", "1")
+                        .header("x-upstream", "echo")
+                        .body(hyper::Body::from(lines.join("\n")))
+                        .unwrap(),
+                )
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let upstream = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        let handler = ProxyHandler::new(vec![upstream])
+            .with_forwarded(ForwardedHeaders::Both)
+            .with_via("salvo")
+            .with_preserve_host(true)
+            .with_request_headers_rewriter(|_, headers| {
+                headers.insert("x-rewritten", HeaderValue::from_static("yes"));
+            })
+            .with_response_headers_rewriter(|_, headers| {
+                headers.remove("x-upstream");
+            });
+        let router = Router::with_path("<**rest>").handle(handler);
+        let service = Service::new(router);
+
+        let mut res = TestClient::get("http://example.com/")
+            .insert_header("host", "example.com")
+            .insert_header("connection", "x-secret")
+            .insert_header("x-secret", "1")
+            .insert_header("te", "trailers")
+            .insert_header("proxy-authorization", "Basic Zm9v")
+            .send(&service)
+            .await;
+        assert!(res.headers().get("x-hop").is_none());
+        assert!(res.headers().get("x-upstream").is_none());
+        assert_eq!(res.headers()["via"], "1.1 salvo");
+        let content = res.take_string().await.unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert!(lines.contains(&"host: example.com"));
+        assert!(lines.contains(&"x-forwarded-proto: http"));
+        assert!(lines.contains(&"x-forwarded-host: example.com"));
+        assert!(lines.contains(&"forwarded: for=unknown;proto=http;host=\"example.com\""));
+        assert!(lines.contains(&"via: 1.1 salvo"));
+        assert!(lines.contains(&"x-rewritten: yes"));
+        assert!(!lines.iter().any(|l| l.starts_with("x-secret") || l.starts_with("te:")));
+        assert!(!lines.iter().any(|l| l.starts_with("proxy-authorization")));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_upgrade() {
+        use hyper::service::{make_service_fn, service_fn};
+        use salvo_core::listener::TcpListener;
+        use std::convert::Infallible;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Switches to an echo protocol.
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|mut req: hyper::Request<hyper::Body>| async move {
+                let on_upgrade = hyper::upgrade::on(&mut req);
+                tokio::spawn(async move {
+                    let mut upgraded = on_upgrade.await.unwrap();
+                    let mut buf = [0; 4];
+                    upgraded.read_exact(&mut buf).await.unwrap();
+                    upgraded.write_all(&buf).await.unwrap();
+                });
+                Ok::<_, Infallible>(
+                    hyper::Response::builder()
+                        .status(StatusCode::SWITCHING_PROTOCOLS)
+                        .header("connection", "upgrade")
+                        .header("upgrade", "echo")
+                        .body(hyper::Body::empty())
+                        .unwrap(),
+                )
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let upstream = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        let listener = TcpListener::bind("127.0.0.1:0");
+        let addr = listener.local_addr();
+        let router = Router::with_path("<**rest>").handle(ProxyHandler::new(vec![upstream]));
+        tokio::spawn(Server::new(listener).serve(router));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /echo HTTP/1.1\r\nhost: localhost\r\nconnection: upgrade\r\nupgrade: echo\r\n\r\n")
+            .await
+            .unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0; 1];
+            stream.read_exact(&mut byte).await.unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap().to_lowercase();
+        assert!(head.starts_with("http/1.1 101"));
+        assert!(head.contains("upgrade: echo"));
+
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_proxy_retry() {
+        let alive = spawn_upstream("alive", false).await;
+        let handler = ProxyHandler::new(vec![dead_upstream(), alive]).with_retries(1);
+        let router = Router::with_path("<**rest>").handle(handler);
+        let service = Service::new(router);
+
+        // Requests which are not idempotent are not retried.
+        let mut statuses = vec![];
+        for _ in 0..2 {
+            let res = TestClient::post("http://127.0.0.1:7979/").send(&service).await;
+            statuses.push(res.status_code().unwrap());
+        }
+        assert!(statuses.contains(&StatusCode::BAD_GATEWAY));
+
+        for _ in 0..4 {
+            let mut res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+            assert_eq!(res.take_string().await.unwrap(), "alive");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_response_timeout() {
+        // Accepts connections but never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hung = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        let handler = ProxyHandler::new(vec![hung]).with_response_timeout(std::time::Duration::from_millis(50));
+        let router = Router::with_path("<**rest>").handle(handler);
+
+        let res = TestClient::get("http://127.0.0.1:7979/").send(router).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_circuit_breaker() {
+        let pool = UpstreamPool::new(vec![dead_upstream()]).with_circuit_breaker(
+            CircuitBreaker::new()
+                .with_failure_threshold(1)
+                .with_open_timeout(std::time::Duration::from_secs(60)),
+        );
+        let handler = ProxyHandler::from_pool(pool);
+        let upstreams = handler.upstreams().to_vec();
+        let router = Router::with_path("<**rest>").handle(handler);
+        let service = Service::new(router);
+
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::BAD_GATEWAY);
+        assert_eq!(upstreams[0].circuit_state(), CircuitState::Open);
+        let res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_rewrite() {
+        use hyper::service::{make_service_fn, service_fn};
+        use std::convert::Infallible;
+
+        async fn spawn_echo_uri(name: &'static str) -> String {
+            let make_svc = make_service_fn(move |_| async move {
+                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| async move {
+                    Ok::<_, Infallible>(hyper::Response::new(hyper::Body::from(format!(
+                        "{} {}",
+                        name,
+                        req.uri()
+                    ))))
+                }))
+            });
+            let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+            let addr = server.local_addr();
+            tokio::spawn(server);
+            format!("http://{}", addr)
+        }
+        let users = spawn_echo_uri("users").await;
+        let admin = spawn_echo_uri("admin").await;
+
+        let handler = ProxyHandler::new(vec![format!("{}/internal/<id>", users)])
+            .with_query_rewrite(QueryRewrite::Remove("token".into()))
+            .with_query_rewrite(QueryRewrite::Set("source".into(), "gateway".into()));
+        let router = Router::new()
+            .push(Router::with_path("api/v1/users/<id>").handle(handler))
+            .push(
+                Router::with_path("api/<**rest>").handle(
+                    ProxyHandler::new(vec![users])
+                        .with_path_rewrite(PathRewrite::strip_prefix("/api"))
+                        .with_path_rewrite(PathRewrite::add_prefix("/v2"))
+                        .with_route(
+                            |req| req.headers().contains_key("x-admin"),
+                            UpstreamPool::new(vec![admin]),
+                        ),
+                ),
+            );
+        let service = Service::new(router);
+
+        let content = TestClient::get("http://127.0.0.1:7979/api/v1/users/42?token=x&q=1")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "users /internal/42?q=1&source=gateway");
+
+        let content = TestClient::get("http://127.0.0.1:7979/api/orders/7")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "users /v2/orders/7");
+
+        let content = TestClient::get("http://127.0.0.1:7979/api/orders/7")
+            .insert_header("x-admin", "1")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "admin /v2/orders/7");
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use regex::Regex;
+
+/// A rule rewriting the path of proxied requests.
+///
+/// Rules are applied in order to the path of the client request, the result is appended to the upstream url.
+#[derive(Clone, Debug)]
+pub enum PathRewrite {
+    /// Removes a prefix from the path if it is present.
+    StripPrefix(String),
+    /// Adds a prefix to the path.
+    AddPrefix(String),
+    /// Replaces all matches of a regex, the replacement can refer to capture groups like `$1`.
+    Replace(Regex, String),
+    /// Replaces the whole path with a template, `<name>` is replaced with the path param `name`.
+    Template(String),
+}
+impl PathRewrite {
+    /// Create a [`PathRewrite::StripPrefix`] rule.
+    #[inline]
+    pub fn strip_prefix(prefix: impl Into<String>) -> Self {
+        PathRewrite::StripPrefix(prefix.into())
+    }
+    /// Create a [`PathRewrite::AddPrefix`] rule.
+    #[inline]
+    pub fn add_prefix(prefix: impl Into<String>) -> Self {
+        PathRewrite::AddPrefix(prefix.into())
+    }
+    /// Create a [`PathRewrite::Replace`] rule.
+    ///
+    /// Returns an error if `pattern` is not a valid regex.
+    #[inline]
+    pub fn replace(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(PathRewrite::Replace(Regex::new(pattern)?, replacement.into()))
+    }
+    /// Create a [`PathRewrite::Template`] rule.
+    #[inline]
+    pub fn template(template: impl Into<String>) -> Self {
+        PathRewrite::Template(template.into())
+    }
+
+    pub(crate) fn apply(&self, path: &str, params: &HashMap<String, String>) -> String {
+        match self {
+            PathRewrite::StripPrefix(prefix) => {
+                let prefix = prefix.trim_end_matches('/');
+                match path.strip_prefix(prefix) {
+                    Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.to_owned(),
+                    _ => path.to_owned(),
+                }
+            }
+            PathRewrite::AddPrefix(prefix) => {
+                format!("{}/{}", prefix.trim_end_matches('/'), path.trim_start_matches('/'))
+            }
+            PathRewrite::Replace(regex, replacement) => regex.replace_all(path, replacement.as_str()).into_owned(),
+            PathRewrite::Template(template) => render_template(template, params),
+        }
+    }
+}
+
+/// Replaces `<name>` in `template` with the percent-encoded path param `name`, wildcard params can be
+/// referred to with or without their leading `*`.
+pub(crate) fn render_template(template: &str, params: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        rendered.push_str(&rest[..start]);
+        let name = rest[start + 1..end].trim_start_matches('*');
+        if let Some((_, value)) = params.iter().find(|(key, _)| key.trim_start_matches('*') == name) {
+            rendered.push_str(&super::encode_url_path(value));
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// A rule rewriting the query string of proxied requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryRewrite {
+    /// Sets a query param, replacing all existing values.
+    Set(String, String),
+    /// Appends a query param.
+    Append(String, String),
+    /// Removes a query param.
+    Remove(String),
+    /// Removes all query params.
+    Clear,
+}
+impl QueryRewrite {
+    pub(crate) fn apply(&self, pairs: &mut Vec<(String, String)>) {
+        let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+        match self {
+            QueryRewrite::Set(name, value) => {
+                pairs.retain(|(k, _)| !key_eq(k, name));
+                pairs.push((encode(name), encode(value)));
+            }
+            QueryRewrite::Append(name, value) => pairs.push((encode(name), encode(value))),
+            QueryRewrite::Remove(name) => pairs.retain(|(k, _)| !key_eq(k, name)),
+            QueryRewrite::Clear => pairs.clear(),
+        }
+    }
+}
+
+#[inline]
+fn key_eq(raw: &str, name: &str) -> bool {
+    percent_decode_str(&raw.replace('+', " ")).decode_utf8_lossy() == name
+}
+
+/// Applies query rewrite rules to a raw query string, returns `None` if the result is empty.
+pub(crate) fn rewrite_query(query: Option<&str>, rules: &[QueryRewrite]) -> Option<String> {
+    if rules.is_empty() {
+        return query.filter(|q| !q.is_empty()).map(ToOwned::to_owned);
+    }
+    let mut pairs = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k.to_owned(), v.to_owned()),
+            None => (pair.to_owned(), String::new()),
+        })
+        .collect::<Vec<_>>();
+    for rule in rules {
+        rule.apply(&mut pairs);
+    }
+    if pairs.is_empty() {
+        None
+    } else {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| {
+                    if v.is_empty() {
+                        k.clone()
+                    } else {
+                        format!("{}={}", k, v)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("&"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_rewrite() {
+        let mut params = HashMap::new();
+        params.insert("id".to_owned(), "a b".to_owned());
+        params.insert("**rest".to_owned(), "x/y".to_owned());
+        assert_eq!(
+            PathRewrite::strip_prefix("/api/").apply("/api/users", &params),
+            "/users"
+        );
+        assert_eq!(PathRewrite::strip_prefix("/api").apply("/apis", &params), "/apis");
+        assert_eq!(PathRewrite::add_prefix("/v2/").apply("/users", &params), "/v2/users");
+        assert_eq!(
+            PathRewrite::replace(r"^/v1/(\w+)", "/$1/v1")
+                .unwrap()
+                .apply("/v1/users/1", &params),
+            "/users/v1/1"
+        );
+        assert!(PathRewrite::replace(r"^/v1/(\w+", "/$1").is_err());
+        assert_eq!(
+            PathRewrite::template("/internal/<id>/<rest>").apply("/ignored", &params),
+            "/internal/a%20b/x/y"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_query() {
+        let rules = vec![
+            QueryRewrite::Remove("token".into()),
+            QueryRewrite::Set("page".into(), "2".into()),
+            QueryRewrite::Append("tag".into(), "a b".into()),
+        ];
+        assert_eq!(
+            rewrite_query(Some("page=1&token=x&q=rust"), &rules).unwrap(),
+            "q=rust&page=2&tag=a%20b"
+        );
+        assert_eq!(rewrite_query(Some("a=1"), &[QueryRewrite::Clear]), None);
+        assert_eq!(rewrite_query(Some(""), &[]), None);
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rand::Rng;
+use salvo_core::addr::SocketAddr;
+use salvo_core::Request;
+
+use super::Upstream;
+
+/// Load-balancing strategy, selects an upstream for a request.
+pub trait Strategy: Send + Sync + 'static {
+    /// Returns index of the selected upstream in `upstreams`, which is never empty unless the pool is.
+    fn select(&self, upstreams: &[Arc<Upstream>], req: &Request) -> Option<usize>;
+}
+
+/// Round-robin strategy, selects upstreams in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    counter: AtomicUsize,
+}
+impl RoundRobin {
+    /// Create new `RoundRobin`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+impl Strategy for RoundRobin {
+    #[inline]
+    fn select(&self, upstreams: &[Arc<Upstream>], _req: &Request) -> Option<usize> {
+        if upstreams.is_empty() {
+            return None;
+        }
+        Some(self.counter.fetch_add(1, Ordering::Relaxed) % upstreams.len())
+    }
+}
+
+/// Smooth weighted round-robin strategy, upstreams get requests in proportion to their weight
+/// and are interleaved evenly.
+#[derive(Debug, Default)]
+pub struct WeightedRoundRobin {
+    current: Mutex<HashMap<String, i64>>,
+}
+impl WeightedRoundRobin {
+    /// Create new `WeightedRoundRobin`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+impl Strategy for WeightedRoundRobin {
+    fn select(&self, upstreams: &[Arc<Upstream>], _req: &Request) -> Option<usize> {
+        let mut current = self.current.lock();
+        let mut total = 0;
+        let mut best: Option<(usize, i64)> = None;
+        for (index, upstream) in upstreams.iter().enumerate() {
+            let weight = upstream.weight() as i64;
+            total += weight;
+            let value = current.entry(upstream.url().to_owned()).or_insert(0);
+            *value += weight;
+            if best.map(|(_, v)| *value > v).unwrap_or(true) {
+                best = Some((index, *value));
+            }
+        }
+        let (index, _) = best?;
+        if let Some(value) = current.get_mut(upstreams[index].url()) {
+            *value -= total;
+        }
+        Some(index)
+    }
+}
+
+/// Least-connections strategy, selects the upstream with the fewest in-flight requests.
+#[derive(Debug, Default)]
+pub struct LeastConnections;
+impl LeastConnections {
+    /// Create new `LeastConnections`.
+    #[inline]
+    pub fn new() -> Self {
+        LeastConnections
+    }
+}
+impl Strategy for LeastConnections {
+    #[inline]
+    fn select(&self, upstreams: &[Arc<Upstream>], _req: &Request) -> Option<usize> {
+        upstreams
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, upstream)| upstream.active_connections())
+            .map(|(index, _)| index)
+    }
+}
+
+/// Power of two random choices strategy, picks two upstreams at random and selects the one with
+/// fewer in-flight requests.
+#[derive(Debug, Default)]
+pub struct RandomTwoChoices;
+impl RandomTwoChoices {
+    /// Create new `RandomTwoChoices`.
+    #[inline]
+    pub fn new() -> Self {
+        RandomTwoChoices
+    }
+}
+impl Strategy for RandomTwoChoices {
+    fn select(&self, upstreams: &[Arc<Upstream>], _req: &Request) -> Option<usize> {
+        match upstreams.len() {
+            0 => None,
+            1 => Some(0),
+            len => {
+                let mut rng = rand::thread_rng();
+                let a = rng.gen_range(0..len);
+                let b = (a + rng.gen_range(1..len)) % len;
+                if upstreams[b].active_connections() < upstreams[a].active_connections() {
+                    Some(b)
+                } else {
+                    Some(a)
+                }
+            }
+        }
+    }
+}
+
+/// Where [`ConsistentHash`] reads the hash key from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashKey {
+    /// Value of a request header.
+    Header(String),
+    /// Value of a request cookie.
+    Cookie(String),
+    /// Client IP address.
+    RemoteAddr,
+}
+
+/// Consistent hashing strategy for sticky sessions, requests with the same key go to the same
+/// upstream as long as it is available.
+///
+/// Uses rendezvous hashing, so when an upstream goes away only its keys move to other upstreams.
+/// Keys are hashed with FNV-1a, so every gateway instance sends a key to the same upstream.
+/// Requests without the key fall back to the client IP address.
+#[derive(Clone, Debug)]
+pub struct ConsistentHash {
+    key: HashKey,
+}
+impl ConsistentHash {
+    /// Create new `ConsistentHash`.
+    #[inline]
+    pub fn new(key: HashKey) -> Self {
+        ConsistentHash { key }
+    }
+    /// Create new `ConsistentHash` which hashes a request header.
+    #[inline]
+    pub fn header(name: impl Into<String>) -> Self {
+        Self::new(HashKey::Header(name.into()))
+    }
+    /// Create new `ConsistentHash` which hashes a request cookie.
+    #[inline]
+    pub fn cookie(name: impl Into<String>) -> Self {
+        Self::new(HashKey::Cookie(name.into()))
+    }
+
+    fn key(&self, req: &Request) -> Option<String> {
+        let key = match &self.key {
+            HashKey::Header(name) => req
+                .headers()
+                .get(name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(ToOwned::to_owned),
+            HashKey::Cookie(name) => req.cookie(name.as_str()).map(|c| c.value().to_owned()),
+            HashKey::RemoteAddr => None,
+        };
+        key.or_else(|| match req.remote_addr()? {
+            SocketAddr::IPv4(addr) => Some(addr.ip().to_string()),
+            SocketAddr::IPv6(addr) => Some(addr.ip().to_string()),
+            #[cfg(unix)]
+            SocketAddr::Unix(_) => None,
+        })
+    }
+}
+impl Strategy for ConsistentHash {
+    fn select(&self, upstreams: &[Arc<Upstream>], req: &Request) -> Option<usize> {
+        let key = self.key(req).unwrap_or_default();
+        upstreams
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, upstream)| fnv1a(&[key.as_bytes(), &[0xff], upstream.url().as_bytes()]))
+            .map(|(index, _)| index)
+    }
+}
+
+/// 64-bit FNV-1a hash of the concatenated `parts`.
+///
+/// Unlike `DefaultHasher` its output is fixed, so every process and build maps a key to the same upstream.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstreams(weights: &[u32]) -> Vec<Arc<Upstream>> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| Arc::new(Upstream::new(format!("http://10.0.0.{}", i)).with_weight(*w)))
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let upstreams = upstreams(&[5, 1, 1]);
+        let strategy = WeightedRoundRobin::new();
+        let req = Request::new();
+        let selected = (0..7)
+            .map(|_| strategy.select(&upstreams, &req).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(selected, vec![0, 0, 1, 0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn test_least_connections() {
+        let upstreams = upstreams(&[1, 1, 1]);
+        let _a = upstreams[0].connect();
+        let _b = upstreams[1].connect();
+        assert_eq!(LeastConnections::new().select(&upstreams, &Request::new()), Some(2));
+        assert_eq!(
+            RandomTwoChoices::new()
+                .select(&upstreams[..2], &Request::new())
+                .map(|i| i < 2),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_consistent_hash() {
+        let all = upstreams(&[1, 1, 1, 1]);
+        let strategy = ConsistentHash::header("x-session");
+        let mut req = Request::new();
+        req.headers_mut().insert("x-session", "user-42".parse().unwrap());
+        let first = strategy.select(&all, &req).unwrap();
+        for _ in 0..10 {
+            assert_eq!(strategy.select(&all, &req), Some(first));
+        }
+        let others = all
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != first)
+            .map(|(_, u)| u.clone())
+            .collect::<Vec<_>>();
+        let moved = strategy.select(&others, &req).unwrap();
+        assert_ne!(others[moved].url(), all[first].url());
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(&[b"foo", b"bar"]), fnv1a(&[b"foobar"]));
+    }
+}
@@ -0,0 +1,521 @@
+use std::fmt::{self, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use hyper::Uri;
+use parking_lot::Mutex;
+use salvo_core::Request;
+
+use crate::http_client::HttpClient;
+
+use super::circuit_breaker::{Circuit, CircuitBreaker, CircuitState};
+use super::strategy::{RoundRobin, Strategy};
+
+/// An upstream server of the proxy.
+pub struct Upstream {
+    url: String,
+    weight: u32,
+    healthy: AtomicBool,
+    failures: AtomicUsize,
+    retry_at: Mutex<Option<Instant>>,
+    active: AtomicUsize,
+    circuit: Circuit,
+}
+
+impl fmt::Debug for Upstream {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Upstream")
+            .field("url", &self.url)
+            .field("weight", &self.weight)
+            .field("healthy", &self.is_healthy())
+            .field("active_connections", &self.active_connections())
+            .field("circuit_state", &self.circuit_state())
+            .finish()
+    }
+}
+
+impl Upstream {
+    /// Create new `Upstream` with weight 1.
+    #[inline]
+    pub fn new(url: impl Into<String>) -> Self {
+        Upstream {
+            url: url.into(),
+            weight: 1,
+            healthy: AtomicBool::new(true),
+            failures: AtomicUsize::new(0),
+            retry_at: Mutex::new(None),
+            active: AtomicUsize::new(0),
+            circuit: Circuit::default(),
+        }
+    }
+    /// Sets weight used by [`WeightedRoundRobin`](super::WeightedRoundRobin) and returns Self.
+    #[inline]
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+    /// Get upstream url.
+    #[inline]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    /// Get upstream weight.
+    #[inline]
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+    /// Returns `true` if the upstream is considered healthy.
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Acquire)
+    }
+    /// Get the number of requests currently proxied to this upstream.
+    #[inline]
+    pub fn active_connections(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+    /// Get circuit state, it is always [`CircuitState::Closed`] unless the pool has a [`CircuitBreaker`].
+    #[inline]
+    pub fn circuit_state(&self) -> CircuitState {
+        self.circuit.state()
+    }
+
+    /// Returns `true` if requests can be sent to this upstream, an unhealthy upstream is tried again
+    /// after the fail timeout passed.
+    #[inline]
+    fn is_available(&self) -> bool {
+        self.is_healthy() || self.retry_at.lock().map(|at| at <= Instant::now()).unwrap_or(false)
+    }
+    #[inline]
+    fn mark_healthy(&self) {
+        self.failures.store(0, Ordering::Release);
+        *self.retry_at.lock() = None;
+        self.healthy.store(true, Ordering::Release);
+    }
+    #[inline]
+    fn mark_unhealthy(&self, retry_at: Option<Instant>) {
+        *self.retry_at.lock() = retry_at;
+        self.healthy.store(false, Ordering::Release);
+    }
+
+    /// Increase active connections, they are decreased when the returned guard is dropped.
+    #[inline]
+    pub(crate) fn connect(self: &Arc<Self>) -> ConnectionGuard {
+        self.active.fetch_add(1, Ordering::AcqRel);
+        ConnectionGuard(self.clone())
+    }
+}
+
+impl From<String> for Upstream {
+    #[inline]
+    fn from(url: String) -> Self {
+        Upstream::new(url)
+    }
+}
+impl From<&str> for Upstream {
+    #[inline]
+    fn from(url: &str) -> Self {
+        Upstream::new(url)
+    }
+}
+
+/// Permission to send a request to an upstream selected by [`UpstreamPool::select`].
+///
+/// When the upstream circuit is half-open, the permit holds one of its trial slots. The slot is given
+/// back if the permit is dropped without a result being reported, e.g. when the request is cancelled.
+#[derive(Debug)]
+pub struct UpstreamPermit<'a> {
+    pool: &'a UpstreamPool,
+    upstream: Arc<Upstream>,
+    reported: bool,
+}
+impl UpstreamPermit<'_> {
+    /// Get the selected upstream.
+    #[inline]
+    pub fn upstream(&self) -> &Arc<Upstream> {
+        &self.upstream
+    }
+    /// Report result of the request sent to the upstream, see [`UpstreamPool::report`].
+    #[inline]
+    pub fn report(mut self, success: bool) {
+        self.reported = true;
+        self.pool.report(&self.upstream, success);
+    }
+}
+impl Drop for UpstreamPermit<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.reported && self.pool.circuit_breaker.is_some() {
+            self.upstream.circuit.release();
+        }
+    }
+}
+
+pub(crate) struct ConnectionGuard(Arc<Upstream>);
+impl Drop for ConnectionGuard {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Active health check, every upstream is probed with a `GET` request periodically.
+///
+/// An upstream which answers with a success or redirection status is healthy, others are unhealthy
+/// until a later probe succeeds.
+#[derive(Clone, Debug)]
+pub struct HealthCheck {
+    path: String,
+    interval: Duration,
+    timeout: Duration,
+}
+impl Default for HealthCheck {
+    #[inline]
+    fn default() -> Self {
+        Self::new("/")
+    }
+}
+impl HealthCheck {
+    /// Create new `HealthCheck` which probes `path` every 10 seconds with a 3 seconds timeout.
+    #[inline]
+    pub fn new(path: impl Into<String>) -> Self {
+        HealthCheck {
+            path: path.into(),
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(3),
+        }
+    }
+    /// Get probe path.
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    /// Get probe interval.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+    /// Sets probe interval and returns Self.
+    #[inline]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    /// Get probe timeout.
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    /// Sets probe timeout and returns Self.
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn probe(&self, client: &HttpClient, upstream: &Upstream) -> bool {
+        let url = format!("{}{}", upstream.url().trim_end_matches('/'), self.path);
+        let uri = match url.parse::<Uri>() {
+            Ok(uri) => uri,
+            Err(e) => {
+                tracing::error!(error = ?e, url = %url, "invalid health check url");
+                return false;
+            }
+        };
+        match tokio::time::timeout(self.timeout, client.get(uri)).await {
+            Ok(Ok(res)) => res.status().is_success() || res.status().is_redirection(),
+            _ => false,
+        }
+    }
+}
+
+/// A group of upstreams with a load-balancing strategy and health checks.
+///
+/// Passive health check is always enabled: an upstream is marked unhealthy after `max_failures`
+/// consecutive failed requests and gets requests again after `fail_timeout`. When every upstream is
+/// unhealthy, requests are sent to all of them rather than failing.
+///
+/// With a [`CircuitBreaker`], upstreams whose circuit is open get no request at all, and requests
+/// fail fast when every circuit is open.
+pub struct UpstreamPool {
+    upstreams: Vec<Arc<Upstream>>,
+    strategy: Box<dyn Strategy>,
+    max_failures: usize,
+    fail_timeout: Duration,
+    health_check: Option<HealthCheck>,
+    health_check_started: AtomicBool,
+    circuit_breaker: Option<CircuitBreaker>,
+}
+
+impl fmt::Debug for UpstreamPool {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("UpstreamPool")
+            .field("upstreams", &self.upstreams)
+            .field("max_failures", &self.max_failures)
+            .field("fail_timeout", &self.fail_timeout)
+            .field("health_check", &self.health_check)
+            .field("circuit_breaker", &self.circuit_breaker)
+            .finish()
+    }
+}
+
+impl UpstreamPool {
+    /// Create new `UpstreamPool` with [`RoundRobin`] strategy.
+    #[inline]
+    pub fn new<I>(upstreams: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Upstream>,
+    {
+        UpstreamPool {
+            upstreams: upstreams.into_iter().map(|u| Arc::new(u.into())).collect(),
+            strategy: Box::new(RoundRobin::new()),
+            max_failures: 3,
+            fail_timeout: Duration::from_secs(10),
+            health_check: None,
+            health_check_started: AtomicBool::new(false),
+            circuit_breaker: None,
+        }
+    }
+    /// Get upstreams list.
+    #[inline]
+    pub fn upstreams(&self) -> &[Arc<Upstream>] {
+        &self.upstreams
+    }
+    /// Sets load-balancing strategy and returns Self.
+    #[inline]
+    pub fn with_strategy(mut self, strategy: impl Strategy) -> Self {
+        self.strategy = Box::new(strategy);
+        self
+    }
+    /// Get max consecutive failures before an upstream is marked unhealthy.
+    #[inline]
+    pub fn max_failures(&self) -> usize {
+        self.max_failures
+    }
+    /// Sets max consecutive failures before an upstream is marked unhealthy, default is 3.
+    #[inline]
+    pub fn with_max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+    /// Get fail timeout.
+    #[inline]
+    pub fn fail_timeout(&self) -> Duration {
+        self.fail_timeout
+    }
+    /// Sets how long an upstream marked unhealthy by passive health check gets no requests, default is 10 seconds.
+    #[inline]
+    pub fn with_fail_timeout(mut self, fail_timeout: Duration) -> Self {
+        self.fail_timeout = fail_timeout;
+        self
+    }
+    /// Get active health check.
+    #[inline]
+    pub fn health_check(&self) -> Option<&HealthCheck> {
+        self.health_check.as_ref()
+    }
+    /// Enables active health check and returns Self.
+    ///
+    /// Probing starts with the first proxied request.
+    #[inline]
+    pub fn with_health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = Some(health_check);
+        self
+    }
+
+    /// Get circuit breaker.
+    #[inline]
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_ref()
+    }
+    /// Enables circuit breaking and returns Self.
+    #[inline]
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Select an upstream for the request.
+    ///
+    /// When a circuit breaker is enabled, the selected upstream counts as a trial request of a
+    /// half-open circuit until its result is passed to [`UpstreamPermit::report`] or the permit is dropped.
+    #[inline]
+    pub fn select(&self, req: &Request) -> Option<UpstreamPermit<'_>> {
+        self.select_except(req, &[])
+    }
+
+    /// Select an upstream which is not in `tried`, used to retry a request on another upstream.
+    ///
+    /// An upstream whose circuit trial slot was taken by a concurrent request in the meantime is
+    /// skipped and the strategy selects again among the remaining ones.
+    pub(crate) fn select_except(&self, req: &Request, tried: &[Arc<Upstream>]) -> Option<UpstreamPermit<'_>> {
+        let mut rejected: Vec<Arc<Upstream>> = Vec::new();
+        loop {
+            let allowed = self
+                .upstreams
+                .iter()
+                .filter(|u| !tried.iter().chain(&rejected).any(|t| Arc::ptr_eq(t, u)))
+                .filter(|u| {
+                    self.circuit_breaker
+                        .as_ref()
+                        .map(|breaker| u.circuit.is_allowed(breaker))
+                        .unwrap_or(true)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let available = allowed.iter().filter(|u| u.is_available()).cloned().collect::<Vec<_>>();
+            let candidates = if available.is_empty() { &allowed } else { &available };
+            let upstream = self
+                .strategy
+                .select(candidates, req)
+                .and_then(|index| candidates.get(index))
+                .cloned()?;
+            match &self.circuit_breaker {
+                Some(breaker) if !upstream.circuit.acquire(breaker) => rejected.push(upstream),
+                _ => {
+                    return Some(UpstreamPermit {
+                        pool: self,
+                        upstream,
+                        reported: false,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Report result of a request sent to the upstream, used by passive health check and circuit breaker.
+    pub fn report(&self, upstream: &Upstream, success: bool) {
+        if let Some(breaker) = &self.circuit_breaker {
+            match upstream.circuit.record(breaker, success) {
+                Some(CircuitState::Open) => tracing::warn!(upstream = %upstream.url(), "upstream circuit opened"),
+                Some(CircuitState::Closed) => tracing::info!(upstream = %upstream.url(), "upstream circuit closed"),
+                _ => {}
+            }
+        }
+        if success {
+            if !upstream.is_healthy() || upstream.failures.load(Ordering::Acquire) > 0 {
+                upstream.mark_healthy();
+            }
+        } else {
+            let failures = upstream.failures.fetch_add(1, Ordering::AcqRel) + 1;
+            if failures >= self.max_failures {
+                if upstream.is_healthy() {
+                    tracing::warn!(upstream = %upstream.url(), failures, "upstream marked unhealthy");
+                }
+                upstream.mark_unhealthy(Some(Instant::now() + self.fail_timeout));
+            }
+        }
+    }
+
+    /// Start active health check if it is configured and not started yet.
+    pub(crate) fn start_health_check(self: &Arc<Self>, client: HttpClient) {
+        let health_check = match &self.health_check {
+            Some(health_check) => health_check.clone(),
+            None => return,
+        };
+        if self.health_check_started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let pool: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                let upstreams = match pool.upgrade() {
+                    Some(pool) => pool.upstreams.clone(),
+                    None => break,
+                };
+                for upstream in upstreams {
+                    if health_check.probe(&client, &upstream).await {
+                        if !upstream.is_healthy() {
+                            tracing::info!(upstream = %upstream.url(), "upstream is healthy again");
+                        }
+                        upstream.mark_healthy();
+                    } else {
+                        if upstream.is_healthy() {
+                            tracing::warn!(upstream = %upstream.url(), "upstream failed health check");
+                        }
+                        upstream.mark_unhealthy(None);
+                    }
+                }
+                tokio::time::sleep(health_check.interval).await;
+            }
+        });
+    }
+}
+
+impl<T> From<Vec<T>> for UpstreamPool
+where
+    T: Into<Upstream>,
+{
+    #[inline]
+    fn from(upstreams: Vec<T>) -> Self {
+        UpstreamPool::new(upstreams)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::test::TestClient;
+
+    use super::*;
+
+    /// Selects the first candidate, after taking the trial slot of `http://a` like a concurrent request would.
+    struct Racing(CircuitBreaker);
+    impl Strategy for Racing {
+        fn select(&self, upstreams: &[Arc<Upstream>], _req: &Request) -> Option<usize> {
+            let upstream = upstreams.first()?;
+            if upstream.url() == "http://a" {
+                upstream.circuit.acquire(&self.0);
+            }
+            Some(0)
+        }
+    }
+
+    #[test]
+    fn test_select_skips_taken_trial_slot() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(1)
+            .with_open_timeout(Duration::ZERO);
+        let pool = UpstreamPool::new(["http://a", "http://b"])
+            .with_strategy(Racing(breaker))
+            .with_circuit_breaker(breaker);
+        for upstream in pool.upstreams() {
+            assert!(upstream.circuit.acquire(&breaker));
+            pool.report(upstream, false);
+        }
+
+        let req = TestClient::get("http://example.com/").build();
+        let permit = pool.select(&req).unwrap();
+        assert_eq!(permit.upstream().url(), "http://b");
+        assert_eq!(pool.upstreams()[0].circuit_state(), CircuitState::HalfOpen);
+        // Both trial slots are taken.
+        assert!(pool.select(&req).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_releases_trial_slot() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(1)
+            .with_open_timeout(Duration::ZERO);
+        let pool = UpstreamPool::new(["http://a"]).with_circuit_breaker(breaker);
+        let req = TestClient::get("http://example.com/").build();
+        pool.select(&req).unwrap().report(false);
+        assert_eq!(pool.upstreams()[0].circuit_state(), CircuitState::Open);
+
+        // The request holding the trial slot never completes and is cancelled.
+        let request = async {
+            let _permit = pool.select(&req).unwrap();
+            futures_util::future::pending::<()>().await;
+        };
+        assert!(tokio::time::timeout(Duration::from_millis(10), request).await.is_err());
+        assert_eq!(pool.upstreams()[0].circuit_state(), CircuitState::HalfOpen);
+
+        let permit = pool.select(&req).unwrap();
+        assert!(pool.select(&req).is_none());
+        permit.report(true);
+        assert_eq!(pool.upstreams()[0].circuit_state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,576 @@
+//! Security headers middleware.
+//!
+//! Sets `Strict-Transport-Security`, `X-Content-Type-Options`, `X-Frame-Options`, `Referrer-Policy`,
+//! `Permissions-Policy`, the `Cross-Origin-*-Policy` headers and `Content-Security-Policy`.
+//! Headers already set by inner handlers are left untouched, so a route can still override them.
+//!
+//! # Example
+//!
+//! ```
+//! use salvo_core::prelude::*;
+//! use salvo_extra::security_headers::{ContentSecurityPolicy, SecurityHeaders, SecurityHeadersDepotExt};
+//!
+//! #[handler]
+//! async fn index(depot: &mut Depot, res: &mut Response) {
+//!     let nonce = depot.csp_nonce().unwrap_or_default();
+//!     res.render(Text::Html(format!(r#"<script nonce="{}">console.log("hi")</script>"#, nonce)));
+//! }
+//!
+//! let csp = ContentSecurityPolicy::new()
+//!     .with_directive("default-src", ["'self'"])
+//!     .with_directive("script-src", ["'self'"])
+//!     .with_nonce("script-src");
+//! let router = Router::new().hoop(SecurityHeaders::new().with_csp(csp)).get(index);
+//! ```
+use std::fmt::{self, Display, Formatter};
+
+use rand::RngCore;
+use salvo_core::async_trait;
+use salvo_core::http::header::{
+    HeaderName, HeaderValue, CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY, REFERRER_POLICY,
+    STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use salvo_core::http::{Request, Response};
+use salvo_core::routing::FlowCtrl;
+use salvo_core::{Depot, Handler};
+
+/// key used to insert the per-request CSP nonce to depot.
+pub const CSP_NONCE_KEY: &str = "::salvo::extra::security_headers::csp_nonce";
+
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+const CROSS_ORIGIN_OPENER_POLICY: HeaderName = HeaderName::from_static("cross-origin-opener-policy");
+const CROSS_ORIGIN_EMBEDDER_POLICY: HeaderName = HeaderName::from_static("cross-origin-embedder-policy");
+const CROSS_ORIGIN_RESOURCE_POLICY: HeaderName = HeaderName::from_static("cross-origin-resource-policy");
+
+/// SecurityHeadersDepotExt
+pub trait SecurityHeadersDepotExt {
+    /// Get the CSP nonce generated for current request.
+    fn csp_nonce(&self) -> Option<&str>;
+}
+
+impl SecurityHeadersDepotExt for Depot {
+    #[inline]
+    fn csp_nonce(&self) -> Option<&str> {
+        self.get::<String>(CSP_NONCE_KEY).map(|s| &**s)
+    }
+}
+
+macro_rules! header_value_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident => $value:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+        }
+        impl $name {
+            /// Get header value str.
+            #[inline]
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value,)+
+                }
+            }
+        }
+        impl Display for $name {
+            #[inline]
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+        impl From<$name> for HeaderValue {
+            #[inline]
+            fn from(value: $name) -> Self {
+                HeaderValue::from_static(value.as_str())
+            }
+        }
+    };
+}
+
+header_value_enum! {
+    /// Value of `X-Frame-Options` header.
+    pub enum FrameOptions {
+        /// `DENY`
+        Deny => "DENY",
+        /// `SAMEORIGIN`
+        SameOrigin => "SAMEORIGIN",
+    }
+}
+
+header_value_enum! {
+    /// Value of `Referrer-Policy` header.
+    pub enum ReferrerPolicy {
+        /// `no-referrer`
+        NoReferrer => "no-referrer",
+        /// `no-referrer-when-downgrade`
+        NoReferrerWhenDowngrade => "no-referrer-when-downgrade",
+        /// `origin`
+        Origin => "origin",
+        /// `origin-when-cross-origin`
+        OriginWhenCrossOrigin => "origin-when-cross-origin",
+        /// `same-origin`
+        SameOrigin => "same-origin",
+        /// `strict-origin`
+        StrictOrigin => "strict-origin",
+        /// `strict-origin-when-cross-origin`
+        StrictOriginWhenCrossOrigin => "strict-origin-when-cross-origin",
+        /// `unsafe-url`
+        UnsafeUrl => "unsafe-url",
+    }
+}
+
+header_value_enum! {
+    /// Value of `Cross-Origin-Opener-Policy` header.
+    pub enum CrossOriginOpenerPolicy {
+        /// `unsafe-none`
+        UnsafeNone => "unsafe-none",
+        /// `same-origin-allow-popups`
+        SameOriginAllowPopups => "same-origin-allow-popups",
+        /// `same-origin`
+        SameOrigin => "same-origin",
+    }
+}
+
+header_value_enum! {
+    /// Value of `Cross-Origin-Embedder-Policy` header.
+    pub enum CrossOriginEmbedderPolicy {
+        /// `unsafe-none`
+        UnsafeNone => "unsafe-none",
+        /// `require-corp`
+        RequireCorp => "require-corp",
+        /// `credentialless`
+        Credentialless => "credentialless",
+    }
+}
+
+header_value_enum! {
+    /// Value of `Cross-Origin-Resource-Policy` header.
+    pub enum CrossOriginResourcePolicy {
+        /// `same-site`
+        SameSite => "same-site",
+        /// `same-origin`
+        SameOrigin => "same-origin",
+        /// `cross-origin`
+        CrossOrigin => "cross-origin",
+    }
+}
+
+/// Builder of `Content-Security-Policy` header value.
+#[derive(Clone, Debug, Default)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<String>)>,
+    nonce_directives: Vec<String>,
+    report_only: bool,
+}
+
+impl ContentSecurityPolicy {
+    /// Create a new empty `ContentSecurityPolicy`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds sources to a directive, such as `script-src`. The directive is created if it does not exist.
+    ///
+    /// Pass an empty list for directives without sources, such as `upgrade-insecure-requests`.
+    #[inline]
+    pub fn with_directive<N, I>(mut self, name: N, sources: I) -> Self
+    where
+        N: Into<String>,
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let name = name.into();
+        let sources = sources.into_iter().map(Into::into);
+        match self.directives.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, values)) => values.extend(sources),
+            None => self.directives.push((name, sources.collect())),
+        }
+        self
+    }
+
+    /// Appends the per-request `'nonce-...'` source to a directive, such as `script-src` or `style-src`.
+    ///
+    /// A directive which is not set is created with the sources of `default-src`, or `'self'` without it, so the
+    /// nonce doesn't block resources the `default-src` fallback allowed.
+    ///
+    /// The nonce is inserted into the depot with [`CSP_NONCE_KEY`], use [`SecurityHeadersDepotExt::csp_nonce`]
+    /// to read it in templates.
+    #[inline]
+    pub fn with_nonce(mut self, directive: impl Into<String>) -> Self {
+        let directive = directive.into();
+        if !self.nonce_directives.contains(&directive) {
+            self.nonce_directives.push(directive);
+        }
+        self
+    }
+
+    /// Get whether the policy is sent with `Content-Security-Policy-Report-Only` header.
+    #[inline]
+    pub fn report_only(&self) -> bool {
+        self.report_only
+    }
+    /// Sets whether the policy is sent with `Content-Security-Policy-Report-Only` header.
+    #[inline]
+    pub fn with_report_only(mut self, report_only: bool) -> Self {
+        self.report_only = report_only;
+        self
+    }
+
+    /// Returns `true` if a nonce is needed for this policy.
+    #[inline]
+    pub fn needs_nonce(&self) -> bool {
+        !self.nonce_directives.is_empty()
+    }
+
+    /// Build header value string, `nonce` is appended to all nonce directives.
+    pub fn to_header_string(&self, nonce: Option<&str>) -> String {
+        let mut directives = self.directives.clone();
+        if let Some(nonce) = nonce {
+            let fallback = match self.directives.iter().find(|(n, _)| n == "default-src") {
+                Some((_, values)) => values.iter().filter(|v| *v != "'none'").cloned().collect(),
+                None => vec!["'self'".to_owned()],
+            };
+            for name in &self.nonce_directives {
+                let source = format!("'nonce-{}'", nonce);
+                match directives.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, values)) => values.push(source),
+                    None => {
+                        let mut values = fallback.clone();
+                        values.push(source);
+                        directives.push((name.clone(), values));
+                    }
+                }
+            }
+        }
+        directives
+            .iter()
+            .map(|(name, values)| {
+                if values.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{} {}", name, values.join(" "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// SecurityHeaders
+///
+/// `SecurityHeaders::new()` uses defaults which suit most sites:
+///
+/// - `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+/// - `X-Content-Type-Options: nosniff`
+/// - `X-Frame-Options: DENY`
+/// - `Referrer-Policy: strict-origin-when-cross-origin`
+/// - `Cross-Origin-Opener-Policy: same-origin`
+/// - `Cross-Origin-Resource-Policy: same-origin`
+///
+/// `Permissions-Policy`, `Cross-Origin-Embedder-Policy` and `Content-Security-Policy` are not sent unless configured.
+#[derive(Clone, Debug)]
+pub struct SecurityHeaders {
+    hsts: Option<String>,
+    content_type_options: bool,
+    frame_options: Option<FrameOptions>,
+    referrer_policy: Option<ReferrerPolicy>,
+    permissions_policy: Option<String>,
+    coop: Option<CrossOriginOpenerPolicy>,
+    coep: Option<CrossOriginEmbedderPolicy>,
+    corp: Option<CrossOriginResourcePolicy>,
+    csp: Option<ContentSecurityPolicy>,
+}
+
+impl Default for SecurityHeaders {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecurityHeaders {
+    /// Create a new `SecurityHeaders` with default policies.
+    #[inline]
+    pub fn new() -> Self {
+        SecurityHeaders {
+            hsts: Some("max-age=31536000; includeSubDomains".into()),
+            content_type_options: true,
+            frame_options: Some(FrameOptions::Deny),
+            referrer_policy: Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+            permissions_policy: None,
+            coop: Some(CrossOriginOpenerPolicy::SameOrigin),
+            coep: None,
+            corp: Some(CrossOriginResourcePolicy::SameOrigin),
+            csp: None,
+        }
+    }
+    /// Create a new `SecurityHeaders` which sends no header at all, policies must be enabled one by one.
+    #[inline]
+    pub fn empty() -> Self {
+        SecurityHeaders {
+            hsts: None,
+            content_type_options: false,
+            frame_options: None,
+            referrer_policy: None,
+            permissions_policy: None,
+            coop: None,
+            coep: None,
+            corp: None,
+            csp: None,
+        }
+    }
+
+    /// Sets `Strict-Transport-Security` header, `max_age` is in seconds.
+    #[inline]
+    pub fn with_hsts(mut self, max_age: u64, include_subdomains: bool, preload: bool) -> Self {
+        let mut value = format!("max-age={}", max_age);
+        if include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if preload {
+            value.push_str("; preload");
+        }
+        self.hsts = Some(value);
+        self
+    }
+    /// Disables `Strict-Transport-Security` header.
+    #[inline]
+    pub fn without_hsts(mut self) -> Self {
+        self.hsts = None;
+        self
+    }
+
+    /// Sets whether to send `X-Content-Type-Options: nosniff`.
+    #[inline]
+    pub fn with_content_type_options(mut self, nosniff: bool) -> Self {
+        self.content_type_options = nosniff;
+        self
+    }
+
+    /// Sets `X-Frame-Options` header, `None` disables it.
+    #[inline]
+    pub fn with_frame_options(mut self, value: impl Into<Option<FrameOptions>>) -> Self {
+        self.frame_options = value.into();
+        self
+    }
+
+    /// Sets `Referrer-Policy` header, `None` disables it.
+    #[inline]
+    pub fn with_referrer_policy(mut self, value: impl Into<Option<ReferrerPolicy>>) -> Self {
+        self.referrer_policy = value.into();
+        self
+    }
+
+    /// Sets `Permissions-Policy` header, such as `camera=(), geolocation=(self)`.
+    #[inline]
+    pub fn with_permissions_policy(mut self, value: impl Into<String>) -> Self {
+        self.permissions_policy = Some(value.into());
+        self
+    }
+
+    /// Sets `Cross-Origin-Opener-Policy` header, `None` disables it.
+    #[inline]
+    pub fn with_cross_origin_opener_policy(mut self, value: impl Into<Option<CrossOriginOpenerPolicy>>) -> Self {
+        self.coop = value.into();
+        self
+    }
+    /// Sets `Cross-Origin-Embedder-Policy` header, `None` disables it.
+    #[inline]
+    pub fn with_cross_origin_embedder_policy(mut self, value: impl Into<Option<CrossOriginEmbedderPolicy>>) -> Self {
+        self.coep = value.into();
+        self
+    }
+    /// Sets `Cross-Origin-Resource-Policy` header, `None` disables it.
+    #[inline]
+    pub fn with_cross_origin_resource_policy(mut self, value: impl Into<Option<CrossOriginResourcePolicy>>) -> Self {
+        self.corp = value.into();
+        self
+    }
+
+    /// Get content security policy reference.
+    #[inline]
+    pub fn csp(&self) -> Option<&ContentSecurityPolicy> {
+        self.csp.as_ref()
+    }
+    /// Sets `Content-Security-Policy` header.
+    #[inline]
+    pub fn with_csp(mut self, csp: ContentSecurityPolicy) -> Self {
+        self.csp = Some(csp);
+        self
+    }
+}
+
+#[inline]
+fn generate_nonce() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::encode(bytes)
+}
+
+#[async_trait]
+impl Handler for SecurityHeaders {
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        let nonce = match &self.csp {
+            Some(csp) if csp.needs_nonce() => {
+                let nonce = generate_nonce();
+                depot.insert(CSP_NONCE_KEY, nonce.clone());
+                Some(nonce)
+            }
+            _ => None,
+        };
+        ctrl.call_next(req, depot, res).await;
+
+        let mut headers = Vec::with_capacity(9);
+        if let Some(hsts) = &self.hsts {
+            headers.push((STRICT_TRANSPORT_SECURITY, HeaderValue::from_str(hsts).ok()));
+        }
+        if self.content_type_options {
+            headers.push((X_CONTENT_TYPE_OPTIONS, Some(HeaderValue::from_static("nosniff"))));
+        }
+        if let Some(value) = self.frame_options {
+            headers.push((X_FRAME_OPTIONS, Some(value.into())));
+        }
+        if let Some(value) = self.referrer_policy {
+            headers.push((REFERRER_POLICY, Some(value.into())));
+        }
+        if let Some(value) = &self.permissions_policy {
+            headers.push((PERMISSIONS_POLICY, HeaderValue::from_str(value).ok()));
+        }
+        if let Some(value) = self.coop {
+            headers.push((CROSS_ORIGIN_OPENER_POLICY, Some(value.into())));
+        }
+        if let Some(value) = self.coep {
+            headers.push((CROSS_ORIGIN_EMBEDDER_POLICY, Some(value.into())));
+        }
+        if let Some(value) = self.corp {
+            headers.push((CROSS_ORIGIN_RESOURCE_POLICY, Some(value.into())));
+        }
+        if let Some(csp) = &self.csp {
+            let name = if csp.report_only() {
+                CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                CONTENT_SECURITY_POLICY
+            };
+            headers.push((
+                name,
+                HeaderValue::from_str(&csp.to_header_string(nonce.as_deref())).ok(),
+            ));
+        }
+        for (name, value) in headers {
+            match value {
+                Some(value) => {
+                    res.headers_mut().entry(name).or_insert(value);
+                }
+                None => {
+                    tracing::error!(header = %name, "invalid security header value");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo_core::http::header::HeaderMap;
+    use salvo_core::prelude::*;
+    use salvo_core::test::{ResponseExt, TestClient};
+
+    use super::*;
+
+    #[handler]
+    async fn index(depot: &mut Depot) -> String {
+        depot.csp_nonce().unwrap_or_default().to_owned()
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+        headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    #[test]
+    fn test_csp_header_string() {
+        let csp = ContentSecurityPolicy::new()
+            .with_directive("default-src", ["'self'"])
+            .with_directive("script-src", ["'self'"])
+            .with_directive("script-src", ["https://cdn.example.com"])
+            .with_directive("upgrade-insecure-requests", Vec::<String>::new())
+            .with_nonce("script-src")
+            .with_nonce("style-src");
+        assert_eq!(
+            csp.to_header_string(Some("abc")),
+            "default-src 'self'; script-src 'self' https://cdn.example.com 'nonce-abc'; \
+             upgrade-insecure-requests; style-src 'self' 'nonce-abc'"
+        );
+        assert_eq!(
+            csp.to_header_string(None),
+            "default-src 'self'; script-src 'self' https://cdn.example.com; upgrade-insecure-requests"
+        );
+
+        let csp = ContentSecurityPolicy::new()
+            .with_directive("default-src", ["'none'"])
+            .with_nonce("script-src");
+        assert_eq!(
+            csp.to_header_string(Some("abc")),
+            "default-src 'none'; script-src 'nonce-abc'"
+        );
+        let csp = ContentSecurityPolicy::new().with_nonce("script-src");
+        assert_eq!(csp.to_header_string(Some("abc")), "script-src 'self' 'nonce-abc'");
+    }
+
+    #[tokio::test]
+    async fn test_security_headers() {
+        let csp = ContentSecurityPolicy::new()
+            .with_directive("default-src", ["'self'"])
+            .with_nonce("script-src");
+        let router = Router::new()
+            .hoop(
+                SecurityHeaders::new()
+                    .with_csp(csp)
+                    .with_frame_options(FrameOptions::SameOrigin),
+            )
+            .get(index);
+        let service = Service::new(router);
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        let nonce = res.take_string().await.unwrap();
+        assert_eq!(nonce.len(), 24);
+        let headers = res.headers();
+        assert_eq!(
+            header(headers, "strict-transport-security"),
+            Some("max-age=31536000; includeSubDomains")
+        );
+        assert_eq!(header(headers, "x-content-type-options"), Some("nosniff"));
+        assert_eq!(header(headers, "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(
+            header(headers, "referrer-policy"),
+            Some("strict-origin-when-cross-origin")
+        );
+        assert_eq!(header(headers, "cross-origin-embedder-policy"), None);
+        assert_eq!(
+            header(headers, "content-security-policy"),
+            Some(&*format!("default-src 'self'; script-src 'self' 'nonce-{}'", nonce))
+        );
+
+        let mut other = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_ne!(other.take_string().await.unwrap(), nonce);
+    }
+
+    #[tokio::test]
+    async fn test_security_headers_keep_existing() {
+        #[handler]
+        async fn framed(res: &mut Response) {
+            res.headers_mut()
+                .insert(X_FRAME_OPTIONS, HeaderValue::from_static("SAMEORIGIN"));
+        }
+        let router = Router::new()
+            .hoop(SecurityHeaders::empty().with_frame_options(FrameOptions::Deny))
+            .get(framed);
+        let res = TestClient::get("http://127.0.0.1:7979/").send(router).await;
+        assert_eq!(header(res.headers(), "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(header(res.headers(), "x-content-type-options"), None);
+    }
+}
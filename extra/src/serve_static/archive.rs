@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Error as IoError;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use bytes::Bytes;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use crc32fast::Hasher;
+use futures_util::stream::{self, Stream};
+use tokio::io::AsyncReadExt;
+
+use super::store::{StaticStore, StoreMetadata, StoreReader};
+
+const BLOCK_SIZE: usize = 512;
+const BUFFER_SIZE: u64 = 64 * 1024;
+
+/// A file or directory added to an archive.
+#[derive(Debug)]
+pub(super) struct ArchiveEntry {
+    pub(super) path: String,
+    pub(super) is_dir: bool,
+    pub(super) size: u64,
+    pub(super) mtime: u64,
+}
+impl ArchiveEntry {
+    pub(super) fn new(path: String, metadata: &StoreMetadata) -> Self {
+        ArchiveEntry {
+            path,
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            mtime: metadata
+                .modified()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+enum Part {
+    Bytes(Bytes),
+    File { path: String, size: u64 },
+}
+
+/// Streams a ustar archive of `entries` read from `store`, keyed by their path in the archive.
+///
+/// Files are read while streaming; a file that shrank is padded with zeros and a file that grew is truncated so
+/// the archive stays consistent with its headers.
+pub(super) fn tar_stream(
+    store: Arc<dyn StaticStore>,
+    entries: BTreeMap<String, ArchiveEntry>,
+) -> impl Stream<Item = Result<Bytes, IoError>> + Send + 'static {
+    let mut parts = VecDeque::with_capacity(entries.len() * 2 + 1);
+    for (name, entry) in entries {
+        let header = match header(&name, &entry) {
+            Some(header) => header,
+            None => {
+                tracing::warn!(name = %name, "path is too long for tar archive, skipped");
+                continue;
+            }
+        };
+        parts.push_back(Part::Bytes(Bytes::from(header)));
+        if !entry.is_dir && entry.size > 0 {
+            parts.push_back(Part::File {
+                path: entry.path,
+                size: entry.size,
+            });
+        }
+    }
+    parts.push_back(Part::Bytes(Bytes::from(vec![0; BLOCK_SIZE * 2])));
+
+    let state = (store, parts, None::<(Box<dyn StoreReader>, u64)>);
+    stream::unfold(state, |(store, mut parts, mut reading)| async move {
+        loop {
+            if let Some((mut file, remaining)) = reading.take() {
+                let mut buf = vec![0; BUFFER_SIZE.min(remaining) as usize];
+                let read = match file.read(&mut buf).await {
+                    Ok(read) => read,
+                    Err(e) => return Some((Err(e), (store, VecDeque::new(), None))),
+                };
+                // The file shrank if nothing is read, the zero filled buffer keeps the archive valid.
+                if read > 0 {
+                    buf.truncate(read);
+                }
+                let remaining = remaining - buf.len() as u64;
+                if remaining > 0 {
+                    reading = Some((file, remaining));
+                }
+                return Some((Ok(Bytes::from(buf)), (store, parts, reading)));
+            }
+            match parts.pop_front()? {
+                Part::Bytes(bytes) => return Some((Ok(bytes), (store, parts, None))),
+                Part::File { path, size } => {
+                    let file = match store.open(&path).await {
+                        Ok(file) => file,
+                        Err(e) => return Some((Err(e), (store, VecDeque::new(), None))),
+                    };
+                    let padding = (BLOCK_SIZE - (size as usize % BLOCK_SIZE)) % BLOCK_SIZE;
+                    if padding > 0 {
+                        parts.push_front(Part::Bytes(Bytes::from(vec![0; padding])));
+                    }
+                    reading = Some((file, size));
+                }
+            }
+        }
+    })
+}
+
+/// Sizes and offsets from this value on are stored in zip64 fields.
+const ZIP64_LIMIT: u64 = u32::MAX as u64;
+
+struct ZipFile {
+    reader: Box<dyn StoreReader>,
+    remaining: u64,
+    hasher: Hasher,
+    record: ZipRecord,
+}
+
+/// Fields of a zip entry repeated in the central directory.
+struct ZipRecord {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    crc: u32,
+    time: u16,
+    date: u16,
+    offset: u64,
+}
+impl ZipRecord {
+    /// Returns `true` if the sizes of the entry are written as zip64 fields.
+    fn has_zip64_size(&self) -> bool {
+        self.size >= ZIP64_LIMIT
+    }
+    fn version_needed(&self) -> u16 {
+        if self.has_zip64_size() || self.offset >= ZIP64_LIMIT {
+            45
+        } else {
+            20
+        }
+    }
+}
+
+struct ZipState {
+    store: Arc<dyn StaticStore>,
+    entries: std::collections::btree_map::IntoIter<String, ArchiveEntry>,
+    reading: Option<ZipFile>,
+    pending: Option<Bytes>,
+    offset: u64,
+    central: Vec<u8>,
+    count: u64,
+    finished: bool,
+}
+
+/// Streams a zip archive of `entries` read from `store` with stored (uncompressed) entries, keyed by their path
+/// in the archive.
+///
+/// CRC-32 checksums are computed while streaming and written in data descriptors after file contents. Like
+/// [`tar_stream`], file sizes are fixed by the entries. Zip64 records are written for files and archives larger
+/// than 4 GiB or with more than 65535 entries.
+pub(super) fn zip_stream(
+    store: Arc<dyn StaticStore>,
+    entries: BTreeMap<String, ArchiveEntry>,
+) -> impl Stream<Item = Result<Bytes, IoError>> + Send + 'static {
+    let state = ZipState {
+        store,
+        entries: entries.into_iter(),
+        reading: None,
+        pending: None,
+        offset: 0,
+        central: Vec::new(),
+        count: 0,
+        finished: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+        match state.next_chunk().await {
+            Ok(Some(bytes)) => {
+                state.offset += bytes.len() as u64;
+                Some((Ok(bytes), state))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                state.finished = true;
+                Some((Err(e), state))
+            }
+        }
+    })
+}
+
+impl ZipState {
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, IoError> {
+        if let Some(bytes) = self.pending.take() {
+            return Ok(Some(bytes));
+        }
+        if let Some(mut file) = self.reading.take() {
+            let mut buf = vec![0; BUFFER_SIZE.min(file.remaining) as usize];
+            let read = file.reader.read(&mut buf).await?;
+            // The file shrank if nothing is read, the zero filled buffer keeps the archive valid.
+            if read > 0 {
+                buf.truncate(read);
+            }
+            file.hasher.update(&buf);
+            file.remaining -= buf.len() as u64;
+            if file.remaining > 0 {
+                self.reading = Some(file);
+            } else {
+                let ZipFile { hasher, mut record, .. } = file;
+                record.crc = hasher.finalize();
+                self.pending = Some(Bytes::from(data_descriptor(&record)));
+                self.push_central(&record);
+            }
+            return Ok(Some(Bytes::from(buf)));
+        }
+        let (name, entry) = match self.entries.next() {
+            Some(entry) => entry,
+            None => {
+                self.finished = true;
+                return Ok(Some(self.end_of_central_directory()));
+            }
+        };
+        self.count += 1;
+        let (time, date) = dos_date_time(entry.mtime);
+        let mut record = ZipRecord {
+            name: if entry.is_dir { format!("{}/", name) } else { name },
+            is_dir: entry.is_dir,
+            size: if entry.is_dir { 0 } else { entry.size },
+            crc: 0,
+            time,
+            date,
+            offset: self.offset,
+        };
+        let header = local_header(&record);
+        if record.is_dir {
+            self.push_central(&record);
+        } else if record.size == 0 {
+            record.crc = Hasher::new().finalize();
+            self.pending = Some(Bytes::from(data_descriptor(&record)));
+            self.push_central(&record);
+        } else {
+            self.reading = Some(ZipFile {
+                reader: self.store.open(&entry.path).await?,
+                remaining: record.size,
+                hasher: Hasher::new(),
+                record,
+            });
+        }
+        Ok(Some(Bytes::from(header)))
+    }
+
+    fn push_central(&mut self, record: &ZipRecord) {
+        // Zip64 extra field holding the sizes and offset which do not fit in the record.
+        let mut extra = Vec::new();
+        if record.has_zip64_size() {
+            extra.extend_from_slice(&record.size.to_le_bytes());
+            extra.extend_from_slice(&record.size.to_le_bytes());
+        }
+        if record.offset >= ZIP64_LIMIT {
+            extra.extend_from_slice(&record.offset.to_le_bytes());
+        }
+        let size = record.size.min(ZIP64_LIMIT) as u32;
+
+        let central = &mut self.central;
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        // Made by on Unix, so that external attributes hold the file mode.
+        central.extend_from_slice(&(0x0300u16 | record.version_needed()).to_le_bytes());
+        central.extend_from_slice(&record.version_needed().to_le_bytes());
+        central.extend_from_slice(&flags(record).to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&record.time.to_le_bytes());
+        central.extend_from_slice(&record.date.to_le_bytes());
+        central.extend_from_slice(&record.crc.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&(record.name.len() as u16).to_le_bytes());
+        let extra_len = if extra.is_empty() { 0 } else { extra.len() as u16 + 4 };
+        central.extend_from_slice(&extra_len.to_le_bytes());
+        // Comment, disk number and internal attributes.
+        central.extend_from_slice(&[0; 6]);
+        let attributes = if record.is_dir {
+            (0o40755u32 << 16) | 0x10
+        } else {
+            0o100644u32 << 16
+        };
+        central.extend_from_slice(&attributes.to_le_bytes());
+        central.extend_from_slice(&(record.offset.min(ZIP64_LIMIT) as u32).to_le_bytes());
+        central.extend_from_slice(record.name.as_bytes());
+        if !extra.is_empty() {
+            central.extend_from_slice(&1u16.to_le_bytes());
+            central.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            central.extend_from_slice(&extra);
+        }
+    }
+
+    fn end_of_central_directory(&mut self) -> Bytes {
+        let offset = self.offset;
+        let size = self.central.len() as u64;
+        let mut central = std::mem::take(&mut self.central);
+        if self.count >= u16::MAX as u64 || offset >= ZIP64_LIMIT || size >= ZIP64_LIMIT {
+            // Zip64 end of central directory record and its locator.
+            let record_offset = offset + size;
+            central.extend_from_slice(&0x0606_4b50u32.to_le_bytes());
+            central.extend_from_slice(&44u64.to_le_bytes());
+            central.extend_from_slice(&(0x0300u16 | 45).to_le_bytes());
+            central.extend_from_slice(&45u16.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&self.count.to_le_bytes());
+            central.extend_from_slice(&self.count.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(&0x0706_4b50u32.to_le_bytes());
+            central.extend_from_slice(&0u32.to_le_bytes());
+            central.extend_from_slice(&record_offset.to_le_bytes());
+            central.extend_from_slice(&1u32.to_le_bytes());
+        }
+        let count = self.count.min(u16::MAX as u64) as u16;
+        central.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        central.extend_from_slice(&[0; 4]);
+        central.extend_from_slice(&count.to_le_bytes());
+        central.extend_from_slice(&count.to_le_bytes());
+        central.extend_from_slice(&(size.min(ZIP64_LIMIT) as u32).to_le_bytes());
+        central.extend_from_slice(&(offset.min(ZIP64_LIMIT) as u32).to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes());
+        Bytes::from(central)
+    }
+}
+
+/// General purpose flags: names are UTF-8 and files have a data descriptor.
+fn flags(record: &ZipRecord) -> u16 {
+    if record.is_dir {
+        0x0800
+    } else {
+        0x0808
+    }
+}
+
+fn local_header(record: &ZipRecord) -> Vec<u8> {
+    let mut header = Vec::with_capacity(50 + record.name.len());
+    header.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+    header.extend_from_slice(&record.version_needed().to_le_bytes());
+    header.extend_from_slice(&flags(record).to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&record.time.to_le_bytes());
+    header.extend_from_slice(&record.date.to_le_bytes());
+    // CRC-32 and sizes are in the data descriptor, a zip64 extra field tells readers that its sizes are 8 bytes.
+    header.extend_from_slice(&0u32.to_le_bytes());
+    if record.has_zip64_size() {
+        header.extend_from_slice(&[0xff; 8]);
+    } else {
+        header.extend_from_slice(&[0; 8]);
+    }
+    header.extend_from_slice(&(record.name.len() as u16).to_le_bytes());
+    header.extend_from_slice(&(if record.has_zip64_size() { 20u16 } else { 0 }).to_le_bytes());
+    header.extend_from_slice(record.name.as_bytes());
+    if record.has_zip64_size() {
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(&[0; 16]);
+    }
+    header
+}
+
+fn data_descriptor(record: &ZipRecord) -> Vec<u8> {
+    let mut descriptor = Vec::with_capacity(24);
+    descriptor.extend_from_slice(&0x0807_4b50u32.to_le_bytes());
+    descriptor.extend_from_slice(&record.crc.to_le_bytes());
+    if record.has_zip64_size() {
+        descriptor.extend_from_slice(&record.size.to_le_bytes());
+        descriptor.extend_from_slice(&record.size.to_le_bytes());
+    } else {
+        descriptor.extend_from_slice(&(record.size as u32).to_le_bytes());
+        descriptor.extend_from_slice(&(record.size as u32).to_le_bytes());
+    }
+    descriptor
+}
+
+/// Converts a unix timestamp to MS-DOS time and date, times before 1980 are clamped.
+fn dos_date_time(mtime: u64) -> (u16, u16) {
+    let datetime = match DateTime::<Utc>::from_timestamp(mtime as i64, 0) {
+        Some(datetime) if datetime.year() >= 1980 => datetime,
+        _ => return (0, (1 << 5) | 1),
+    };
+    let time = (datetime.hour() << 11) | (datetime.minute() << 5) | (datetime.second() / 2);
+    let date = ((datetime.year() as u32 - 1980) << 9) | (datetime.month() << 5) | datetime.day();
+    (time as u16, date as u16)
+}
+
+/// Builds a ustar header block, returns `None` if `name` does not fit.
+fn header(name: &str, entry: &ArchiveEntry) -> Option<Vec<u8>> {
+    let mut name = name.to_owned();
+    if entry.is_dir {
+        name.push('/');
+    }
+    let (prefix, name) = split_name(&name)?;
+
+    let mut block = vec![0u8; BLOCK_SIZE];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut block[100..108], if entry.is_dir { 0o755 } else { 0o644 });
+    write_octal(&mut block[108..116], 0);
+    write_octal(&mut block[116..124], 0);
+    write_octal(&mut block[124..136], entry.size);
+    write_octal(&mut block[136..148], entry.mtime);
+    block[156] = if entry.is_dir { b'5' } else { b'0' };
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is computed with the checksum field filled with spaces.
+    block[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = block.iter().map(|b| *b as u64).sum();
+    write_octal(&mut block[148..155], checksum);
+    block[155] = b' ';
+    Some(block)
+}
+
+/// Splits a path into the ustar prefix and name fields.
+fn split_name(path: &str) -> Option<(&str, &str)> {
+    if path.len() <= 100 {
+        return Some(("", path));
+    }
+    let trimmed = path.trim_end_matches('/');
+    path.char_indices()
+        .filter(|(i, c)| *c == '/' && *i < trimmed.len())
+        .map(|(i, _)| (&path[..i], &path[i + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100)
+}
+
+/// Writes `value` as a zero-padded octal number followed by a NUL byte.
+fn write_octal(field: &mut [u8], value: u64) {
+    let len = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = len);
+    if digits.len() > len {
+        // Does not fit, keep the field as large as possible.
+        field[..len].fill(b'7');
+    } else {
+        field[..len].copy_from_slice(digits.as_bytes());
+    }
+    field[len] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::super::store::MemoryStore;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tar_stream() {
+        let store = MemoryStore::new().with_file("sub/a.txt", "hello");
+        let mut entries = BTreeMap::new();
+        let metadata = store.metadata("sub").await.unwrap();
+        entries.insert("sub".to_owned(), ArchiveEntry::new("sub".to_owned(), &metadata));
+        let metadata = store.metadata("sub/a.txt").await.unwrap();
+        entries.insert(
+            "sub/a.txt".to_owned(),
+            ArchiveEntry::new("sub/a.txt".to_owned(), &metadata),
+        );
+
+        let mut archive = Vec::new();
+        let mut stream = Box::pin(tar_stream(Arc::new(store), entries));
+        while let Some(chunk) = stream.next().await {
+            archive.extend_from_slice(&chunk.unwrap());
+        }
+
+        assert_eq!(archive.len(), BLOCK_SIZE * 5);
+        assert_eq!(&archive[..4], b"sub/");
+        assert_eq!(archive[156], b'5');
+        assert_eq!(&archive[BLOCK_SIZE..BLOCK_SIZE + 9], b"sub/a.txt");
+        assert_eq!(&archive[BLOCK_SIZE + 124..BLOCK_SIZE + 135], b"00000000005");
+        assert_eq!(&archive[BLOCK_SIZE * 2..BLOCK_SIZE * 2 + 5], b"hello");
+        assert!(archive[BLOCK_SIZE * 3..].iter().all(|b| *b == 0));
+
+        let checksum: u64 = archive[..BLOCK_SIZE]
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u64
+                } else {
+                    *b as u64
+                }
+            })
+            .sum();
+        assert_eq!(
+            std::str::from_utf8(&archive[148..154]).unwrap(),
+            format!("{:06o}", checksum)
+        );
+        assert_eq!(
+            split_name(&format!("{}/{}", "a".repeat(120), "b.txt")).unwrap().1,
+            "b.txt"
+        );
+        assert!(split_name(&"a".repeat(120)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zip_stream() {
+        let store = MemoryStore::new()
+            .with_file("sub/a.txt", "hello")
+            .with_file("sub/empty.txt", "");
+        let mut entries = BTreeMap::new();
+        for path in ["sub", "sub/a.txt", "sub/empty.txt"] {
+            let metadata = store.metadata(path).await.unwrap();
+            entries.insert(path.to_owned(), ArchiveEntry::new(path.to_owned(), &metadata));
+        }
+
+        let mut archive = Vec::new();
+        let mut stream = Box::pin(zip_stream(Arc::new(store), entries));
+        while let Some(chunk) = stream.next().await {
+            archive.extend_from_slice(&chunk.unwrap());
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([archive[i], archive[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([archive[i], archive[i + 1], archive[i + 2], archive[i + 3]]);
+        assert_eq!(u32_at(0), 0x0403_4b50);
+        assert_eq!(&archive[30..34], b"sub/");
+        // Local header and contents of `sub/a.txt` follow the directory entry.
+        assert_eq!(u32_at(34), 0x0403_4b50);
+        assert_eq!(u16_at(34 + 6), 0x0808);
+        assert_eq!(&archive[64..73], b"sub/a.txt");
+        assert_eq!(&archive[73..78], b"hello");
+        assert_eq!(u32_at(78), 0x0807_4b50);
+        assert_eq!(u32_at(82), 0x3610_a686);
+        assert_eq!(u32_at(86), 5);
+
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(end), 0x0605_4b50);
+        assert_eq!(u16_at(end + 10), 3);
+        let central_size = u32_at(end + 12) as usize;
+        let central_offset = u32_at(end + 16) as usize;
+        assert_eq!(central_offset + central_size, end);
+        assert_eq!(u32_at(central_offset), 0x0201_4b50);
+        // The central directory records the checksum of `sub/a.txt` and where its local header starts.
+        let second = central_offset + 46 + 4;
+        assert_eq!(u32_at(second), 0x0201_4b50);
+        assert_eq!(u32_at(second + 16), 0x3610_a686);
+        assert_eq!(u32_at(second + 42), 34);
+
+        assert_eq!(dos_date_time(0), (0, 0x21));
+        // 2020-09-13 12:26:40 UTC.
+        assert_eq!(
+            dos_date_time(1_600_000_000),
+            ((12 << 11) | (26 << 5) | 20, (40 << 9) | (9 << 5) | 13)
+        );
+    }
+
+    #[test]
+    fn test_zip64_records() {
+        let record = ZipRecord {
+            name: "large.bin".to_owned(),
+            is_dir: false,
+            size: 5 << 30,
+            crc: 1,
+            time: 0,
+            date: 0x21,
+            offset: 6 << 30,
+        };
+        let u64_at = |bytes: &[u8], i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+
+        let header = local_header(&record);
+        assert_eq!(&header[4..6], &45u16.to_le_bytes());
+        assert_eq!(&header[18..26], &[0xff; 8]);
+        assert_eq!(&header[39..43], &[1, 0, 16, 0]);
+        let descriptor = data_descriptor(&record);
+        assert_eq!(descriptor.len(), 24);
+        assert_eq!(u64_at(&descriptor, 8), 5 << 30);
+
+        let mut state = ZipState {
+            store: Arc::new(MemoryStore::new()),
+            entries: BTreeMap::new().into_iter(),
+            reading: None,
+            pending: None,
+            offset: 7 << 30,
+            central: Vec::new(),
+            count: 70_000,
+            finished: false,
+        };
+        state.push_central(&record);
+        let central_len = state.central.len();
+        assert_eq!(central_len, 46 + 9 + 28);
+        assert_eq!(&state.central[20..28], &[0xff; 8]);
+        assert_eq!(&state.central[42..46], &[0xff; 4]);
+        assert_eq!(&state.central[55..59], &[1, 0, 24, 0]);
+        assert_eq!(u64_at(&state.central, 59), 5 << 30);
+        assert_eq!(u64_at(&state.central, 75), 6 << 30);
+
+        let end = state.end_of_central_directory();
+        let zip64_end = central_len;
+        assert_eq!(&end[zip64_end..zip64_end + 4], &0x0606_4b50u32.to_le_bytes());
+        assert_eq!(u64_at(&end, zip64_end + 32), 70_000);
+        assert_eq!(u64_at(&end, zip64_end + 48), 7 << 30);
+        let locator = zip64_end + 56;
+        assert_eq!(&end[locator..locator + 4], &0x0706_4b50u32.to_le_bytes());
+        assert_eq!(u64_at(&end, locator + 8), (7 << 30) + central_len as u64);
+        assert_eq!(&end[locator + 20..locator + 24], &0x0605_4b50u32.to_le_bytes());
+        assert_eq!(&end[locator + 30..locator + 32], &[0xff; 2]);
+        assert_eq!(&end[locator + 36..locator + 40], &[0xff; 4]);
+        assert_eq!(end.len(), locator + 42);
+    }
+}
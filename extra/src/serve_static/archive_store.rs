@@ -0,0 +1,630 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Error as IoError, ErrorKind, Read, Result as IoResult, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use chrono::NaiveDate;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use futures_util::ready;
+use salvo_core::async_trait;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+use super::store::{normalize_path, StaticStore, StoreEntry, StoreMetadata, StoreReader};
+
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// Serves files from a tar, gzip compressed tar or zip archive.
+///
+/// The archive is indexed when the store is created, the format is detected from its content. Files of a tar or
+/// zip archive on disk are read from it when they are opened, a gzip compressed tar archive is decompressed in
+/// memory. Zip entries must be stored or deflated, and zip64 archives are not supported.
+///
+/// # Example
+///
+/// ```no_run
+/// use salvo_extra::serve_static::{ArchiveStore, DirHandler};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let handler = DirHandler::from_store(ArchiveStore::from_path("assets.tar.gz")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ArchiveStore {
+    source: Source,
+    files: BTreeMap<String, ArchiveFile>,
+    dirs: BTreeMap<String, Option<SystemTime>>,
+}
+
+#[derive(Clone, Debug)]
+enum Source {
+    Memory(Bytes),
+    File(PathBuf),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ArchiveFile {
+    offset: u64,
+    size: u64,
+    compressed_size: u64,
+    deflated: bool,
+    modified: Option<SystemTime>,
+}
+
+impl ArchiveStore {
+    /// Create a new `ArchiveStore` serving files of the archive in `data`.
+    pub fn from_bytes(data: impl Into<Bytes>) -> IoResult<Self> {
+        let mut data = data.into();
+        if data.starts_with(&[0x1f, 0x8b]) {
+            let mut decoded = Vec::new();
+            GzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+            data = Bytes::from(decoded);
+        }
+        let mut store = ArchiveStore {
+            source: Source::Memory(data.clone()),
+            files: BTreeMap::new(),
+            dirs: BTreeMap::new(),
+        };
+        store.index(Cursor::new(&data[..]))?;
+        Ok(store)
+    }
+
+    /// Create a new `ArchiveStore` serving files of the archive at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> IoResult<Self> {
+        let path = path.as_ref();
+        let mut file = std::fs::File::open(path)?;
+        let mut magic = [0; 2];
+        let is_gzip = file.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+        file.seek(SeekFrom::Start(0))?;
+        if is_gzip {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            return Self::from_bytes(data);
+        }
+        let mut store = ArchiveStore {
+            source: Source::File(path.to_owned()),
+            files: BTreeMap::new(),
+            dirs: BTreeMap::new(),
+        };
+        store.index(file)?;
+        Ok(store)
+    }
+
+    fn index<R: Read + Seek>(&mut self, mut reader: R) -> IoResult<()> {
+        let mut head = [0; 263];
+        let len = read_full(&mut reader, &mut head)?;
+        reader.seek(SeekFrom::Start(0))?;
+        if head[..len].starts_with(b"PK\x03\x04") || head[..len].starts_with(b"PK\x05\x06") {
+            self.index_zip(reader)
+        } else if len == head.len() && &head[257..262] == b"ustar" {
+            self.index_tar(reader)
+        } else {
+            Err(IoError::new(ErrorKind::InvalidData, "unknown archive format"))
+        }
+    }
+
+    fn index_tar<R: Read + Seek>(&mut self, mut reader: R) -> IoResult<()> {
+        let mut offset = 0;
+        let mut long_name = None;
+        let mut header = [0; TAR_BLOCK_SIZE as usize];
+        loop {
+            if read_full(&mut reader, &mut header)? < header.len() || header.iter().all(|b| *b == 0) {
+                return Ok(());
+            }
+            let size = parse_octal(&header[124..136])?;
+            let data_offset = offset + TAR_BLOCK_SIZE;
+            offset = data_offset + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+            let name = match header[156] {
+                // GNU long name and pax extended header, they set the name of the next entry.
+                b'L' | b'x' => {
+                    let mut data = vec![0; size as usize];
+                    reader.read_exact(&mut data)?;
+                    long_name = if header[156] == b'L' {
+                        Some(String::from_utf8_lossy(&data).trim_end_matches('\0').to_owned())
+                    } else {
+                        pax_path(&data).or(long_name)
+                    };
+                    reader.seek(SeekFrom::Start(offset))?;
+                    continue;
+                }
+                _ => match long_name.take() {
+                    Some(name) => name,
+                    None => {
+                        let name = c_str(&header[..100]);
+                        let prefix = c_str(&header[345..500]);
+                        if prefix.is_empty() {
+                            name
+                        } else {
+                            format!("{}/{}", prefix, name)
+                        }
+                    }
+                },
+            };
+            let modified = parse_octal(&header[136..148])
+                .ok()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+            match header[156] {
+                b'0' | 0 | b'7' if !name.ends_with('/') => self.insert_file(
+                    &name,
+                    ArchiveFile {
+                        offset: data_offset,
+                        size,
+                        compressed_size: size,
+                        deflated: false,
+                        modified,
+                    },
+                ),
+                b'5' | b'0' | 0 => self.insert_dir(&name, modified),
+                // Links and special files are not served.
+                _ => {}
+            }
+            reader.seek(SeekFrom::Start(offset))?;
+        }
+    }
+
+    fn index_zip<R: Read + Seek>(&mut self, mut reader: R) -> IoResult<()> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let tail_len = len.min(22 + u16::MAX as u64);
+        reader.seek(SeekFrom::Start(len - tail_len))?;
+        let mut tail = vec![0; tail_len as usize];
+        reader.read_exact(&mut tail)?;
+        let end = (0..tail.len().saturating_sub(21))
+            .rev()
+            .find(|i| tail[*i..].starts_with(b"PK\x05\x06"))
+            .map(|i| &tail[i..])
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "zip end of central directory not found"))?;
+        let count = le_u16(end, 10);
+        let central_offset = le_u32(end, 16);
+        if count == u16::MAX || central_offset == u32::MAX {
+            return Err(IoError::new(ErrorKind::InvalidData, "zip64 archives are not supported"));
+        }
+        let mut central = vec![0; le_u32(end, 12) as usize];
+        reader.seek(SeekFrom::Start(central_offset as u64))?;
+        reader.read_exact(&mut central)?;
+
+        let mut pos = 0;
+        for _ in 0..count {
+            let record = central
+                .get(pos..pos + 46)
+                .filter(|record| record.starts_with(b"PK\x01\x02"))
+                .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "invalid zip central directory"))?;
+            let flags = le_u16(record, 8);
+            let method = le_u16(record, 10);
+            let modified = dos_time(le_u16(record, 12), le_u16(record, 14));
+            let compressed_size = le_u32(record, 20) as u64;
+            let size = le_u32(record, 24) as u64;
+            let name_len = le_u16(record, 28) as usize;
+            let header_offset = le_u32(record, 42) as u64;
+            let next = pos + 46 + name_len + le_u16(record, 30) as usize + le_u16(record, 32) as usize;
+            let name = central
+                .get(pos + 46..pos + 46 + name_len)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "invalid zip central directory"))?;
+            pos = next;
+
+            if name.ends_with('/') {
+                self.insert_dir(&name, modified);
+                continue;
+            }
+            if flags & 1 != 0 || (method != 0 && method != 8) {
+                tracing::warn!(name = %name, method, "encrypted or unsupported zip entry, skipped");
+                continue;
+            }
+            let mut local = [0; 30];
+            reader.seek(SeekFrom::Start(header_offset))?;
+            reader.read_exact(&mut local)?;
+            if !local.starts_with(b"PK\x03\x04") {
+                return Err(IoError::new(ErrorKind::InvalidData, "invalid zip local header"));
+            }
+            let offset = header_offset + 30 + le_u16(&local, 26) as u64 + le_u16(&local, 28) as u64;
+            self.insert_file(
+                &name,
+                ArchiveFile {
+                    offset,
+                    size,
+                    compressed_size,
+                    deflated: method == 8,
+                    modified,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn insert_file(&mut self, name: &str, file: ArchiveFile) {
+        if let Some(path) = entry_path(name) {
+            self.files.insert(path, file);
+        }
+    }
+
+    fn insert_dir(&mut self, name: &str, modified: Option<SystemTime>) {
+        if let Some(path) = entry_path(name) {
+            self.dirs.insert(path, modified);
+        }
+    }
+
+    /// Returns the names of the direct children of the directory `path` in `paths`, with `true` if the child
+    /// is a directory containing the path.
+    fn children<'a, V>(paths: &'a BTreeMap<String, V>, path: &str) -> impl Iterator<Item = (&'a str, bool, &'a V)> {
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path)
+        };
+        let skip = prefix.len();
+        paths
+            .range(prefix.clone()..)
+            .take_while(move |(name, _)| name.starts_with(&prefix))
+            .map(move |(name, value)| match name[skip..].split_once('/') {
+                Some((dir, _)) => (dir, true, value),
+                None => (&name[skip..], false, value),
+            })
+    }
+}
+
+#[async_trait]
+impl StaticStore for ArchiveStore {
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata> {
+        if let Some(file) = self.files.get(path) {
+            Some(StoreMetadata::file(file.size, file.modified))
+        } else if let Some(modified) = self.dirs.get(path) {
+            Some(StoreMetadata::dir(*modified))
+        } else if path.is_empty()
+            || Self::children(&self.files, path).next().is_some()
+            || Self::children(&self.dirs, path).next().is_some()
+        {
+            Some(StoreMetadata::dir(None))
+        } else {
+            None
+        }
+    }
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>> {
+        if self
+            .metadata(path)
+            .await
+            .map(|metadata| metadata.is_file())
+            .unwrap_or(true)
+        {
+            return Err(IoError::new(ErrorKind::NotFound, "directory not found"));
+        }
+        let mut names = HashSet::new();
+        let mut entries = Vec::new();
+        let files = Self::children(&self.files, path).map(|(name, is_dir, file)| (name, (!is_dir).then_some(file)));
+        let dirs = Self::children(&self.dirs, path).map(|(name, _, _)| (name, None));
+        for (name, file) in files.chain(dirs) {
+            if !names.insert(name) {
+                continue;
+            }
+            let metadata = match file {
+                Some(file) => StoreMetadata::file(file.size, file.modified),
+                None => {
+                    let dir_path = if path.is_empty() {
+                        name.to_owned()
+                    } else {
+                        format!("{}/{}", path, name)
+                    };
+                    StoreMetadata::dir(self.dirs.get(&dir_path).copied().flatten())
+                }
+            };
+            entries.push(StoreEntry {
+                name: name.to_owned(),
+                metadata,
+            });
+        }
+        Ok(entries)
+    }
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>> {
+        let file = *self
+            .files
+            .get(path)
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "file not found"))?;
+        let compressed = match &self.source {
+            Source::Memory(data) => {
+                let end = file.offset + file.compressed_size;
+                if end > data.len() as u64 {
+                    return Err(IoError::new(ErrorKind::UnexpectedEof, "archive is truncated"));
+                }
+                data.slice(file.offset as usize..end as usize)
+            }
+            Source::File(archive) => {
+                let mut reader = tokio::fs::File::open(archive).await?;
+                reader.seek(SeekFrom::Start(file.offset)).await?;
+                if !file.deflated {
+                    return Ok(Box::new(EntryReader {
+                        file: reader,
+                        start: file.offset,
+                        len: file.size,
+                        pos: 0,
+                    }));
+                }
+                let mut data = vec![0; file.compressed_size as usize];
+                tokio::io::AsyncReadExt::read_exact(&mut reader, &mut data).await?;
+                Bytes::from(data)
+            }
+        };
+        if file.deflated {
+            let mut data = Vec::with_capacity(file.size as usize);
+            DeflateDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+            Ok(Box::new(Cursor::new(data)))
+        } else {
+            Ok(Box::new(Cursor::new(compressed)))
+        }
+    }
+}
+
+/// Reads a stored entry from an archive file.
+struct EntryReader {
+    file: tokio::fs::File,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+impl AsyncRead for EntryReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        let remaining = (this.len - this.pos).min(buf.remaining() as u64) as usize;
+        if remaining == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let mut data = vec![0; remaining];
+        let mut limited = ReadBuf::new(&mut data);
+        ready!(Pin::new(&mut this.file).poll_read(cx, &mut limited))?;
+        let read = limited.filled().len();
+        buf.put_slice(&data[..read]);
+        this.pos += read as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+impl AsyncSeek for EntryReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> IoResult<()> {
+        let this = self.get_mut();
+        let pos = match position {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => this.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => this.pos.checked_add_signed(delta),
+        }
+        .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "invalid seek to a negative position"))?;
+        Pin::new(&mut this.file).start_seek(SeekFrom::Start(this.start + pos.min(this.len)))
+    }
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<u64>> {
+        let this = self.get_mut();
+        let pos = ready!(Pin::new(&mut this.file).poll_complete(cx))?;
+        this.pos = pos.saturating_sub(this.start).min(this.len);
+        Poll::Ready(Ok(this.pos))
+    }
+}
+
+/// Normalizes the path of an archive entry, entries escaping the root are ignored.
+fn entry_path(name: &str) -> Option<String> {
+    let path = normalize_path(name);
+    if path.is_empty() || path.split('/').any(|part| part == "..") {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Reads until `buf` is full or the end of `reader`, returns the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> IoResult<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn c_str(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Parses a tar number field, octal or base-256 for large values.
+fn parse_octal(field: &[u8]) -> IoResult<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..].iter().fold(0, |value, b| (value << 8) | *b as u64));
+    }
+    let digits = std::str::from_utf8(field)
+        .map_err(|_| IoError::new(ErrorKind::InvalidData, "invalid tar header"))?
+        .trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| IoError::new(ErrorKind::InvalidData, "invalid tar header"))
+}
+
+/// Get the `path` record of a pax extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let data = std::str::from_utf8(data).ok()?;
+    let mut rest = data;
+    while let Some((len, _)) = rest.split_once(' ') {
+        let len = len.parse::<usize>().ok()?;
+        let record = rest.get(..len)?;
+        rest = &rest[len..];
+        if let Some(path) = record.split_once(' ')?.1.strip_prefix("path=") {
+            return Some(path.trim_end_matches('\n').to_owned());
+        }
+    }
+    None
+}
+
+fn dos_time(time: u16, date: u16) -> Option<SystemTime> {
+    let datetime = NaiveDate::from_ymd_opt(
+        1980 + (date >> 9) as i32,
+        ((date >> 5) & 0xf) as u32,
+        (date & 0x1f) as u32,
+    )?
+    .and_hms_opt(
+        (time >> 11) as u32,
+        ((time >> 5) & 0x3f) as u32,
+        ((time & 0x1f) * 2) as u32,
+    )?;
+    Some(SystemTime::from(datetime.and_utc()))
+}
+
+fn le_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn le_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+    use futures_util::StreamExt;
+    use tokio::io::AsyncReadExt;
+
+    use super::super::archive::{tar_stream, zip_stream, ArchiveEntry};
+    use super::super::store::MemoryStore;
+    use super::*;
+
+    async fn archive(zip: bool) -> Vec<u8> {
+        let store = MemoryStore::new()
+            .with_file("index.html", "home")
+            .with_file("docs/guide/intro.md", "intro")
+            .with_file("docs/readme.md", "0123456789");
+        let mut entries = BTreeMap::new();
+        for path in [
+            "docs",
+            "docs/guide",
+            "docs/guide/intro.md",
+            "docs/readme.md",
+            "index.html",
+        ] {
+            let metadata = store.metadata(path).await.unwrap();
+            entries.insert(path.to_owned(), ArchiveEntry::new(path.to_owned(), &metadata));
+        }
+        let store = Arc::new(store);
+        let mut data = Vec::new();
+        if zip {
+            let mut stream = Box::pin(zip_stream(store, entries));
+            while let Some(chunk) = stream.next().await {
+                data.extend_from_slice(&chunk.unwrap());
+            }
+        } else {
+            let mut stream = Box::pin(tar_stream(store, entries));
+            while let Some(chunk) = stream.next().await {
+                data.extend_from_slice(&chunk.unwrap());
+            }
+        }
+        data
+    }
+
+    /// Builds a zip archive with a single deflated entry, as written by most zip tools.
+    fn deflated_zip(name: &str) -> Vec<u8> {
+        let content = b"deflated content, deflated content";
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut zip = Vec::new();
+        zip.extend_from_slice(b"PK\x03\x04\x14\x00\x00\x00\x08\x00");
+        zip.extend_from_slice(&[0; 16]);
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 2]);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(&compressed);
+        let central_offset = zip.len() as u32;
+        zip.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00\x00\x00\x08\x00");
+        zip.extend_from_slice(&[0; 8]);
+        zip.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 16]);
+        zip.extend_from_slice(name.as_bytes());
+        let central_size = zip.len() as u32 - central_offset;
+        zip.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00\x01\x00\x01\x00");
+        zip.extend_from_slice(&central_size.to_le_bytes());
+        zip.extend_from_slice(&central_offset.to_le_bytes());
+        zip.extend_from_slice(&[0; 2]);
+        zip
+    }
+
+    async fn read(store: &ArchiveStore, path: &str) -> String {
+        let mut content = String::new();
+        store
+            .open(path)
+            .await
+            .unwrap()
+            .read_to_string(&mut content)
+            .await
+            .unwrap();
+        content
+    }
+
+    async fn check(store: &ArchiveStore) {
+        assert!(store.metadata("").await.unwrap().is_dir());
+        assert!(store.metadata("docs/guide").await.unwrap().is_dir());
+        assert_eq!(store.metadata("docs/readme.md").await.unwrap().len(), 10);
+        assert!(store.metadata("doc").await.is_none());
+        assert!(store.read_dir("index.html").await.is_err());
+
+        let mut names = store
+            .read_dir("docs")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.metadata.is_dir()))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec![("guide".to_owned(), true), ("readme.md".to_owned(), false)]);
+        assert_eq!(store.read_dir("").await.unwrap().len(), 2);
+
+        assert_eq!(read(store, "docs/guide/intro.md").await, "intro");
+        assert_eq!(read(store, "index.html").await, "home");
+        let mut file = store.open("docs/readme.md").await.unwrap();
+        file.seek(SeekFrom::Start(4)).await.unwrap();
+        let mut content = String::new();
+        file.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "456789");
+        assert!(store.open("docs").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tar_archive_store() {
+        let tar = archive(false).await;
+        check(&ArchiveStore::from_bytes(tar.clone()).unwrap()).await;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar).unwrap();
+        check(&ArchiveStore::from_bytes(encoder.finish().unwrap()).unwrap()).await;
+
+        let path = std::env::temp_dir().join(format!("salvo-archive-store-{}.tar", std::process::id()));
+        std::fs::write(&path, &tar).unwrap();
+        check(&ArchiveStore::from_path(&path).unwrap()).await;
+        std::fs::remove_file(&path).ok();
+
+        assert!(ArchiveStore::from_bytes(&b"not an archive"[..]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_zip_archive_store() {
+        let zip = archive(true).await;
+        check(&ArchiveStore::from_bytes(zip.clone()).unwrap()).await;
+
+        let path = std::env::temp_dir().join(format!("salvo-archive-store-{}.zip", std::process::id()));
+        std::fs::write(&path, &zip).unwrap();
+        check(&ArchiveStore::from_path(&path).unwrap()).await;
+        std::fs::remove_file(&path).ok();
+
+        let store = ArchiveStore::from_bytes(deflated_zip("a.txt")).unwrap();
+        assert_eq!(store.metadata("a.txt").await.unwrap().len(), 34);
+        assert_eq!(read(&store, "a.txt").await, "deflated content, deflated content");
+        // Entries escaping the root are ignored.
+        let store = ArchiveStore::from_bytes(deflated_zip("../a.txt")).unwrap();
+        assert!(store.read_dir("").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use salvo_core::http::header::{HeaderValue, CACHE_CONTROL};
+use salvo_core::http::Response;
+
+use super::glob;
+
+/// A rule setting the `Cache-Control` header of files matching a glob pattern.
+///
+/// `*` matches any characters except `/`, `**` matches any characters and `?` matches one character. Patterns
+/// without `/` are matched against the file name only, so `*.html` matches HTML files in all directories.
+#[derive(Clone, Debug)]
+pub struct CacheRule {
+    pattern: String,
+    value: HeaderValue,
+}
+impl CacheRule {
+    /// Create a new `CacheRule`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    #[inline]
+    pub fn new(pattern: impl Into<String>, value: &str) -> Self {
+        CacheRule {
+            pattern: pattern.into().trim_start_matches('/').to_owned(),
+            value: HeaderValue::from_str(value).expect("invalid cache control value"),
+        }
+    }
+    /// Create a rule caching matched files for one year without revalidation, suitable for files with a content
+    /// hash in their names.
+    #[inline]
+    pub fn immutable(pattern: impl Into<String>) -> Self {
+        Self::new(pattern, "public, max-age=31536000, immutable")
+    }
+    /// Create a rule forcing clients to revalidate matched files before using a cached copy.
+    #[inline]
+    pub fn no_cache(pattern: impl Into<String>) -> Self {
+        Self::new(pattern, "no-cache")
+    }
+    /// Get the glob pattern.
+    #[inline]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+    /// Get the `Cache-Control` value.
+    #[inline]
+    pub fn value(&self) -> &HeaderValue {
+        &self.value
+    }
+    /// Returns `true` if the rule applies to `path`, which is relative to the static root.
+    #[inline]
+    pub fn matches(&self, path: &str) -> bool {
+        glob::matches(&self.pattern, path)
+    }
+}
+
+/// Sets `Cache-Control` from the first rule matching `path`.
+pub(crate) fn apply_rules(rules: &[CacheRule], path: &str, res: &mut Response) {
+    if let Some(rule) = rules.iter().find(|rule| rule.matches(path)) {
+        res.headers_mut().insert(CACHE_CONTROL, rule.value.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_rule_matches() {
+        let html = CacheRule::no_cache("*.html");
+        assert!(html.matches("index.html"));
+        assert!(html.matches("/docs/guide/index.html"));
+        assert!(!html.matches("app.js"));
+
+        let assets = CacheRule::immutable("assets/**/*.?s");
+        assert!(assets.matches("assets/app.js"));
+        assert!(assets.matches("assets/chunks/vendor.ts"));
+        assert!(!assets.matches("assets/app.css"));
+        assert!(!assets.matches("static/assets/app.js"));
+
+        let nested = CacheRule::immutable("**/vendor/**");
+        assert!(nested.matches("vendor/a.js"));
+        assert!(nested.matches("lib/vendor/b/c.js"));
+        assert!(!nested.matches("lib/vendors/c.js"));
+
+        let all = CacheRule::new("static/*", "max-age=60");
+        assert!(all.matches("static/logo.png"));
+        assert!(!all.matches("static/img/logo.png"));
+    }
+}
@@ -1,40 +1,108 @@
-use std::collections::HashMap;
-use std::fmt::Write;
-use std::fs::Metadata;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::Arc;
 
-use chrono::prelude::*;
-use percent_encoding::{utf8_percent_encode, CONTROLS};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use salvo_core::async_trait;
 use salvo_core::fs::NamedFile;
+use salvo_core::http::header::{HeaderValue, InvalidHeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE};
 use salvo_core::http::{Request, Response, StatusCode, StatusError};
 use salvo_core::routing::FlowCtrl;
-use salvo_core::writer::Text;
 use salvo_core::{Depot, Handler};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+
+use super::archive::{self, ArchiveEntry};
+use super::cache_control::{self, CacheRule};
+use super::glob;
+use super::listing::{CurrentInfo, DefaultRenderer, DirInfo, FileInfo, ListingRenderer, SortKey, SortOrder};
+use super::precompressed::{self, Precompressed};
+use super::send;
+use super::store::{join_path, normalize_path, LocalStore, OverlayStore, StaticStore};
 
 /// Options
+///
+/// Build it with [`Options::new`] and the `with_*` methods, fields may be added in future versions.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Options {
-    /// List dot files.
+    /// Serve and list dot files and directories.
     pub dot_files: bool,
     /// Listing dir
     pub listing: bool,
     /// Default file names list.
     pub defaults: Vec<String>,
+    /// File served instead of 404 for paths without a file extension, like `index.html` for single page apps
+    /// using client-side routing.
+    pub fallback: Option<String>,
+    /// Rules setting `Cache-Control` of served files, the first matching rule is used.
+    pub cache_rules: Vec<CacheRule>,
+    /// Glob patterns of files and directories which are not served, listed or archived, see [`CacheRule`] for
+    /// the syntax.
+    pub hidden: Vec<String>,
+    /// Allow downloading a listed directory as a tar or zip archive with the `download=tar` or `download=zip`
+    /// query param.
+    pub archive: bool,
 }
 
 impl Options {
+    /// Create new `Options`.
     #[inline]
-    fn new() -> Options {
+    pub fn new() -> Options {
         Options {
             dot_files: false,
             listing: false,
             defaults: vec!["index.html".to_owned()],
+            fallback: None,
+            cache_rules: Vec::new(),
+            hidden: Vec::new(),
+            archive: false,
         }
     }
+    /// Sets whether dot files and directories are served and listed and returns `Self`.
+    #[inline]
+    pub fn with_dot_files(mut self, dot_files: bool) -> Self {
+        self.dot_files = dot_files;
+        self
+    }
+    /// Sets whether directories are listed and returns `Self`.
+    #[inline]
+    pub fn with_listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+    /// Sets default file names and returns `Self`.
+    #[inline]
+    pub fn with_defaults<I>(mut self, defaults: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.defaults = defaults.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Sets fallback file and returns `Self`.
+    #[inline]
+    pub fn with_fallback(mut self, fallback: impl Into<String>) -> Self {
+        self.fallback = Some(fallback.into());
+        self
+    }
+    /// Adds a `Cache-Control` rule and returns `Self`.
+    #[inline]
+    pub fn with_cache_rule(mut self, rule: CacheRule) -> Self {
+        self.cache_rules.push(rule);
+        self
+    }
+    /// Adds a glob pattern of hidden files and returns `Self`.
+    #[inline]
+    pub fn with_hidden(mut self, pattern: impl Into<String>) -> Self {
+        self.hidden.push(pattern.into());
+        self
+    }
+    /// Sets whether directories can be downloaded as tar or zip archives and returns `Self`.
+    #[inline]
+    pub fn with_archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
 }
 
 impl Default for Options {
@@ -99,9 +167,11 @@ impl StaticRoots for PathBuf {
 /// DirHandler
 #[derive(Clone)]
 pub struct DirHandler {
-    roots: Vec<PathBuf>,
+    store: Arc<dyn StaticStore>,
     options: Options,
     chunk_size: Option<u64>,
+    precompressed: Vec<Precompressed>,
+    renderer: Arc<dyn ListingRenderer>,
 }
 impl DirHandler {
     /// Create new `DirHandler`.
@@ -112,12 +182,33 @@ impl DirHandler {
     /// Create new `DirHandler` with options.
     #[inline]
     pub fn width_options<T: StaticRoots + Sized>(roots: T, options: Options) -> Self {
+        let mut roots = roots.collect();
+        let store: Arc<dyn StaticStore> = if roots.len() == 1 {
+            Arc::new(LocalStore::new(roots.remove(0)))
+        } else {
+            Arc::new(roots.into_iter().fold(OverlayStore::new(), |store, root| {
+                store.with_layer(LocalStore::new(root))
+            }))
+        };
+        DirHandler::from_store(store).with_options(options)
+    }
+    /// Create new `DirHandler` serving files from `store`.
+    #[inline]
+    pub fn from_store(store: impl StaticStore) -> Self {
         DirHandler {
-            roots: roots.collect(),
-            options,
+            store: Arc::new(store),
+            options: Options::default(),
             chunk_size: None,
+            precompressed: Vec::new(),
+            renderer: Arc::new(DefaultRenderer),
         }
     }
+    /// Sets options and returns `Self`.
+    #[inline]
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
 
     /// During the file chunk read, the maximum read size at one time will affect the
     /// access experience and the demand for server memory.
@@ -130,314 +221,236 @@ impl DirHandler {
         self.chunk_size = Some(size);
         self
     }
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct CurrentInfo {
-    path: String,
-    files: Vec<FileInfo>,
-    dirs: Vec<DirInfo>,
-}
-impl CurrentInfo {
+
+    /// Serve precompressed variants like `app.js.br` next to the requested file if the client accepts
+    /// their encoding.
+    ///
+    /// Encodings are listed from the most to the least preferred. Disabled by default.
     #[inline]
-    fn new(path: String, files: Vec<FileInfo>, dirs: Vec<DirInfo>) -> CurrentInfo {
-        CurrentInfo { path, files, dirs }
+    pub fn precompressed(mut self, encodings: Vec<Precompressed>) -> Self {
+        self.precompressed = encodings;
+        self
     }
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct FileInfo {
-    name: String,
-    size: u64,
-    modified: DateTime<Local>,
-}
-impl FileInfo {
+
+    /// Sets the renderer of directory listings and returns `Self`.
     #[inline]
-    fn new(name: String, metadata: Metadata) -> FileInfo {
-        FileInfo {
-            name,
-            size: metadata.len(),
-            modified: metadata.modified().unwrap_or_else(|_| SystemTime::now()).into(),
+    pub fn with_renderer(mut self, renderer: impl ListingRenderer) -> Self {
+        self.renderer = Arc::new(renderer);
+        self
+    }
+
+    /// Returns `true` if an entry named `name` at `rel_path` is hidden.
+    fn is_hidden(&self, name: &str, rel_path: &str) -> bool {
+        (!self.options.dot_files && name.starts_with('.'))
+            || self
+                .options
+                .hidden
+                .iter()
+                .any(|pattern| glob::matches(pattern, rel_path))
+    }
+
+    /// Returns `true` if `rel_path` or one of its parent directories is hidden.
+    fn is_hidden_path(&self, rel_path: &str) -> bool {
+        let mut end = 0;
+        !rel_path.is_empty()
+            && rel_path.split('/').any(|name| {
+                end += name.len();
+                let hidden = self.is_hidden(name, &rel_path[..end]);
+                end += 1;
+                hidden
+            })
+    }
+
+    /// Collects visible files and directories under `rel_path`, keyed by their path in the archive.
+    async fn archive_entries(&self, rel_path: &str) -> BTreeMap<String, ArchiveEntry> {
+        let mut entries = BTreeMap::new();
+        let mut pending = vec![(rel_path.to_owned(), String::new())];
+        while let Some((dir, prefix)) = pending.pop() {
+            let dir_entries = match self.store.read_dir(&dir).await {
+                Ok(dir_entries) => dir_entries,
+                Err(_) => continue,
+            };
+            for entry in dir_entries {
+                let path = join_path(&dir, &entry.name);
+                if self.is_hidden(&entry.name, &path) {
+                    continue;
+                }
+                let archive_name = join_path(&prefix, &entry.name);
+                if entry.metadata.is_dir() {
+                    pending.push((path.clone(), archive_name.clone()));
+                }
+                entries.insert(archive_name, ArchiveEntry::new(path, &entry.metadata));
+            }
         }
+        entries
     }
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct DirInfo {
-    name: String,
-    modified: DateTime<Local>,
-}
-impl DirInfo {
-    #[inline]
-    fn new(name: String, metadata: Metadata) -> DirInfo {
-        DirInfo {
-            name,
-            modified: metadata.modified().unwrap_or_else(|_| SystemTime::now()).into(),
+
+    async fn send_file(&self, rel_path: &str, req: &mut Request, res: &mut Response) {
+        let sent = if let Some(path) = self.store.local_path(rel_path).await {
+            let mut builder = NamedFile::builder(path.clone());
+            if let Some(size) = self.chunk_size {
+                builder = builder.with_buffer_size(size);
+            }
+            let builder = precompressed::select_variant(builder, &path, &self.precompressed, req.headers());
+            if let Ok(named_file) = builder.build().await {
+                named_file.send(req, res).await;
+                true
+            } else {
+                res.set_status_error(StatusError::internal_server_error().with_summary("file read error"));
+                false
+            }
+        } else {
+            send::send_store_file(&*self.store, rel_path, &self.precompressed, self.chunk_size, req, res).await
+        };
+        if sent {
+            if !self.precompressed.is_empty() {
+                precompressed::add_vary(res);
+            }
+            cache_control::apply_rules(&self.options.cache_rules, rel_path, res);
         }
     }
 }
-
 #[async_trait]
 impl Handler for DirHandler {
     async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, _ctrl: &mut FlowCtrl) {
-        let param = req.params().iter().find(|(key, _)| key.starts_with('*'));
         let req_path = req.uri().path();
-        let rel_path = if let Some((_, value)) = param {
-            value.clone()
-        } else {
-            decode_url_path_safely(req_path)
-        };
-        let mut used_parts = Vec::with_capacity(8);
-        for part in rel_path.split(['/', '\\']) {
-            if part.is_empty() || part == "." {
-                continue;
-            } else if part == ".." {
-                used_parts.pop();
-            } else {
-                used_parts.push(part);
-            }
+        let rel_path = rel_path(req);
+        if self.is_hidden_path(&rel_path) {
+            res.set_status_error(StatusError::not_found());
+            return;
         }
-        let rel_path: String = used_parts.join("/");
-        let mut files: HashMap<String, Metadata> = HashMap::new();
-        let mut dirs: HashMap<String, Metadata> = HashMap::new();
-        let mut path_exist = false;
-        for root in &self.roots {
-            let path = root.join(&rel_path);
-            if path.is_dir() && self.options.listing {
-                path_exist = true;
+        match self.store.metadata(&rel_path).await {
+            Some(metadata) if metadata.is_dir() && self.options.listing => {
                 if !req_path.ends_with('/') {
                     res.redirect_found(format!("{}/", req_path));
                     return;
                 }
                 for ifile in &self.options.defaults {
-                    let ipath = path.join(ifile);
-                    if ipath.exists() {
-                        let builder = {
-                            let mut builder = NamedFile::builder(ipath);
-                            if let Some(size) = self.chunk_size {
-                                builder = builder.with_buffer_size(size);
-                            }
-                            builder
-                        };
-                        if let Ok(named_file) = builder.build().await {
-                            named_file.send(req, res).await;
-                        } else {
-                            res.set_status_error(StatusError::internal_server_error().with_summary("file read error"));
-                        }
+                    let path = join_path(&rel_path, ifile);
+                    if !self.is_hidden(ifile, &path) && self.store.metadata(&path).await.is_some() {
+                        self.send_file(&path, req, res).await;
                         return;
                     }
                 }
-                // list the dir
-                if let Ok(mut entries) = tokio::fs::read_dir(&path).await {
-                    while let Ok(Some(entry)) = entries.next_entry().await {
-                        if let Ok(metadata) = entry.metadata().await {
-                            if metadata.is_dir() {
-                                dirs.entry(entry.file_name().to_string_lossy().to_string())
-                                    .or_insert(metadata);
-                            } else {
-                                let file_name = entry.file_name().to_string_lossy().to_string();
-                                if !self.options.dot_files && file_name.starts_with('.') {
-                                    continue;
-                                }
-                                files.entry(file_name).or_insert(metadata);
-                            }
-                        }
+            }
+            Some(metadata) if metadata.is_file() => {
+                self.send_file(&rel_path, req, res).await;
+                return;
+            }
+            _ => {
+                if let Some(fallback) = &self.options.fallback {
+                    let is_asset = rel_path
+                        .rsplit('/')
+                        .next()
+                        .map(|name| name.contains('.'))
+                        .unwrap_or(false);
+                    let fallback = normalize_path(fallback);
+                    if !is_asset && matches!(self.store.metadata(&fallback).await, Some(metadata) if metadata.is_file())
+                    {
+                        self.send_file(&fallback, req, res).await;
+                        return;
                     }
                 }
-            } else if path.is_file() {
-                if !self.options.dot_files
-                    && path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| s.starts_with('.'))
-                        .unwrap_or(false)
-                {
-                    res.set_status_error(StatusError::not_found());
-                    return;
-                }
-                if let Ok(named_file) = NamedFile::open(path).await {
-                    named_file.send(req, res).await;
-                } else {
-                    res.set_status_error(StatusError::internal_server_error().with_summary("file read error"));
-                }
+                res.set_status_error(StatusError::not_found());
                 return;
             }
         }
-        if !path_exist || !self.options.listing {
-            res.set_status_error(StatusError::not_found());
+        let download = req.query::<String>("download");
+        if let (true, Some(format @ ("tar" | "zip"))) = (self.options.archive, download.as_deref()) {
+            let entries = self.archive_entries(&rel_path).await;
+            let name = rel_path
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .unwrap_or("archive");
+            let content_type = if format == "zip" {
+                "application/zip"
+            } else {
+                "application/x-tar"
+            };
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            if let Ok(disposition) = attachment_disposition(&format!("{}.{}", name, format)) {
+                res.headers_mut().insert(CONTENT_DISPOSITION, disposition);
+            }
+            res.set_status_code(StatusCode::OK);
+            if format == "zip" {
+                res.streaming(archive::zip_stream(self.store.clone(), entries)).ok();
+            } else {
+                res.streaming(archive::tar_stream(self.store.clone(), entries)).ok();
+            }
             return;
         }
-        let format = req.first_accept().unwrap_or(mime::TEXT_HTML);
-        let mut files: Vec<FileInfo> = files
-            .into_iter()
-            .map(|(name, metadata)| FileInfo::new(name, metadata))
-            .collect();
-        files.sort_by(|a, b| a.name.cmp(&b.name));
-        let mut dirs: Vec<DirInfo> = dirs
-            .into_iter()
-            .map(|(name, metadata)| DirInfo::new(name, metadata))
-            .collect();
-        dirs.sort_by(|a, b| a.name.cmp(&b.name));
-        let root = CurrentInfo::new(decode_url_path_safely(req_path), files, dirs);
-        res.set_status_code(StatusCode::OK);
-        match format.subtype().as_ref() {
-            "plain" => res.render(Text::Plain(list_text(&root))),
-            "json" => res.render(Text::Json(list_json(&root))),
-            "xml" => res.render(Text::Xml(list_xml(&root))),
-            _ => res.render(Text::Html(list_html(&root))),
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        for entry in self.store.read_dir(&rel_path).await.unwrap_or_default() {
+            if self.is_hidden(&entry.name, &join_path(&rel_path, &entry.name)) {
+                continue;
+            }
+            if entry.metadata.is_dir() {
+                dirs.push(DirInfo::new(entry.name, entry.metadata));
+            } else {
+                files.push(FileInfo::new(entry.name, entry.metadata));
+            }
         }
+        let mut current = CurrentInfo::new(decode_url_path_safely(req_path), files, dirs);
+        current.sort_entries(
+            req.query::<SortKey>("sort").unwrap_or(SortKey::Name),
+            req.query::<SortOrder>("order").unwrap_or(SortOrder::Asc),
+        );
+        self.renderer.render(req, &current, res);
     }
 }
 
 #[inline]
-fn encode_url_path(path: &str) -> String {
-    path.split('/')
-        .map(|s| utf8_percent_encode(s, CONTROLS).to_string())
-        .collect::<Vec<_>>()
-        .join("/")
-}
-
-#[inline]
-fn decode_url_path_safely(path: &str) -> String {
+pub(super) fn decode_url_path_safely(path: &str) -> String {
     percent_encoding::percent_decode_str(path)
         .decode_utf8_lossy()
         .to_string()
 }
 
-#[inline]
-fn list_json(current: &CurrentInfo) -> String {
-    json!(current).to_string()
-}
-fn list_xml(current: &CurrentInfo) -> String {
-    let mut ftxt = "<list>".to_owned();
-    if current.dirs.is_empty() && current.files.is_empty() {
-        ftxt.push_str("No files");
+/// Builds `Content-Disposition` of a downloaded file, characters which can not be put in the quoted `filename` are
+/// replaced there and the name is kept percent-encoded in `filename*`.
+fn attachment_disposition(file_name: &str) -> Result<HeaderValue, InvalidHeaderValue> {
+    let fallback = file_name
+        .chars()
+        .map(|c| {
+            if c == ' ' || (c.is_ascii_graphic() && c != '"' && c != '\\') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if fallback == file_name {
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name))
     } else {
-        for dir in &current.dirs {
-            write!(
-                ftxt,
-                "<dir><name>{}</name><modified>{}</modified><link>{}</link></dir>",
-                dir.name,
-                dir.modified.format("%Y-%m-%d %H:%M:%S"),
-                encode_url_path(&dir.name),
-            )
-            .ok();
-        }
-        for file in &current.files {
-            write!(
-                ftxt,
-                "<file><name>{}</name><modified>{}</modified><size>{}</size><link>{}</link></file>",
-                file.name,
-                file.modified.format("%Y-%m-%d %H:%M:%S"),
-                file.size,
-                encode_url_path(&file.name),
-            )
-            .ok();
-        }
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+            fallback,
+            utf8_percent_encode(file_name, NON_ALPHANUMERIC)
+        ))
     }
-    ftxt.push_str("</list>");
-    ftxt
 }
-fn list_html(current: &CurrentInfo) -> String {
-    fn header_links(path: &str) -> String {
-        let segments = path.trim_start_matches('/').trim_end_matches('/').split('/');
-        let mut link = "".to_string();
-        format!(
-            r#"<a href="/">{}</a>{}"#,
-            HOME_ICON,
-            segments
-                .map(|seg| {
-                    link = format!("{}/{}", link, seg);
-                    format!("/<a href=\"{}\">{}</a>", link, seg)
-                })
-                .collect::<Vec<_>>()
-                .join("")
-        )
-    }
-    let mut ftxt = format!(
-        r#"<!DOCTYPE html><html><head>
-        <meta charset="utf-8">
-        <meta name="viewport" content="width=device-width">
-        <title>{}</title>
-        <style>{}</style></head><body><header><h3>Index of: {}</h3></header><hr/>"#,
-        current.path,
-        HTML_STYLE,
-        header_links(&current.path)
-    );
-    if current.dirs.is_empty() && current.files.is_empty() {
-        write!(ftxt, "<p>No files</p>").ok();
+
+/// Returns the requested path relative to the root, taken from the wildcard param if present, with `.` and `..`
+/// segments resolved so that it can not escape the root.
+pub(super) fn rel_path(req: &Request) -> String {
+    let param = req.params().iter().find(|(key, _)| key.starts_with('*'));
+    let rel_path = if let Some((_, value)) = param {
+        value.clone()
     } else {
-        write!(ftxt, "<table><tr><th>").ok();
-        if !(current.path.is_empty() || current.path == "/") {
-            write!(ftxt, "<a href=\"../\">[..]</a>").ok();
-        }
-        write!(ftxt, "</th><th>Name</th><th>Last modified</th><th>Size</th></tr>").ok();
-        for dir in &current.dirs {
-            write!(
-                ftxt,
-                r#"<tr><td>{}</td><td><a href="./{}/">{}</a></td><td>{}</td><td></td></tr>"#,
-                DIR_ICON,
-                encode_url_path(&dir.name),
-                dir.name,
-                dir.modified.format("%Y-%m-%d %H:%M:%S")
-            )
-            .ok();
-        }
-        for file in &current.files {
-            write!(
-                ftxt,
-                r#"<tr><td>{}</td><td><a href="./{}">{}</a></td><td>{}</td><td>{}</td></tr>"#,
-                FILE_ICON,
-                encode_url_path(&file.name),
-                file.name,
-                file.modified.format("%Y-%m-%d %H:%M:%S"),
-                file.size
-            )
-            .ok();
+        decode_url_path_safely(req.uri().path())
+    };
+    let mut used_parts = Vec::with_capacity(8);
+    for part in rel_path.split(['/', '\\']) {
+        if part.is_empty() || part == "." {
+            continue;
+        } else if part == ".." {
+            used_parts.pop();
+        } else {
+            used_parts.push(part);
         }
-        write!(ftxt, "</table>").ok();
     }
-    write!(
-        ftxt,
-        r#"<hr/><footer><a href="https://salvo.rs" target="_blank">salvo</a></footer></body>"#
-    ).ok();
-    ftxt
-}
-#[inline]
-fn list_text(current: &CurrentInfo) -> String {
-    json!(current).to_string()
+    used_parts.join("/")
 }
-
-const HTML_STYLE: &str = r#"
-    :root {
-        --bg-color: #fff;
-        --text-color: #222;
-        --link-color: #0366d6;
-        --link-visited-color: #f22526;
-        --dir-icon-color: #79b8ff;
-        --file-icon-color: #959da5;
-    }
-    body {background: var(--bg-color); color: var(--text-color);}
-    a {text-decoration:none;color:var(--link-color);}
-    a:visited {color: var(--link-visited-color);}
-    a:hover {text-decoration:underline;}
-    header a {padding: 0 6px;}
-    footer {text-align:center;font-size:12px;}
-    table {text-align:left;border-collapse: collapse;}
-    tr {border-bottom: solid 1px #ccc;}
-    tr:last-child {border-bottom: none;}
-    th, td {padding: 5px;}
-    th:first-child,td:first-child {text-align: center;}
-    svg[data-icon="dir"] {vertical-align: text-bottom; color: var(--dir-icon-color); fill: currentColor;}
-    svg[data-icon="file"] {vertical-align: text-bottom; color: var(--file-icon-color); fill: currentColor;}
-    svg[data-icon="home"] {width:18px;}
-    @media (prefers-color-scheme: dark) {
-        :root {
-            --bg-color: #222;
-            --text-color: #ddd;
-            --link-color: #539bf5;
-            --link-visited-color: #f25555;
-            --dir-icon-color: #7da3d0;
-            --file-icon-color: #545d68;
-        }}
-    }"#;
-const DIR_ICON: &str = r#"<svg aria-label="Directory" data-icon="dir" width="20" height="20" viewBox="0 0 512 512" version="1.1" role="img"><path fill="currentColor" d="M464 128H272l-64-64H48C21.49 64 0 85.49 0 112v288c0 26.51 21.49 48 48 48h416c26.51 0 48-21.49 48-48V176c0-26.51-21.49-48-48-48z"></path></svg>"#;
-const FILE_ICON: &str = r#"<svg aria-label="File" data-icon="file" width="20" height="20" viewBox="0 0 384 512" version="1.1" role="img"><path d="M369.9 97.9L286 14C277 5 264.8-.1 252.1-.1H48C21.5 0 0 21.5 0 48v416c0 26.5 21.5 48 48 48h288c26.5 0 48-21.5 48-48V131.9c0-12.7-5.1-25-14.1-34zM332.1 128H256V51.9l76.1 76.1zM48 464V48h160v104c0 13.3 10.7 24 24 24h104v288H48z"/></svg>"#;
-const HOME_ICON: &str = r#"<svg aria-hidden="true" data-icon="home" viewBox="0 0 576 512"><path fill="currentColor" d="M280.37 148.26L96 300.11V464a16 16 0 0 0 16 16l112.06-.29a16 16 0 0 0 15.92-16V368a16 16 0 0 1 16-16h64a16 16 0 0 1 16 16v95.64a16 16 0 0 0 16 16.05L464 480a16 16 0 0 0 16-16V300L295.67 148.26a12.19 12.19 0 0 0-15.3 0zM571.6 251.47L488 182.56V44.05a12 12 0 0 0-12-12h-56a12 12 0 0 0-12 12v72.61L318.47 43a48 48 0 0 0-61 0L4.34 251.47a12 12 0 0 0-1.6 16.9l25.5 31A12 12 0 0 0 45.15 301l235.22-193.74a12.19 12.19 0 0 1 15.3 0L530.9 301a12 12 0 0 0 16.9-1.6l25.5-31a12 12 0 0 0-1.7-16.93z"></path></svg>"#;
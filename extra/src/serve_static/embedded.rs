@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use salvo_core::async_trait;
+use salvo_core::fs::NamedContent;
+use salvo_core::http::headers::ETag;
+use salvo_core::http::{Request, Response, StatusError};
+use salvo_core::routing::FlowCtrl;
+use salvo_core::{Depot, Handler};
+use sha2::{Digest, Sha256};
+
+use super::dir::rel_path;
+use super::store::normalize_path;
+
+/// A file embedded in the binary.
+#[derive(Clone, Debug)]
+pub struct EmbeddedFile {
+    name: String,
+    data: Bytes,
+    content_type: mime::Mime,
+    etag: Option<ETag>,
+}
+impl EmbeddedFile {
+    /// Create a new `EmbeddedFile`, content type is guessed from `path`.
+    pub fn new(path: &str, data: impl Into<Cow<'static, [u8]>>) -> Self {
+        let data = match data.into() {
+            Cow::Borrowed(data) => Bytes::from_static(data),
+            Cow::Owned(data) => Bytes::from(data),
+        };
+        let mut etag = String::with_capacity(66);
+        etag.push('"');
+        for byte in Sha256::digest(&data) {
+            let _ = write!(etag, "{:02x}", byte);
+        }
+        etag.push('"');
+        let etag = etag.parse::<ETag>().ok();
+        EmbeddedFile {
+            name: path.to_owned(),
+            data,
+            content_type: guess_content_type(path),
+            etag,
+        }
+    }
+    /// Get file content.
+    #[inline]
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+    /// Get content type.
+    #[inline]
+    pub fn content_type(&self) -> &mime::Mime {
+        &self.content_type
+    }
+    /// Get ETag, it is the hex encoded SHA-256 hash of the content, like the hash computed by `rust-embed`.
+    #[inline]
+    pub fn etag(&self) -> Option<&ETag> {
+        self.etag.as_ref()
+    }
+}
+
+/// Serves files embedded in the binary, for example with `rust-embed` or `include_dir`.
+///
+/// # Example
+///
+/// ```
+/// use salvo_core::prelude::*;
+/// use salvo_extra::serve_static::EmbeddedHandler;
+///
+/// let handler = EmbeddedHandler::new()
+///     .with_file("index.html", &b"<h1>Hello</h1>"[..])
+///     .with_file("assets/app.js", &b"console.log('hello');"[..]);
+/// let router = Router::with_path("<**path>").get(handler);
+/// ```
+///
+/// Files embedded by `rust-embed` can be added with:
+///
+/// ```ignore
+/// #[derive(RustEmbed)]
+/// #[folder = "static"]
+/// struct Assets;
+///
+/// let handler = EmbeddedHandler::new()
+///     .with_files(Assets::iter().filter_map(|path| Assets::get(&path).map(|file| (path, file.data))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct EmbeddedHandler {
+    files: HashMap<String, EmbeddedFile>,
+    defaults: Vec<String>,
+    last_modified: Option<SystemTime>,
+}
+impl Default for EmbeddedHandler {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl EmbeddedHandler {
+    /// Create a new `EmbeddedHandler` without files.
+    ///
+    /// Files have no Last-Modified header, `index.html` is served for directories.
+    #[inline]
+    pub fn new() -> Self {
+        EmbeddedHandler {
+            files: HashMap::new(),
+            defaults: vec!["index.html".to_owned()],
+            last_modified: None,
+        }
+    }
+    /// Adds a file at `path` relative to the handler's root and returns `Self`.
+    #[inline]
+    pub fn with_file(mut self, path: impl Into<String>, data: impl Into<Cow<'static, [u8]>>) -> Self {
+        let path = normalize_path(&path.into());
+        let file = EmbeddedFile::new(&path, data);
+        self.files.insert(path, file);
+        self
+    }
+    /// Adds files from an iterator of `(path, data)` pairs and returns `Self`.
+    #[inline]
+    pub fn with_files<I, P, D>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = (P, D)>,
+        P: Into<String>,
+        D: Into<Cow<'static, [u8]>>,
+    {
+        for (path, data) in files {
+            self = self.with_file(path, data);
+        }
+        self
+    }
+    /// Get a file by its path.
+    #[inline]
+    pub fn file(&self, path: &str) -> Option<&EmbeddedFile> {
+        self.files.get(&normalize_path(path))
+    }
+    /// Get default file names served for directories.
+    #[inline]
+    pub fn defaults(&self) -> &Vec<String> {
+        &self.defaults
+    }
+    /// Sets default file names served for directories and returns `Self`.
+    #[inline]
+    pub fn with_defaults(mut self, defaults: Vec<String>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+    /// Get Last-Modified time of files.
+    #[inline]
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+    /// Sets Last-Modified time of files, like the build time of the binary, and returns `Self`.
+    ///
+    /// `None` disables the header, it is the default.
+    #[inline]
+    pub fn with_last_modified(mut self, last_modified: Option<SystemTime>) -> Self {
+        self.last_modified = last_modified;
+        self
+    }
+
+    async fn send(&self, file: &EmbeddedFile, req: &mut Request, res: &mut Response) {
+        let mut builder =
+            NamedContent::from_bytes(file.name.clone(), file.data.clone()).with_content_type(file.content_type.clone());
+        if let Some(etag) = &file.etag {
+            builder = builder.with_etag(etag.clone());
+        }
+        if let Some(last_modified) = self.last_modified {
+            builder = builder.with_last_modified(last_modified);
+        }
+        builder.send(req, res).await;
+    }
+}
+
+#[async_trait]
+impl Handler for EmbeddedHandler {
+    async fn handle(&self, req: &mut Request, _depot: &mut Depot, res: &mut Response, _ctrl: &mut FlowCtrl) {
+        let rel_path = rel_path(req);
+        if let Some(file) = self.files.get(&rel_path) {
+            self.send(file, req, res).await;
+        } else if let Some(file) = self.defaults.iter().find_map(|default| {
+            let path = if rel_path.is_empty() {
+                default.clone()
+            } else {
+                format!("{}/{}", rel_path, default)
+            };
+            self.files.get(&path)
+        }) {
+            let req_path = req.uri().path();
+            if !req_path.ends_with('/') {
+                res.redirect_found(format!("{}/", req_path));
+            } else {
+                self.send(file, req, res).await;
+            }
+        } else {
+            res.set_status_error(StatusError::not_found());
+        }
+    }
+}
+
+fn guess_content_type(path: &str) -> mime::Mime {
+    let ct = mime_guess::from_path(path).first_or_octet_stream();
+    if (ct.type_() == mime::TEXT || ct.subtype() == mime::JSON || ct.subtype() == mime::JAVASCRIPT)
+        && ct.get_param(mime::CHARSET).is_none()
+    {
+        format!("{}; charset=utf-8", ct).parse::<mime::Mime>().unwrap_or(ct)
+    } else {
+        ct
+    }
+}
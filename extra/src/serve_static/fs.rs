@@ -7,26 +7,47 @@ use salvo_core::routing::FlowCtrl;
 use salvo_core::Handler;
 use salvo_core::{Depot, Request, Response, Writer};
 
+use super::precompressed::{self, Precompressed};
+
 /// FileHandler
 #[derive(Clone)]
-pub struct FileHandler(NamedFileBuilder);
+pub struct FileHandler {
+    path: PathBuf,
+    builder: NamedFileBuilder,
+    precompressed: Vec<Precompressed>,
+}
 
 impl FileHandler {
     /// Create a new `FileHandler`.
     #[inline]
     pub fn new(path: impl Into<PathBuf>) -> Self {
-        FileHandler(NamedFile::builder(path))
+        let path = path.into();
+        FileHandler {
+            builder: NamedFile::builder(path.clone()),
+            path,
+            precompressed: Vec::new(),
+        }
     }
 
     /// During the file chunk read, the maximum read size at one time will affect the
-    /// access experience and the demand for server memory. 
-    /// 
+    /// access experience and the demand for server memory.
+    ///
     /// Please set it according to your own situation.
-    /// 
+    ///
     /// The default is 1M.
     #[inline]
-    pub fn chunk_size(self, size: u64) -> Self {
-        Self(self.0.with_buffer_size(size))
+    pub fn chunk_size(mut self, size: u64) -> Self {
+        self.builder = self.builder.with_buffer_size(size);
+        self
+    }
+
+    /// Serve precompressed variants like `app.js.br` next to the file if the client accepts their encoding.
+    ///
+    /// Encodings are listed from the most to the least preferred. Disabled by default.
+    #[inline]
+    pub fn precompressed(mut self, encodings: Vec<Precompressed>) -> Self {
+        self.precompressed = encodings;
+        self
     }
 }
 
@@ -34,8 +55,15 @@ impl FileHandler {
 impl Handler for FileHandler {
     #[inline]
     async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
-        match self.0.clone().build().await {
-            Ok(file) => file.write(req, depot, res).await,
+        let builder =
+            precompressed::select_variant(self.builder.clone(), &self.path, &self.precompressed, req.headers());
+        match builder.build().await {
+            Ok(file) => {
+                file.write(req, depot, res).await;
+                if !self.precompressed.is_empty() {
+                    precompressed::add_vary(res);
+                }
+            }
             Err(_) => {
                 res.set_status_error(StatusError::not_found());
             }
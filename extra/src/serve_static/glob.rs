@@ -0,0 +1,30 @@
+/// Returns `true` if `path`, relative to the static root, matches the glob `pattern`.
+///
+/// `*` matches any characters except `/`, `**` matches any characters and `?` matches one character. Patterns
+/// without `/` are matched against the file name only.
+pub(super) fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches('/');
+    let path = path.trim_start_matches('/');
+    if pattern.contains('/') {
+        glob_match(pattern.as_bytes(), path.as_bytes())
+    } else {
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        glob_match(pattern.as_bytes(), file_name.as_bytes())
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => match pattern[2..].strip_prefix(b"/") {
+            // `**/` matches zero or more whole directories.
+            Some(rest) => (0..=text.len()).any(|i| (i == 0 || text[i - 1] == b'/') && glob_match(rest, &text[i..])),
+            None => (0..=text.len()).any(|i| glob_match(&pattern[2..], &text[i..])),
+        },
+        Some(b'*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && glob_match(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
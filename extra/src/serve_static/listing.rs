@@ -0,0 +1,430 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use chrono::prelude::*;
+use percent_encoding::{utf8_percent_encode, CONTROLS};
+use salvo_core::http::{Request, Response, StatusCode};
+use salvo_core::writer::Text;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::store::StoreMetadata;
+
+/// Sort key of directory listings, taken from the `sort` query param.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Sort by name.
+    Name,
+    /// Sort by size, directories are sorted by name.
+    Size,
+    /// Sort by last modified time.
+    Modified,
+}
+impl FromStr for SortKey {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(SortKey::Name),
+            "size" => Ok(SortKey::Size),
+            "modified" | "mtime" => Ok(SortKey::Modified),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Sort order of directory listings, taken from the `order` query param.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Ascending order.
+    Asc,
+    /// Descending order.
+    Desc,
+}
+impl FromStr for SortOrder {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Listed directory.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrentInfo {
+    /// Request path of the directory.
+    pub path: String,
+    /// Files in the directory.
+    pub files: Vec<FileInfo>,
+    /// Sub directories.
+    pub dirs: Vec<DirInfo>,
+    /// Sort key of `files` and `dirs`.
+    pub sort: SortKey,
+    /// Sort order of `files` and `dirs`.
+    pub order: SortOrder,
+}
+impl CurrentInfo {
+    #[inline]
+    pub(crate) fn new(path: String, files: Vec<FileInfo>, dirs: Vec<DirInfo>) -> CurrentInfo {
+        let mut current = CurrentInfo {
+            path,
+            files,
+            dirs,
+            sort: SortKey::Name,
+            order: SortOrder::Asc,
+        };
+        current.sort_entries(SortKey::Name, SortOrder::Asc);
+        current
+    }
+    /// Sorts files and directories.
+    pub fn sort_entries(&mut self, sort: SortKey, order: SortOrder) {
+        match sort {
+            SortKey::Name => {
+                self.files.sort_by(|a, b| a.name.cmp(&b.name));
+                self.dirs.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            SortKey::Size => {
+                self.files
+                    .sort_by(|a, b| a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name)));
+                self.dirs.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            SortKey::Modified => {
+                self.files
+                    .sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.name.cmp(&b.name)));
+                self.dirs
+                    .sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.name.cmp(&b.name)));
+            }
+        }
+        if order == SortOrder::Desc {
+            self.files.reverse();
+            self.dirs.reverse();
+        }
+        self.sort = sort;
+        self.order = order;
+    }
+    /// Returns links to the root and to every parent directory of the listed directory.
+    pub fn breadcrumbs(&self) -> Vec<Breadcrumb> {
+        let mut link = String::new();
+        let mut breadcrumbs = vec![Breadcrumb {
+            name: "/".into(),
+            link: "/".into(),
+        }];
+        for segment in self.path.split('/').filter(|s| !s.is_empty()) {
+            link.push('/');
+            link.push_str(&encode_url_path(segment));
+            breadcrumbs.push(Breadcrumb {
+                name: segment.to_owned(),
+                link: format!("{}/", link),
+            });
+        }
+        breadcrumbs
+    }
+}
+
+/// A link to a directory on the path of the listed directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Breadcrumb {
+    /// Directory name.
+    pub name: String,
+    /// Encoded link to the directory.
+    pub link: String,
+}
+
+/// Listed file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileInfo {
+    /// File name.
+    pub name: String,
+    /// File size in bytes.
+    pub size: u64,
+    /// Last modified time.
+    pub modified: DateTime<Local>,
+}
+impl FileInfo {
+    #[inline]
+    pub(crate) fn new(name: String, metadata: StoreMetadata) -> FileInfo {
+        FileInfo {
+            name,
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or_else(SystemTime::now).into(),
+        }
+    }
+    /// Get human-readable file size like `1.5 KiB`.
+    #[inline]
+    pub fn human_size(&self) -> String {
+        human_size(self.size)
+    }
+}
+
+/// Listed directory.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DirInfo {
+    /// Directory name.
+    pub name: String,
+    /// Last modified time.
+    pub modified: DateTime<Local>,
+}
+impl DirInfo {
+    #[inline]
+    pub(crate) fn new(name: String, metadata: StoreMetadata) -> DirInfo {
+        DirInfo {
+            name,
+            modified: metadata.modified().unwrap_or_else(SystemTime::now).into(),
+        }
+    }
+}
+
+/// Formats a size in bytes with binary units, like `512 B` or `1.5 KiB`.
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Renders directory listings of [`DirHandler`](super::DirHandler).
+///
+/// It is implemented for closures taking the same arguments as [`ListingRenderer::render`].
+pub trait ListingRenderer: Send + Sync + 'static {
+    /// Writes the listing of `current` to `res`.
+    fn render(&self, req: &Request, current: &CurrentInfo, res: &mut Response);
+}
+impl<F> ListingRenderer for F
+where
+    F: Fn(&Request, &CurrentInfo, &mut Response) + Send + Sync + 'static,
+{
+    #[inline]
+    fn render(&self, req: &Request, current: &CurrentInfo, res: &mut Response) {
+        self(req, current, res)
+    }
+}
+
+/// Renders listings as HTML, JSON, XML or plain text according to the request's `Accept` header.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultRenderer;
+impl ListingRenderer for DefaultRenderer {
+    fn render(&self, req: &Request, current: &CurrentInfo, res: &mut Response) {
+        let format = req.first_accept().unwrap_or(mime::TEXT_HTML);
+        res.set_status_code(StatusCode::OK);
+        match format.subtype().as_ref() {
+            "plain" => res.render(Text::Plain(list_text(current))),
+            "json" => res.render(Text::Json(list_json(current))),
+            "xml" => res.render(Text::Xml(list_xml(current))),
+            _ => res.render(Text::Html(list_html(current))),
+        }
+    }
+}
+
+#[inline]
+pub(super) fn encode_url_path(path: &str) -> String {
+    path.split('/')
+        .map(|s| utf8_percent_encode(s, CONTROLS).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[inline]
+fn list_json(current: &CurrentInfo) -> String {
+    json!(current).to_string()
+}
+fn list_xml(current: &CurrentInfo) -> String {
+    let mut ftxt = "<list>".to_owned();
+    if current.dirs.is_empty() && current.files.is_empty() {
+        ftxt.push_str("No files");
+    } else {
+        for dir in &current.dirs {
+            write!(
+                ftxt,
+                "<dir><name>{}</name><modified>{}</modified><link>{}</link></dir>",
+                dir.name,
+                dir.modified.format("%Y-%m-%d %H:%M:%S"),
+                encode_url_path(&dir.name),
+            )
+            .ok();
+        }
+        for file in &current.files {
+            write!(
+                ftxt,
+                "<file><name>{}</name><modified>{}</modified><size>{}</size><link>{}</link></file>",
+                file.name,
+                file.modified.format("%Y-%m-%d %H:%M:%S"),
+                file.size,
+                encode_url_path(&file.name),
+            )
+            .ok();
+        }
+    }
+    ftxt.push_str("</list>");
+    ftxt
+}
+fn list_html(current: &CurrentInfo) -> String {
+    let sort_link = |sort: SortKey, label: &str| {
+        let order = if current.sort == sort && current.order == SortOrder::Asc {
+            "desc"
+        } else {
+            "asc"
+        };
+        let arrow = match (current.sort == sort, current.order) {
+            (true, SortOrder::Asc) => " &#9650;",
+            (true, SortOrder::Desc) => " &#9660;",
+            _ => "",
+        };
+        let sort = match sort {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+        };
+        format!(r#"<a href="?sort={}&order={}">{}{}</a>"#, sort, order, label, arrow)
+    };
+    let header_links = current
+        .breadcrumbs()
+        .iter()
+        .map(|crumb| {
+            if crumb.link == "/" {
+                format!(r#"<a href="/">{}</a>"#, HOME_ICON)
+            } else {
+                format!(r#"<a href="{}">{}</a>"#, crumb.link, crumb.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut ftxt = format!(
+        r#"<!DOCTYPE html><html><head>
+        <meta charset="utf-8">
+        <meta name="viewport" content="width=device-width">
+        <title>{}</title>
+        <style>{}</style></head><body><header><h3>Index of: {}</h3></header><hr/>"#,
+        current.path, HTML_STYLE, header_links
+    );
+    if current.dirs.is_empty() && current.files.is_empty() {
+        write!(ftxt, "<p>No files</p>").ok();
+    } else {
+        write!(ftxt, "<table><tr><th>").ok();
+        if !(current.path.is_empty() || current.path == "/") {
+            write!(ftxt, "<a href=\"../\">[..]</a>").ok();
+        }
+        write!(
+            ftxt,
+            "</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+            sort_link(SortKey::Name, "Name"),
+            sort_link(SortKey::Modified, "Last modified"),
+            sort_link(SortKey::Size, "Size")
+        )
+        .ok();
+        for dir in &current.dirs {
+            write!(
+                ftxt,
+                r#"<tr><td>{}</td><td><a href="./{}/">{}</a></td><td>{}</td><td></td></tr>"#,
+                DIR_ICON,
+                encode_url_path(&dir.name),
+                dir.name,
+                dir.modified.format("%Y-%m-%d %H:%M:%S")
+            )
+            .ok();
+        }
+        for file in &current.files {
+            write!(
+                ftxt,
+                r#"<tr><td>{}</td><td><a href="./{}">{}</a></td><td>{}</td><td title="{}">{}</td></tr>"#,
+                FILE_ICON,
+                encode_url_path(&file.name),
+                file.name,
+                file.modified.format("%Y-%m-%d %H:%M:%S"),
+                file.size,
+                file.human_size()
+            )
+            .ok();
+        }
+        write!(ftxt, "</table>").ok();
+    }
+    write!(
+        ftxt,
+        r#"<hr/><footer><a href="https://salvo.rs" target="_blank">salvo</a></footer></body>"#
+    )
+    .ok();
+    ftxt
+}
+#[inline]
+fn list_text(current: &CurrentInfo) -> String {
+    json!(current).to_string()
+}
+
+const HTML_STYLE: &str = r#"
+    :root {
+        --bg-color: #fff;
+        --text-color: #222;
+        --link-color: #0366d6;
+        --link-visited-color: #f22526;
+        --dir-icon-color: #79b8ff;
+        --file-icon-color: #959da5;
+    }
+    body {background: var(--bg-color); color: var(--text-color);}
+    a {text-decoration:none;color:var(--link-color);}
+    a:visited {color: var(--link-visited-color);}
+    a:hover {text-decoration:underline;}
+    header a {padding: 0 6px;}
+    footer {text-align:center;font-size:12px;}
+    table {text-align:left;border-collapse: collapse;}
+    tr {border-bottom: solid 1px #ccc;}
+    tr:last-child {border-bottom: none;}
+    th, td {padding: 5px;}
+    th:first-child,td:first-child {text-align: center;}
+    svg[data-icon="dir"] {vertical-align: text-bottom; color: var(--dir-icon-color); fill: currentColor;}
+    svg[data-icon="file"] {vertical-align: text-bottom; color: var(--file-icon-color); fill: currentColor;}
+    svg[data-icon="home"] {width:18px;}
+    @media (prefers-color-scheme: dark) {
+        :root {
+            --bg-color: #222;
+            --text-color: #ddd;
+            --link-color: #539bf5;
+            --link-visited-color: #f25555;
+            --dir-icon-color: #7da3d0;
+            --file-icon-color: #545d68;
+        }}
+    }"#;
+const DIR_ICON: &str = r#"<svg aria-label="Directory" data-icon="dir" width="20" height="20" viewBox="0 0 512 512" version="1.1" role="img"><path fill="currentColor" d="M464 128H272l-64-64H48C21.49 64 0 85.49 0 112v288c0 26.51 21.49 48 48 48h416c26.51 0 48-21.49 48-48V176c0-26.51-21.49-48-48-48z"></path></svg>"#;
+const FILE_ICON: &str = r#"<svg aria-label="File" data-icon="file" width="20" height="20" viewBox="0 0 384 512" version="1.1" role="img"><path d="M369.9 97.9L286 14C277 5 264.8-.1 252.1-.1H48C21.5 0 0 21.5 0 48v416c0 26.5 21.5 48 48 48h288c26.5 0 48-21.5 48-48V131.9c0-12.7-5.1-25-14.1-34zM332.1 128H256V51.9l76.1 76.1zM48 464V48h160v104c0 13.3 10.7 24 24 24h104v288H48z"/></svg>"#;
+const HOME_ICON: &str = r#"<svg aria-hidden="true" data-icon="home" viewBox="0 0 576 512"><path fill="currentColor" d="M280.37 148.26L96 300.11V464a16 16 0 0 0 16 16l112.06-.29a16 16 0 0 0 15.92-16V368a16 16 0 0 1 16-16h64a16 16 0 0 1 16 16v95.64a16 16 0 0 0 16 16.05L464 480a16 16 0 0 0 16-16V300L295.67 148.26a12.19 12.19 0 0 0-15.3 0zM571.6 251.47L488 182.56V44.05a12 12 0 0 0-12-12h-56a12 12 0 0 0-12 12v72.61L318.47 43a48 48 0 0 0-61 0L4.34 251.47a12 12 0 0 0-1.6 16.9l25.5 31A12 12 0 0 0 45.15 301l235.22-193.74a12.19 12.19 0 0 1 15.3 0L530.9 301a12 12 0 0 0 16.9-1.6l25.5-31a12 12 0 0 0-1.7-16.93z"></path></svg>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_breadcrumbs() {
+        let current = CurrentInfo::new("/docs/a b/".into(), vec![], vec![]);
+        let breadcrumbs = current
+            .breadcrumbs()
+            .into_iter()
+            .map(|crumb| (crumb.name, crumb.link))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            breadcrumbs,
+            vec![
+                ("/".to_owned(), "/".to_owned()),
+                ("docs".to_owned(), "/docs/".to_owned()),
+                ("a b".to_owned(), "/docs/a b/".to_owned()),
+            ]
+        );
+    }
+}
@@ -1,10 +1,27 @@
 //! serve middleware
 
+mod archive;
+mod archive_store;
+mod cache_control;
 mod dir;
+mod embedded;
 mod fs;
+mod glob;
+mod listing;
+mod precompressed;
+mod send;
+mod store;
 
+pub use archive_store::ArchiveStore;
+pub use cache_control::CacheRule;
 pub use dir::{DirHandler, Options};
+pub use embedded::{EmbeddedFile, EmbeddedHandler};
 pub use fs::FileHandler;
+pub use listing::{
+    human_size, Breadcrumb, CurrentInfo, DefaultRenderer, DirInfo, FileInfo, ListingRenderer, SortKey, SortOrder,
+};
+pub use precompressed::Precompressed;
+pub use store::{LocalStore, MemoryStore, OverlayStore, StaticStore, StoreEntry, StoreMetadata, StoreReader};
 
 #[cfg(test)]
 mod tests {
@@ -16,11 +33,7 @@ mod tests {
     async fn test_serve_static_files() {
         let router = Router::with_path("<**path>").get(DirHandler::width_options(
             vec!["../examples/file-list/static/test"],
-            Options {
-                dot_files: false,
-                listing: true,
-                defaults: vec!["index.html".to_owned()],
-            },
+            Options::new().with_listing(true).with_defaults(["index.html"]),
         ));
         let service = Service::new(router);
 
@@ -70,4 +83,351 @@ mod tests {
         .await;
         assert!(content == "copy3");
     }
+
+    #[tokio::test]
+    async fn test_serve_precompressed_files() {
+        let root = std::env::temp_dir().join(format!("salvo-precompressed-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("app.js"), "raw").unwrap();
+        std::fs::write(root.join("app.js.gz"), "gzip").unwrap();
+        std::fs::write(root.join("app.js.br"), "brotli").unwrap();
+
+        let router = Router::new()
+            .push(
+                Router::with_path("file.js")
+                    .get(FileHandler::new(root.join("app.js")).precompressed(Precompressed::all())),
+            )
+            .push(Router::with_path("<**path>").get(DirHandler::new(root.clone()).precompressed(Precompressed::all())));
+        let service = Service::new(router);
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/app.js")
+            .insert_header("accept-encoding", "gzip, br")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-encoding"], "br");
+        assert_eq!(res.headers()["vary"], "accept-encoding");
+        assert!(res.headers()["content-type"].to_str().unwrap().contains("javascript"));
+        assert_eq!(res.take_bytes().await.unwrap(), "brotli");
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/app.js")
+            .insert_header("accept-encoding", "gzip, br;q=0.5")
+            .insert_header("range", "bytes=1-2")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        assert!(res.headers().contains_key("etag"));
+        assert_eq!(res.take_bytes().await.unwrap(), "zi");
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/app.js")
+            .insert_header("accept-encoding", "zstd")
+            .send(&service)
+            .await;
+        assert!(!res.headers().contains_key("content-encoding"));
+        assert_eq!(res.headers()["vary"], "accept-encoding");
+        assert_eq!(res.take_bytes().await.unwrap(), "raw");
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/file.js")
+            .insert_header("accept-encoding", "gzip")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        assert_eq!(res.take_bytes().await.unwrap(), "gzip");
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_serve_embedded_files() {
+        let handler = EmbeddedHandler::new()
+            .with_file("index.html", &b"<h1>home</h1>"[..])
+            .with_file("/assets/app.js", b"console.log(1);".to_vec())
+            .with_file("docs/index.html", &b"docs"[..]);
+        let etag = "\"35c146f76e129477c64061bc84511e1090f3d4d8059713e6663dd4b35b1f7642\""
+            .parse::<salvo_core::http::headers::ETag>()
+            .unwrap();
+        assert_eq!(handler.file("assets/app.js").unwrap().etag(), Some(&etag));
+        let service = Service::new(Router::with_path("<**path>").get(handler.clone()));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+        assert!(!res.headers().contains_key("last-modified"));
+        assert_eq!(res.take_string().await.unwrap(), "<h1>home</h1>");
+
+        let res = TestClient::get("http://127.0.0.1:7979/docs").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::FOUND));
+        let mut res = TestClient::get("http://127.0.0.1:7979/docs/").send(&service).await;
+        assert_eq!(res.take_string().await.unwrap(), "docs");
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/assets/app.js")
+            .send(&service)
+            .await;
+        assert!(res.headers()["content-type"].to_str().unwrap().contains("javascript"));
+        let etag = res.headers()["etag"].clone();
+        assert_eq!(res.take_string().await.unwrap(), "console.log(1);");
+
+        let res = TestClient::get("http://127.0.0.1:7979/assets/app.js")
+            .insert_header("if-none-match", etag)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_MODIFIED));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/assets/app.js")
+            .insert_header("range", "bytes=8-10")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.headers()["content-range"], "bytes 8-10/15");
+        assert_eq!(res.take_string().await.unwrap(), "log");
+
+        let res = TestClient::get("http://127.0.0.1:7979/assets/../../missing.js")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_FOUND));
+
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        let handler = handler.with_last_modified(Some(modified));
+        let res = TestClient::get("http://127.0.0.1:7979/")
+            .send(Router::with_path("<**path>").get(handler))
+            .await;
+        assert_eq!(res.headers()["last-modified"], "Sun, 13 Sep 2020 12:26:40 GMT");
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_fallback() {
+        let router = Router::with_path("<**path>").get(DirHandler::width_options(
+            vec!["../examples/file-list/static/test"],
+            Options::default()
+                .with_fallback("test1.txt")
+                .with_cache_rule(CacheRule::immutable("dir1/**"))
+                .with_cache_rule(CacheRule::no_cache("*.txt")),
+        ));
+        let service = Service::new(router);
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/users/42").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::OK));
+        assert_eq!(res.headers()["cache-control"], "no-cache");
+        assert!(res.take_string().await.unwrap().contains("copy1"));
+
+        let res = TestClient::get("http://127.0.0.1:7979/missing.js").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_FOUND));
+
+        let res = TestClient::get("http://127.0.0.1:7979/dir1/test3.txt")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["cache-control"], "public, max-age=31536000, immutable");
+        let res = TestClient::get("http://127.0.0.1:7979/test2.txt").send(&service).await;
+        assert_eq!(res.headers()["cache-control"], "no-cache");
+    }
+
+    #[tokio::test]
+    async fn test_serve_listing() {
+        let options = Options::new().with_listing(true).with_hidden("dir2").with_archive(true);
+        let router = Router::with_path("<**path>").get(DirHandler::width_options(
+            vec!["../examples/file-list/static/test"],
+            options.clone(),
+        ));
+        let service = Service::new(router);
+
+        let current = TestClient::get("http://127.0.0.1:7979/?sort=name&order=desc")
+            .insert_header("accept", "application/json")
+            .send(&service)
+            .await
+            .take_json::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(current["files"][0]["name"], "test2.txt");
+        assert_eq!(current["files"][1]["name"], "test1.txt");
+        assert_eq!(current["order"], "desc");
+
+        let content = TestClient::get("http://127.0.0.1:7979/dir1/")
+            .insert_header("accept", "text/html")
+            .send(&service)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert!(content.contains("test3.txt") && !content.contains("dir2"));
+        assert!(content.contains(r#"<a href="/dir1/">dir1</a>"#));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/dir1/?download=tar")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-type"], "application/x-tar");
+        let archive = res.take_bytes().await.unwrap();
+        assert_eq!(&archive[..9], b"test3.txt");
+        assert_eq!(archive.len(), 512 * 4);
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/dir1/?download=zip")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-type"], "application/zip");
+        assert_eq!(
+            res.headers()["content-disposition"],
+            "attachment; filename=\"dir1.zip\""
+        );
+        let archive = res.take_bytes().await.unwrap();
+        assert_eq!(&archive[..4], b"PK\x03\x04");
+
+        let router = Router::with_path("<**path>").get(
+            DirHandler::width_options(vec!["../examples/file-list/static/test"], options).with_renderer(
+                |_req: &Request, current: &CurrentInfo, res: &mut Response| {
+                    let names = current.files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+                    res.render(Text::Plain(names.join(",")));
+                },
+            ),
+        );
+        let content = TestClient::get("http://127.0.0.1:7979/?sort=size&order=desc")
+            .send(router)
+            .await
+            .take_string()
+            .await
+            .unwrap();
+        assert_eq!(content, "test2.txt,test1.txt");
+    }
+
+    #[tokio::test]
+    async fn test_serve_store_files() {
+        let store = OverlayStore::new()
+            .with_layer(
+                MemoryStore::new()
+                    .with_modified(Some(std::time::SystemTime::UNIX_EPOCH))
+                    .with_file("docs/a.txt", "upper"),
+            )
+            .with_layer(
+                MemoryStore::new()
+                    .with_file("docs/a.txt", "lower")
+                    .with_file("docs/b.txt", "0123456789")
+                    .with_file("app.js", "raw")
+                    .with_file("app.js.gz", "gzip"),
+            );
+        let handler = DirHandler::from_store(store)
+            .with_options(Options::default().with_archive(true))
+            .precompressed(Precompressed::all());
+        let service = Service::new(Router::with_path("<**path>").get(handler.clone()));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/docs/a.txt").send(&service).await;
+        let etag = res.headers()["etag"].clone();
+        assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(res.take_string().await.unwrap(), "upper");
+
+        let res = TestClient::get("http://127.0.0.1:7979/docs/a.txt")
+            .insert_header("if-none-match", etag)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_MODIFIED));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/docs/b.txt")
+            .insert_header("range", "bytes=2-4")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code(), Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.headers()["content-range"], "bytes 2-4/10");
+        assert_eq!(res.take_string().await.unwrap(), "234");
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/app.js")
+            .insert_header("accept-encoding", "gzip")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        assert_eq!(res.headers()["vary"], "accept-encoding");
+        assert_eq!(res.take_bytes().await.unwrap(), "gzip");
+
+        let res = TestClient::get("http://127.0.0.1:7979/docs/").send(&service).await;
+        assert_eq!(res.status_code(), Some(StatusCode::NOT_FOUND));
+
+        let service = Service::new(
+            Router::with_path("<**path>")
+                .get(handler.with_options(Options::new().with_listing(true).with_archive(true))),
+        );
+        let current = TestClient::get("http://127.0.0.1:7979/docs/")
+            .insert_header("accept", "application/json")
+            .send(&service)
+            .await
+            .take_json::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(current["files"][0]["name"], "a.txt");
+        assert_eq!(current["files"][0]["size"], 5);
+        assert_eq!(current["files"][1]["name"], "b.txt");
+
+        let archive = TestClient::get("http://127.0.0.1:7979/docs/?download=tar")
+            .send(&service)
+            .await
+            .take_bytes()
+            .await
+            .unwrap();
+        assert_eq!(&archive[..5], b"a.txt");
+        assert_eq!(&archive[512..517], b"upper");
+    }
+
+    #[tokio::test]
+    async fn test_serve_hidden_files() {
+        let store = MemoryStore::new()
+            .with_file(".env", "secret")
+            .with_file(".git/config", "secret")
+            .with_file("docs/a.txt", "visible")
+            .with_file("docs/secret/key.txt", "secret");
+        let options = Options::new()
+            .with_listing(true)
+            .with_archive(true)
+            .with_hidden("secret");
+        let handler = DirHandler::from_store(store).with_options(options.clone());
+        let service = Service::new(Router::with_path("<**path>").get(handler.clone()));
+
+        for path in [
+            "/.env",
+            "/.git/config",
+            "/.git/",
+            "/docs/secret/key.txt",
+            "/docs/secret/",
+        ] {
+            let res = TestClient::get(format!("http://127.0.0.1:7979{}", path))
+                .send(&service)
+                .await;
+            assert_eq!(res.status_code(), Some(StatusCode::NOT_FOUND), "{}", path);
+        }
+        let mut res = TestClient::get("http://127.0.0.1:7979/docs/a.txt").send(&service).await;
+        assert_eq!(res.take_string().await.unwrap(), "visible");
+
+        let contains = |archive: &[u8], name: &[u8]| archive.windows(name.len()).any(|w| w == name);
+        let archive = TestClient::get("http://127.0.0.1:7979/?download=tar")
+            .send(&service)
+            .await
+            .take_bytes()
+            .await
+            .unwrap();
+        assert!(contains(&archive, b"docs/a.txt"));
+        assert!(!contains(&archive, b".git") && !contains(&archive, b".env") && !contains(&archive, b"secret"));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/?download=zip")
+            .send(&service)
+            .await;
+        assert_eq!(res.headers()["content-type"], "application/zip");
+        assert_eq!(
+            res.headers()["content-disposition"],
+            "attachment; filename=\"archive.zip\""
+        );
+        let archive = res.take_bytes().await.unwrap();
+        assert_eq!(&archive[..4], b"PK\x03\x04");
+        assert!(contains(&archive, b"docs/a.txt") && contains(&archive, b"visible"));
+        assert!(!contains(&archive, b".git") && !contains(&archive, b"secret"));
+
+        let store = MemoryStore::new().with_file("say \"hi\"\u{e9}/a.txt", "hi");
+        let quoted = DirHandler::from_store(store).with_options(options.clone());
+        let res = TestClient::get("http://127.0.0.1:7979/say%20%22hi%22%C3%A9/?download=zip")
+            .send(Router::with_path("<**path>").get(quoted))
+            .await;
+        assert_eq!(
+            res.headers()["content-disposition"],
+            "attachment; filename=\"say _hi__.zip\"; filename*=UTF-8''say%20%22hi%22%C3%A9%2Ezip"
+        );
+
+        let handler = handler.with_options(options.with_dot_files(true));
+        let mut res = TestClient::get("http://127.0.0.1:7979/.git/config")
+            .send(Router::with_path("<**path>").get(handler))
+            .await;
+        assert_eq!(res.take_string().await.unwrap(), "secret");
+    }
 }
@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use salvo_core::fs::NamedFileBuilder;
+use salvo_core::http::header::{HeaderValue, ACCEPT_ENCODING, VARY};
+use salvo_core::http::{HeaderMap, Response};
+
+use super::store::{StaticStore, StoreMetadata};
+
+/// Encoding of a precompressed file stored next to the original file.
+///
+/// `app.js.br`, `app.js.zst` and `app.js.gz` are precompressed variants of `app.js`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precompressed {
+    /// Brotli, file extension is `br`.
+    Brotli,
+    /// Zstandard, file extension is `zst`.
+    Zstd,
+    /// Gzip, file extension is `gz`.
+    Gzip,
+}
+impl Precompressed {
+    /// All supported encodings, from the most to the least preferred.
+    #[inline]
+    pub fn all() -> Vec<Precompressed> {
+        vec![Precompressed::Brotli, Precompressed::Zstd, Precompressed::Gzip]
+    }
+    /// Get the file extension of this encoding.
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Precompressed::Brotli => "br",
+            Precompressed::Zstd => "zst",
+            Precompressed::Gzip => "gz",
+        }
+    }
+    /// Get the `Content-Encoding` value of this encoding.
+    #[inline]
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Precompressed::Brotli => "br",
+            Precompressed::Zstd => "zstd",
+            Precompressed::Gzip => "gzip",
+        }
+    }
+
+    #[inline]
+    fn variant_path(&self, path: &Path) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".");
+        file_name.push(self.extension());
+        path.with_file_name(file_name)
+    }
+}
+
+/// Returns the accepted encodings sorted by client preference, ties keep the order of `encodings`.
+fn negotiate(headers: &HeaderMap, encodings: &[Precompressed]) -> Vec<Precompressed> {
+    let mut accepted = Vec::new();
+    let mut wildcard = None;
+    for item in headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+    {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        let q = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if coding == "*" {
+            wildcard = Some(q);
+        } else if !coding.is_empty() {
+            accepted.push((coding, q));
+        }
+    }
+    let mut candidates = encodings
+        .iter()
+        .filter_map(|encoding| {
+            let q = accepted
+                .iter()
+                .find(|(coding, _)| {
+                    coding == encoding.encoding() || (coding == "x-gzip" && *encoding == Precompressed::Gzip)
+                })
+                .map(|(_, q)| *q)
+                .or(wildcard)
+                .unwrap_or(0.0);
+            if q > 0.0 {
+                Some((*encoding, q))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    // Stable sort keeps the server preference between encodings with the same quality.
+    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    candidates.into_iter().map(|(encoding, _)| encoding).collect()
+}
+
+/// Makes `builder` serve the best precompressed variant of `path` accepted by the client, if there is one.
+pub(crate) fn select_variant(
+    builder: NamedFileBuilder,
+    path: &Path,
+    encodings: &[Precompressed],
+    headers: &HeaderMap,
+) -> NamedFileBuilder {
+    for encoding in negotiate(headers, encodings) {
+        let variant = encoding.variant_path(path);
+        if variant.is_file() {
+            return builder.with_encoded_path(variant, encoding.encoding());
+        }
+    }
+    builder
+}
+
+/// Returns the path, metadata and encoding of the best precompressed variant of `path` in `store` accepted by the
+/// client, if there is one.
+pub(crate) async fn select_store_variant(
+    store: &dyn StaticStore,
+    path: &str,
+    encodings: &[Precompressed],
+    headers: &HeaderMap,
+) -> Option<(String, StoreMetadata, Precompressed)> {
+    for encoding in negotiate(headers, encodings) {
+        let variant = format!("{}.{}", path, encoding.extension());
+        match store.metadata(&variant).await {
+            Some(metadata) if metadata.is_file() => return Some((variant, metadata, encoding)),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Adds `Vary: Accept-Encoding` since the response depends on the client's accepted encodings.
+#[inline]
+pub(crate) fn add_vary(res: &mut Response) {
+    res.headers_mut()
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let all = Precompressed::all();
+        assert_eq!(
+            negotiate(&accept("gzip, deflate, br"), &all),
+            vec![Precompressed::Brotli, Precompressed::Gzip]
+        );
+        assert_eq!(
+            negotiate(&accept("br;q=0.5, gzip;q=0.8, zstd"), &all),
+            vec![Precompressed::Zstd, Precompressed::Gzip, Precompressed::Brotli]
+        );
+        assert_eq!(
+            negotiate(&accept("*, br;q=0"), &all),
+            vec![Precompressed::Zstd, Precompressed::Gzip]
+        );
+        assert_eq!(
+            negotiate(&accept("gzip, br"), &[Precompressed::Gzip, Precompressed::Brotli]),
+            vec![Precompressed::Gzip, Precompressed::Brotli]
+        );
+        assert!(negotiate(&accept("identity"), &all).is_empty());
+        assert!(negotiate(&HeaderMap::new(), &all).is_empty());
+    }
+
+    #[test]
+    fn test_variant_path() {
+        assert_eq!(
+            Precompressed::Brotli.variant_path(Path::new("static/app.js")),
+            PathBuf::from("static/app.js.br")
+        );
+        assert_eq!(
+            Precompressed::Gzip.variant_path(Path::new("index.html")),
+            PathBuf::from("index.html.gz")
+        );
+    }
+}
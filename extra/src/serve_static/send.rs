@@ -0,0 +1,51 @@
+use salvo_core::fs::NamedContent;
+use salvo_core::http::{Request, Response, StatusError};
+
+use super::precompressed::{self, Precompressed};
+use super::store::StaticStore;
+
+/// Sends the file at `path` of `store`, or its best precompressed variant accepted by the client, with
+/// [`NamedContent`].
+///
+/// Returns `false` if the file can not be read and an error status is set.
+pub(super) async fn send_store_file(
+    store: &dyn StaticStore,
+    path: &str,
+    encodings: &[Precompressed],
+    buffer_size: Option<u64>,
+    req: &mut Request,
+    res: &mut Response,
+) -> bool {
+    let (content_path, metadata, encoding) =
+        match precompressed::select_store_variant(store, path, encodings, req.headers()).await {
+            Some((variant, metadata, encoding)) => (variant, metadata, Some(encoding)),
+            None => match store.metadata(path).await {
+                Some(metadata) if metadata.is_file() => (path.to_owned(), metadata, None),
+                _ => {
+                    res.set_status_error(StatusError::not_found());
+                    return false;
+                }
+            },
+        };
+    let reader = match store.open(&content_path).await {
+        Ok(reader) => reader,
+        Err(e) => {
+            tracing::error!(error = ?e, path = %content_path, "open file failed");
+            res.set_status_error(StatusError::internal_server_error().with_summary("file read error"));
+            return false;
+        }
+    };
+
+    let mut builder = NamedContent::from_reader(path, reader, metadata.len());
+    if let Some(modified) = metadata.modified() {
+        builder = builder.with_last_modified(modified);
+    }
+    if let Some(encoding) = encoding {
+        builder = builder.with_content_encoding(encoding.encoding());
+    }
+    if let Some(size) = buffer_size {
+        builder = builder.with_buffer_size(size);
+    }
+    builder.send(req, res).await;
+    true
+}
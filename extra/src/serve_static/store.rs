@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Error as IoError, ErrorKind, Result as IoResult};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use salvo_core::async_trait;
+use tokio::io::{AsyncRead, AsyncSeek};
+
+/// Metadata of a file or directory in a [`StaticStore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoreMetadata {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+impl StoreMetadata {
+    /// Create metadata of a file with `len` bytes.
+    #[inline]
+    pub fn file(len: u64, modified: Option<SystemTime>) -> Self {
+        StoreMetadata {
+            is_dir: false,
+            len,
+            modified,
+        }
+    }
+    /// Create metadata of a directory.
+    #[inline]
+    pub fn dir(modified: Option<SystemTime>) -> Self {
+        StoreMetadata {
+            is_dir: true,
+            len: 0,
+            modified,
+        }
+    }
+    /// Returns `true` if this is a directory.
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+    /// Returns `true` if this is a file.
+    #[inline]
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+    /// Get the file size in bytes, it is `0` for directories.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    /// Returns `true` if the file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the last modified time.
+    #[inline]
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+impl From<&std::fs::Metadata> for StoreMetadata {
+    #[inline]
+    fn from(metadata: &std::fs::Metadata) -> Self {
+        if metadata.is_dir() {
+            StoreMetadata::dir(metadata.modified().ok())
+        } else {
+            StoreMetadata::file(metadata.len(), metadata.modified().ok())
+        }
+    }
+}
+
+/// An entry of a directory in a [`StaticStore`].
+#[derive(Clone, Debug)]
+pub struct StoreEntry {
+    /// Entry name.
+    pub name: String,
+    /// Entry metadata.
+    pub metadata: StoreMetadata,
+}
+
+/// Reader of a file opened from a [`StaticStore`].
+pub trait StoreReader: AsyncRead + AsyncSeek + Send + Unpin {}
+impl<T> StoreReader for T where T: AsyncRead + AsyncSeek + Send + Unpin {}
+
+/// A source of static files, like a local directory or files in memory.
+///
+/// Paths are relative to the root of the store, separated by `/`, without `.` or `..` segments. The root itself
+/// is the empty path.
+#[async_trait]
+pub trait StaticStore: Send + Sync + 'static {
+    /// Returns metadata of the file or directory at `path`, `None` if it does not exist.
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata>;
+    /// Lists the entries of the directory at `path`.
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>>;
+    /// Opens the file at `path` for reading.
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>>;
+    /// Returns the path on the local file system of the file at `path`, if there is one.
+    ///
+    /// Files with a local path are sent with [`NamedFile`](salvo_core::fs::NamedFile). The default implementation
+    /// returns `None`.
+    async fn local_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+#[async_trait]
+impl<S> StaticStore for Arc<S>
+where
+    S: StaticStore + ?Sized,
+{
+    #[inline]
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata> {
+        (**self).metadata(path).await
+    }
+    #[inline]
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>> {
+        (**self).read_dir(path).await
+    }
+    #[inline]
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>> {
+        (**self).open(path).await
+    }
+    #[inline]
+    async fn local_path(&self, path: &str) -> Option<PathBuf> {
+        (**self).local_path(path).await
+    }
+}
+
+/// Serves files from a directory on the local file system.
+///
+/// Symbolic links are followed when requested directly, but are not listed by [`StaticStore::read_dir`], so
+/// directory listings and archives never loop.
+#[derive(Clone, Debug)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+impl LocalStore {
+    /// Create a new `LocalStore` serving files under `root`.
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStore { root: root.into() }
+    }
+    /// Get the root directory.
+    #[inline]
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+}
+#[async_trait]
+impl StaticStore for LocalStore {
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata> {
+        tokio::fs::metadata(self.root.join(path))
+            .await
+            .ok()
+            .map(|metadata| StoreMetadata::from(&metadata))
+    }
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>> {
+        let mut read_dir = tokio::fs::read_dir(self.root.join(path)).await?;
+        let mut entries = Vec::new();
+        while let Some(entry) = read_dir.next_entry().await? {
+            let metadata = match entry.metadata().await {
+                Ok(metadata) if metadata.is_dir() || metadata.is_file() => metadata,
+                _ => continue,
+            };
+            entries.push(StoreEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                metadata: StoreMetadata::from(&metadata),
+            });
+        }
+        Ok(entries)
+    }
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>> {
+        let file = tokio::fs::File::open(self.root.join(path)).await?;
+        Ok(Box::new(file))
+    }
+    async fn local_path(&self, path: &str) -> Option<PathBuf> {
+        Some(self.root.join(path))
+    }
+}
+
+/// Serves files kept in memory, directories are implied by the file paths.
+///
+/// # Example
+///
+/// ```
+/// use salvo_extra::serve_static::{DirHandler, MemoryStore};
+///
+/// let store = MemoryStore::new()
+///     .with_file("index.html", &b"<h1>Hello</h1>"[..])
+///     .with_file("assets/app.js", &b"console.log('hello');"[..]);
+/// let handler = DirHandler::from_store(store);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MemoryStore {
+    files: BTreeMap<String, Bytes>,
+    modified: Option<SystemTime>,
+}
+impl Default for MemoryStore {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl MemoryStore {
+    /// Create a new `MemoryStore` without files, their last modified time is unknown like embedded files.
+    ///
+    /// Set it with [`with_modified`](Self::with_modified) to send `Last-Modified` and `ETag` headers.
+    #[inline]
+    pub fn new() -> Self {
+        MemoryStore {
+            files: BTreeMap::new(),
+            modified: None,
+        }
+    }
+    /// Adds a file at `path` and returns `Self`.
+    #[inline]
+    pub fn with_file(mut self, path: impl Into<String>, data: impl Into<Bytes>) -> Self {
+        self.insert(path, data);
+        self
+    }
+    /// Sets last modified time of all files and returns `Self`.
+    #[inline]
+    pub fn with_modified(mut self, modified: Option<SystemTime>) -> Self {
+        self.modified = modified;
+        self
+    }
+    /// Adds or replaces a file at `path`.
+    #[inline]
+    pub fn insert(&mut self, path: impl Into<String>, data: impl Into<Bytes>) {
+        self.files.insert(normalize_path(&path.into()), data.into());
+    }
+
+    /// Returns the files under the directory `path`, with paths relative to it.
+    fn children<'a>(&'a self, path: &str) -> impl Iterator<Item = (&'a str, &'a Bytes)> {
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path)
+        };
+        let skip = prefix.len();
+        self.files
+            .range(prefix.clone()..)
+            .take_while(move |(name, _)| name.starts_with(&prefix))
+            .map(move |(name, data)| (&name[skip..], data))
+    }
+}
+#[async_trait]
+impl StaticStore for MemoryStore {
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata> {
+        if let Some(data) = self.files.get(path) {
+            Some(StoreMetadata::file(data.len() as u64, self.modified))
+        } else if self.children(path).next().is_some() || path.is_empty() {
+            Some(StoreMetadata::dir(self.modified))
+        } else {
+            None
+        }
+    }
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>> {
+        if self.files.contains_key(path) || (!path.is_empty() && self.children(path).next().is_none()) {
+            return Err(IoError::new(ErrorKind::NotFound, "directory not found"));
+        }
+        let mut dirs = HashSet::new();
+        let mut entries = Vec::new();
+        for (name, data) in self.children(path) {
+            match name.split_once('/') {
+                Some((dir, _)) => {
+                    if dirs.insert(dir) {
+                        entries.push(StoreEntry {
+                            name: dir.to_owned(),
+                            metadata: StoreMetadata::dir(self.modified),
+                        });
+                    }
+                }
+                None => entries.push(StoreEntry {
+                    name: name.to_owned(),
+                    metadata: StoreMetadata::file(data.len() as u64, self.modified),
+                }),
+            }
+        }
+        Ok(entries)
+    }
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>> {
+        match self.files.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data.clone()))),
+            None => Err(IoError::new(ErrorKind::NotFound, "file not found")),
+        }
+    }
+}
+
+/// Serves files from several stores, files in former stores shadow files with the same path in latter ones and
+/// directory listings are merged.
+#[derive(Clone, Default)]
+pub struct OverlayStore {
+    layers: Vec<Arc<dyn StaticStore>>,
+}
+impl OverlayStore {
+    /// Create a new `OverlayStore` without layers.
+    #[inline]
+    pub fn new() -> Self {
+        OverlayStore { layers: Vec::new() }
+    }
+    /// Adds a store below the existing ones and returns `Self`.
+    #[inline]
+    pub fn with_layer(mut self, store: impl StaticStore) -> Self {
+        self.layers.push(Arc::new(store));
+        self
+    }
+
+    async fn layer_of(&self, path: &str) -> Option<&Arc<dyn StaticStore>> {
+        for layer in &self.layers {
+            if layer.metadata(path).await.is_some() {
+                return Some(layer);
+            }
+        }
+        None
+    }
+}
+#[async_trait]
+impl StaticStore for OverlayStore {
+    async fn metadata(&self, path: &str) -> Option<StoreMetadata> {
+        for layer in &self.layers {
+            if let Some(metadata) = layer.metadata(path).await {
+                return Some(metadata);
+            }
+        }
+        None
+    }
+    async fn read_dir(&self, path: &str) -> IoResult<Vec<StoreEntry>> {
+        let mut names = HashSet::new();
+        let mut entries = Vec::new();
+        let mut last_error = None;
+        let mut found = false;
+        for layer in &self.layers {
+            match layer.read_dir(path).await {
+                Ok(layer_entries) => {
+                    found = true;
+                    for entry in layer_entries {
+                        if names.insert(entry.name.clone()) {
+                            entries.push(entry);
+                        }
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        if found {
+            Ok(entries)
+        } else {
+            Err(last_error.unwrap_or_else(|| IoError::new(ErrorKind::NotFound, "directory not found")))
+        }
+    }
+    async fn open(&self, path: &str) -> IoResult<Box<dyn StoreReader>> {
+        match self.layer_of(path).await {
+            Some(layer) => layer.open(path).await,
+            None => Err(IoError::new(ErrorKind::NotFound, "file not found")),
+        }
+    }
+    async fn local_path(&self, path: &str) -> Option<PathBuf> {
+        self.layer_of(path).await?.local_path(path).await
+    }
+}
+
+#[inline]
+pub(super) fn normalize_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Joins a store path with a relative path.
+#[inline]
+pub(super) fn join_path(base: &str, path: &str) -> String {
+    if base.is_empty() {
+        path.to_owned()
+    } else {
+        format!("{}/{}", base, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryStore::new()
+            .with_file("index.html", "home")
+            .with_file("/docs/guide/intro.md", "intro")
+            .with_file("docs/readme.md", "readme");
+
+        assert!(store.metadata("").await.unwrap().is_dir());
+        assert!(store.metadata("docs/guide").await.unwrap().is_dir());
+        assert_eq!(store.metadata("docs/readme.md").await.unwrap().len(), 6);
+        assert!(store.metadata("doc").await.is_none());
+        assert!(store.read_dir("index.html").await.is_err());
+        assert_eq!(store.metadata("index.html").await.unwrap().modified(), None);
+        let modified = SystemTime::UNIX_EPOCH;
+        let dated = store.clone().with_modified(Some(modified));
+        assert_eq!(dated.metadata("index.html").await.unwrap().modified(), Some(modified));
+
+        let mut names = store
+            .read_dir("docs")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.metadata.is_dir()))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec![("guide".to_owned(), true), ("readme.md".to_owned(), false)]);
+
+        let mut content = String::new();
+        store
+            .open("docs/guide/intro.md")
+            .await
+            .unwrap()
+            .read_to_string(&mut content)
+            .await
+            .unwrap();
+        assert_eq!(content, "intro");
+    }
+
+    #[tokio::test]
+    async fn test_overlay_store() {
+        let store = OverlayStore::new()
+            .with_layer(MemoryStore::new().with_file("a.txt", "upper"))
+            .with_layer(
+                MemoryStore::new()
+                    .with_file("a.txt", "lower")
+                    .with_file("b.txt", "lower"),
+            );
+        let mut names = store
+            .read_dir("")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["a.txt", "b.txt"]);
+
+        let mut content = String::new();
+        store
+            .open("a.txt")
+            .await
+            .unwrap()
+            .read_to_string(&mut content)
+            .await
+            .unwrap();
+        assert_eq!(content, "upper");
+        assert!(store.open("c.txt").await.is_err());
+        assert!(store.local_path("a.txt").await.is_none());
+    }
+}
@@ -71,41 +71,42 @@ use async_session::hmac::{Hmac, Mac, NewMac};
 use async_session::sha2::Sha256;
 use cookie::{Cookie, Key, SameSite};
 use salvo_core::http::uri::Scheme;
+use salvo_core::http::StatusError;
 use salvo_core::routing::FlowCtrl;
-use salvo_core::{async_trait, Depot, Handler, Request, Response};
+use salvo_core::{async_trait, Depot, DepotError, DepotKey, Handler, Request, Response};
 
 /// Key for store data in depot.
-pub const SESSION_KEY: &str = "::salvo::extra::session";
+pub const SESSION_KEY: DepotKey<Session> = DepotKey::new("::salvo::extra::session");
 const BASE64_DIGEST_LEN: usize = 44;
 
 /// SessionDepotExt
 pub trait SessionDepotExt {
-    /// Set session
-    fn set_session(&mut self, session: Session);
+    /// Set session, returns the previous session.
+    fn set_session(&mut self, session: Session) -> Result<Option<Session>, DepotError>;
     /// Take session
-    fn take_session(&mut self) -> Option<Session>;
+    fn take_session(&mut self) -> Result<Session, DepotError>;
     /// Get session reference
-    fn session(&self) -> Option<&Session>;
+    fn session(&self) -> Result<&Session, DepotError>;
     /// Get session mutable reference
-    fn session_mut(&mut self) -> Option<&mut Session>;
+    fn session_mut(&mut self) -> Result<&mut Session, DepotError>;
 }
 
 impl SessionDepotExt for Depot {
     #[inline]
-    fn set_session(&mut self, session: Session) {
-        self.insert(SESSION_KEY, session);
+    fn set_session(&mut self, session: Session) -> Result<Option<Session>, DepotError> {
+        self.put(SESSION_KEY, session)
     }
     #[inline]
-    fn take_session(&mut self) -> Option<Session> {
-        self.remove(SESSION_KEY)
+    fn take_session(&mut self) -> Result<Session, DepotError> {
+        self.take(SESSION_KEY)
     }
     #[inline]
-    fn session(&self) -> Option<&Session> {
-        self.get(SESSION_KEY)
+    fn session(&self) -> Result<&Session, DepotError> {
+        self.fetch(SESSION_KEY)
     }
     #[inline]
-    fn session_mut(&mut self) -> Option<&mut Session> {
-        self.get_mut(SESSION_KEY)
+    fn session_mut(&mut self) -> Result<&mut Session, DepotError> {
+        self.fetch_mut(SESSION_KEY)
     }
 }
 
@@ -150,14 +151,25 @@ where
             session.expire_in(ttl);
         }
 
-        depot.set_session(session);
+        if let Err(e) = depot.set_session(session) {
+            tracing::error!(error = ?e, "unable to put session to depot");
+            res.set_status_error(StatusError::internal_server_error());
+            ctrl.skip_rest();
+            return;
+        }
 
         ctrl.call_next(req, depot, res).await;
         if ctrl.is_ceased() {
             return;
         }
 
-        let session = depot.take_session().expect("session should exist in depot");
+        let session = match depot.take_session() {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::error!(error = ?e, "unable to take session from depot");
+                return;
+            }
+        };
         if session.is_destroyed() {
             if let Err(e) = self.store.destroy_session(session).await {
                 tracing::error!(error = ?e, "unable to destroy session");
@@ -232,7 +244,7 @@ where
     /// Disables the `save_unchanged` setting.
     ///
     /// When `save_unchanged` is enabled, a session will cookie will always be set.
-    /// 
+    ///
     /// With `save_unchanged` disabled, the session data must be modified
     /// from the `Default` value in order for it to save. If a session
     /// already exists and its data unmodified in the course of a
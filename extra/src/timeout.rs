@@ -1,32 +1,248 @@
-//! basic auth middleware
+//! timeout middleware
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use salvo_core::prelude::*;
+//! use salvo_extra::timeout::{TimeoutDepotExt, TimeoutHandler, TimeoutOverride};
+//!
+//! async fn build_report() -> String {
+//!     tokio::time::sleep(Duration::from_secs(10)).await;
+//!     "report".into()
+//! }
+//!
+//! #[handler]
+//! async fn report(depot: &mut Depot) -> String {
+//!     let token = depot.timeout_control().unwrap().cancellation_token();
+//!     let work = tokio::spawn(async move {
+//!         // The work stops when the deadline passes, or ends by itself when it completes first.
+//!         tokio::select! {
+//!             _ = token.cancelled() => None,
+//!             content = build_report() => Some(content),
+//!         }
+//!     });
+//!     work.await.ok().flatten().unwrap_or_default()
+//! }
+//!
+//! let router = Router::new()
+//!     .hoop(TimeoutHandler::new(Duration::from_secs(5)))
+//!     .push(Router::with_path("report").hoop(TimeoutOverride::new(Duration::from_secs(60))).get(report));
+//! ```
+use std::sync::Arc;
 use std::time::Duration;
 
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
 use salvo_core::async_trait;
-use salvo_core::http::{Request, Response, StatusError};
+use salvo_core::http::{Request, Response, StatusCode, StatusError};
 use salvo_core::routing::FlowCtrl;
 use salvo_core::{Depot, Handler};
 
+/// key used to insert [`TimeoutControl`] to depot.
+pub const TIMEOUT_CONTROL_KEY: &str = "::salvo::extra::timeout::control";
+
+/// TimeoutDepotExt
+pub trait TimeoutDepotExt {
+    /// Get timeout control of current request.
+    fn timeout_control(&self) -> Option<&TimeoutControl>;
+}
+
+impl TimeoutDepotExt for Depot {
+    #[inline]
+    fn timeout_control(&self) -> Option<&TimeoutControl> {
+        self.get(TIMEOUT_CONTROL_KEY)
+    }
+}
+
+#[derive(Debug)]
+struct ControlInner {
+    started: Instant,
+    deadline: Mutex<Instant>,
+    changed: Notify,
+    token: CancellationToken,
+}
+
+/// Deadline of current request, inserted into depot by [`TimeoutHandler`].
+///
+/// The deadline can be changed by inner handlers, and the cancellation token is cancelled when
+/// the deadline passes so that spawned work can stop cleanly.
+#[derive(Clone, Debug)]
+pub struct TimeoutControl {
+    inner: Arc<ControlInner>,
+}
+
+impl TimeoutControl {
+    #[inline]
+    fn new(timeout: Duration) -> Self {
+        let started = Instant::now();
+        TimeoutControl {
+            inner: Arc::new(ControlInner {
+                started,
+                deadline: Mutex::new(started + timeout),
+                changed: Notify::new(),
+                token: CancellationToken::new(),
+            }),
+        }
+    }
+    /// Get the deadline of current request.
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        *self.inner.deadline.lock()
+    }
+    /// Get the time left before the deadline.
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        self.deadline().saturating_duration_since(Instant::now())
+    }
+    /// Sets the timeout of current request, counted from the time the request entered [`TimeoutHandler`].
+    ///
+    /// Has no effect once the request timed out.
+    #[inline]
+    pub fn set_timeout(&self, timeout: Duration) {
+        if self.is_cancelled() {
+            return;
+        }
+        *self.inner.deadline.lock() = self.inner.started + timeout;
+        self.inner.changed.notify_one();
+    }
+    /// Get a cancellation token which is cancelled when the request times out.
+    #[inline]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.inner.token.clone()
+    }
+    /// Returns `true` if the request timed out.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.token.is_cancelled()
+    }
+}
+
 /// TimeoutHandler
+///
+/// Answers with `503 Service Unavailable` by default when the inner handlers do not finish in time.
 pub struct TimeoutHandler {
     timeout: Duration,
+    status_code: StatusCode,
+    summary: Option<String>,
+    detail: Option<String>,
 }
 impl TimeoutHandler {
     /// Create a new `TimeoutHandler`.
     #[inline]
     pub fn new(timeout: Duration) -> Self {
-        TimeoutHandler { timeout }
+        TimeoutHandler {
+            timeout,
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+            summary: None,
+            detail: Some("Server process the request timeout.".into()),
+        }
+    }
+    /// Get timeout.
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    /// Get status code of timeout response.
+    #[inline]
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+    /// Sets status code of timeout response, such as `503 Service Unavailable` or `504 Gateway Timeout`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status_code` is not an error status code.
+    #[inline]
+    pub fn with_status_code(mut self, status_code: StatusCode) -> Self {
+        assert!(
+            StatusError::from_code(status_code).is_some(),
+            "status code of timeout response must be an error status code"
+        );
+        self.status_code = status_code;
+        self
+    }
+    /// Sets summary of timeout response.
+    #[inline]
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+    /// Sets detail of timeout response.
+    #[inline]
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    #[inline]
+    fn status_error(&self) -> StatusError {
+        let mut error = StatusError::from_code(self.status_code).unwrap_or_else(StatusError::service_unavailable);
+        if let Some(summary) = &self.summary {
+            error = error.with_summary(summary);
+        }
+        if let Some(detail) = &self.detail {
+            error = error.with_detail(detail);
+        }
+        error
     }
 }
 #[async_trait]
 impl Handler for TimeoutHandler {
     #[inline]
     async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
-        tokio::select! {
-            _ = ctrl.call_next(req, depot, res) => {},
-            _ = tokio::time::sleep(self.timeout) => {
-                res.set_status_error(StatusError::internal_server_error().with_detail("Server process the request timeout."))
+        let control = TimeoutControl::new(self.timeout);
+        depot.insert(TIMEOUT_CONTROL_KEY, control.clone());
+
+        let finished = {
+            let next = ctrl.call_next(req, depot, res);
+            tokio::pin!(next);
+            loop {
+                tokio::select! {
+                    _ = &mut next => break true,
+                    _ = control.inner.changed.notified() => {},
+                    _ = tokio::time::sleep_until(control.deadline()) => {
+                        if control.deadline() <= Instant::now() {
+                            break false;
+                        }
+                    }
+                }
             }
+        };
+        if finished {
+            return;
         }
+        control.inner.token.cancel();
+        res.set_status_error(self.status_error());
+    }
+}
+
+/// Overrides the timeout set by an outer [`TimeoutHandler`] for the routes it is added to.
+///
+/// The new timeout is counted from the time the request entered the `TimeoutHandler`, so it can
+/// both extend and shorten the deadline.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeoutOverride {
+    timeout: Duration,
+}
+impl TimeoutOverride {
+    /// Create a new `TimeoutOverride`.
+    #[inline]
+    pub fn new(timeout: Duration) -> Self {
+        TimeoutOverride { timeout }
+    }
+}
+#[async_trait]
+impl Handler for TimeoutOverride {
+    #[inline]
+    async fn handle(&self, req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+        if let Some(control) = depot.timeout_control() {
+            control.set_timeout(self.timeout);
+        }
+        ctrl.call_next(req, depot, res).await;
     }
 }
 
@@ -55,12 +271,9 @@ mod tests {
             .push(Router::with_path("fast").get(fast));
         let service = Service::new(router);
 
-        let content = TestClient::get("http://127.0.0.1:7979/slow")
-            .send(&service)
-            .await
-            .take_string()
-            .await
-            .unwrap();
+        let mut res = TestClient::get("http://127.0.0.1:7979/slow").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+        let content = res.take_string().await.unwrap();
         assert!(content.contains("timeout"));
 
         let content = TestClient::get("http://127.0.0.1:7979/fast")
@@ -71,4 +284,37 @@ mod tests {
             .unwrap();
         assert!(content.contains("hello"));
     }
+
+    #[tokio::test]
+    async fn test_timeout_override_and_cancel() {
+        #[handler]
+        async fn slow(depot: &mut Depot) -> &'static str {
+            let token = depot.timeout_control().unwrap().cancellation_token();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(300)) => "hello",
+                _ = token.cancelled() => "cancelled",
+            }
+        }
+
+        let handler = TimeoutHandler::new(Duration::from_millis(100))
+            .with_status_code(StatusCode::GATEWAY_TIMEOUT)
+            .with_detail("upstream took too long");
+        let router = Router::new()
+            .hoop(handler)
+            .push(Router::with_path("slow").get(slow))
+            .push(
+                Router::with_path("extended")
+                    .hoop(TimeoutOverride::new(Duration::from_secs(5)))
+                    .get(slow),
+            );
+        let service = Service::new(router);
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/slow").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(res.take_string().await.unwrap().contains("upstream took too long"));
+
+        let mut res = TestClient::get("http://127.0.0.1:7979/extended").send(&service).await;
+        assert_eq!(res.status_code().unwrap(), StatusCode::OK);
+        assert_eq!(res.take_string().await.unwrap(), "hello");
+    }
 }
@@ -2,7 +2,7 @@ use darling::{FromDeriveInput, FromField, FromMeta};
 use inflector::Inflector;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Attribute, DeriveInput, Error, Generics, Lit, Meta, NestedMeta, Type};
+use syn::{Attribute, DeriveInput, Error, Generics, Lit, LitStr, Meta, NestedMeta, Type};
 
 use crate::shared::{is_internal, omit_type_path_lifetimes, salvo_crate};
 
@@ -14,6 +14,31 @@ struct Field {
     sources: Vec<RawSource>,
     aliases: Vec<String>,
     rename: Option<String>,
+    validators: Vec<Validator>,
+}
+enum Validator {
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+        message: Option<String>,
+    },
+    Range {
+        min: Option<f64>,
+        max: Option<f64>,
+        message: Option<String>,
+    },
+    Regex {
+        pattern: LitStr,
+        message: Option<String>,
+    },
+    Email {
+        message: Option<String>,
+    },
+    Custom {
+        function: syn::Path,
+        message: Option<String>,
+    },
+    Nested,
 }
 #[derive(FromMeta, Debug)]
 struct RawSource {
@@ -34,6 +59,7 @@ impl FromField for Field {
             sources,
             aliases: parse_aliases(&field.attrs)?,
             rename: parse_rename(&field.attrs)?,
+            validators: parse_validators(&field.attrs)?,
         })
     }
 }
@@ -123,6 +149,8 @@ fn metadata_source(salvo: &Ident, source: &RawSource) -> TokenStream {
     let from = Ident::new(&source.from.to_pascal_case(), Span::call_site());
     let format = if source.format.to_lowercase() == "multimap" {
         Ident::new("MultiMap", Span::call_site())
+    } else if source.format.to_lowercase() == "msgpack" {
+        Ident::new("MsgPack", Span::call_site())
     } else {
         Ident::new(&source.format.to_pascal_case(), Span::call_site())
     };
@@ -211,6 +239,80 @@ pub(crate) fn generate(args: DeriveInput) -> Result<TokenStream, Error> {
     }
 
     let sv = format_ident!("__salvo_extract_{}", name);
+    let mut validations = Vec::new();
+    for field in &args.fields {
+        let ident = field.ident.as_ref().expect("fields are named");
+        let field_name = ident.to_string();
+        for validator in &field.validators {
+            let (code, check, message) = match validator {
+                Validator::Nested => {
+                    validations.push(quote! {
+                        if let Err(nested) = #salvo::extract::Extractible::validate(&self.#ident) {
+                            errors.add_nested(&*#sv, #field_name, nested);
+                        }
+                    });
+                    continue;
+                }
+                Validator::Length { min, max, message } => {
+                    let min = option_tokens(min);
+                    let max = option_tokens(max);
+                    (
+                        "length",
+                        quote! { #salvo::extract::validation::length(&self.#ident, #min, #max) },
+                        message,
+                    )
+                }
+                Validator::Range { min, max, message } => {
+                    let min = option_tokens(min);
+                    let max = option_tokens(max);
+                    (
+                        "range",
+                        quote! { #salvo::extract::validation::range(&self.#ident, #min, #max) },
+                        message,
+                    )
+                }
+                Validator::Regex { pattern, message } => (
+                    "regex",
+                    quote! {{
+                        static REGEX: #salvo::__private::once_cell::sync::Lazy<#salvo::__private::regex::Regex> =
+                            #salvo::__private::once_cell::sync::Lazy::new(|| #salvo::__private::regex::Regex::new(#pattern).expect("regex is checked by the macro"));
+                        #salvo::extract::validation::regex(&self.#ident, &*REGEX)
+                    }},
+                    message,
+                ),
+                Validator::Email { message } => (
+                    "email",
+                    quote! { #salvo::extract::validation::email(&self.#ident) },
+                    message,
+                ),
+                Validator::Custom { function, message } => ("custom", quote! { #function(&self.#ident) }, message),
+            };
+            let validation = match message {
+                Some(message) => quote! {
+                    if #check.is_err() {
+                        errors.add(#salvo::extract::ValidationError::for_field(&*#sv, #field_name, #code, #message));
+                    }
+                },
+                None => quote! {
+                    if let Err(message) = #check {
+                        errors.add(#salvo::extract::ValidationError::for_field(&*#sv, #field_name, #code, message));
+                    }
+                },
+            };
+            validations.push(validation);
+        }
+    }
+    let validate_fn = if validations.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            fn validate(&self) -> Result<(), #salvo::extract::ValidationErrors> {
+                let mut errors = #salvo::extract::ValidationErrors::new();
+                #(#validations)*
+                errors.into_result()
+            }
+        })
+    };
     let mt = name.to_string();
     let imp_code = if args.generics.lifetimes().next().is_none() {
         let de_life_def = syn::parse_str("'de").unwrap();
@@ -222,6 +324,7 @@ pub(crate) fn generate(args: DeriveInput) -> Result<TokenStream, Error> {
                 fn metadata() ->  &'static #salvo::extract::Metadata {
                     &*#sv
                 }
+                #validate_fn
             }
         }
     } else {
@@ -230,6 +333,7 @@ pub(crate) fn generate(args: DeriveInput) -> Result<TokenStream, Error> {
                 fn metadata() ->  &'static #salvo::extract::Metadata {
                     &*#sv
                 }
+                #validate_fn
             }
         }
     };
@@ -336,12 +440,25 @@ fn parse_sources(attrs: &[Attribute], key: &str) -> darling::Result<Vec<RawSourc
                                 source.from
                             )));
                         }
-                        if !["multimap", "json", "request"].contains(&source.format.as_str()) {
+                        if !["multimap", "json", "nested", "msgpack", "cbor", "xml", "request"]
+                            .contains(&source.format.as_str())
+                        {
                             return Err(darling::Error::custom(format!(
                                 "source format is invalid: {}",
                                 source.format
                             )));
                         }
+                        if ["msgpack", "cbor", "xml"].contains(&source.format.as_str()) && source.from != "body" {
+                            return Err(darling::Error::custom(format!(
+                                "source format `{}` is only supported for `body` sources",
+                                source.format
+                            )));
+                        }
+                        if source.format == "nested" && source.from != "query" && source.from != "body" {
+                            return Err(darling::Error::custom(
+                                "source format `nested` is only supported for `query` and `body` sources",
+                            ));
+                        }
                         if source.from == "request" && source.format != "request" {
                             return Err(darling::Error::custom(
                                 "source format must be `request` for `request` sources",
@@ -355,3 +472,128 @@ fn parse_sources(attrs: &[Attribute], key: &str) -> darling::Result<Vec<RawSourc
     }
     Ok(sources)
 }
+
+fn option_tokens<T: quote::ToTokens>(value: &Option<T>) -> TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+fn parse_validators(attrs: &[Attribute]) -> darling::Result<Vec<Validator>> {
+    let mut validators = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("validate") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(darling::Error::custom("expected `#[validate(...)]`").with_span(&meta)),
+        };
+        for meta in list.nested.iter() {
+            let meta = match meta {
+                NestedMeta::Meta(meta) => meta,
+                NestedMeta::Lit(lit) => return Err(darling::Error::unexpected_lit_type(lit)),
+            };
+            let name = meta
+                .path()
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            let validator = match (name.as_str(), meta) {
+                ("nested", Meta::Path(_)) => Validator::Nested,
+                ("email", Meta::Path(_)) => Validator::Email { message: None },
+                ("regex", Meta::NameValue(item)) => Validator::Regex {
+                    pattern: lit_regex(&item.lit)?,
+                    message: None,
+                },
+                ("custom", Meta::NameValue(item)) => Validator::Custom {
+                    function: lit_str(&item.lit)?.parse()?,
+                    message: None,
+                },
+                (_, Meta::List(list)) => {
+                    let mut values = Vec::with_capacity(list.nested.len());
+                    for nested in list.nested.iter() {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(item)) if item.path.get_ident().is_some() => {
+                                values.push((item.path.get_ident().unwrap().to_string(), &item.lit));
+                            }
+                            _ => return Err(darling::Error::custom("expected `name = value`").with_span(nested)),
+                        }
+                    }
+                    let get = |key: &str| values.iter().find(|(k, _)| k == key).map(|(_, lit)| *lit);
+                    for (key, lit) in &values {
+                        let allowed: &[&str] = match name.as_str() {
+                            "length" | "range" => &["min", "max", "message"],
+                            "regex" => &["pattern", "message"],
+                            "email" => &["message"],
+                            "custom" => &["function", "message"],
+                            _ => &[],
+                        };
+                        if !allowed.contains(&key.as_str()) {
+                            return Err(darling::Error::unknown_field(key).with_span(lit));
+                        }
+                    }
+                    let message = get("message").map(lit_str).transpose()?.map(|lit| lit.value());
+                    match name.as_str() {
+                        "length" => Validator::Length {
+                            min: get("min").map(lit_number::<usize>).transpose()?,
+                            max: get("max").map(lit_number::<usize>).transpose()?,
+                            message,
+                        },
+                        "range" => Validator::Range {
+                            min: get("min").map(lit_number::<f64>).transpose()?,
+                            max: get("max").map(lit_number::<f64>).transpose()?,
+                            message,
+                        },
+                        "regex" => Validator::Regex {
+                            pattern: lit_regex(
+                                get("pattern").ok_or_else(|| darling::Error::missing_field("pattern"))?,
+                            )?,
+                            message,
+                        },
+                        "email" => Validator::Email { message },
+                        "custom" => Validator::Custom {
+                            function: lit_str(
+                                get("function").ok_or_else(|| darling::Error::missing_field("function"))?,
+                            )?
+                            .parse()?,
+                            message,
+                        },
+                        _ => return Err(darling::Error::custom(format!("unknown validator: {}", name)).with_span(meta)),
+                    }
+                }
+                _ => return Err(darling::Error::custom(format!("invalid validator: {}", name)).with_span(meta)),
+            };
+            validators.push(validator);
+        }
+    }
+    Ok(validators)
+}
+
+fn lit_str(lit: &Lit) -> darling::Result<LitStr> {
+    match lit {
+        Lit::Str(lit) => Ok(lit.clone()),
+        _ => Err(darling::Error::unexpected_lit_type(lit)),
+    }
+}
+
+fn lit_regex(lit: &Lit) -> darling::Result<LitStr> {
+    let lit = lit_str(lit)?;
+    regex::Regex::new(&lit.value()).map_err(|e| syn::Error::new(lit.span(), format!("invalid regex: {}", e)))?;
+    Ok(lit)
+}
+
+fn lit_number<N>(lit: &Lit) -> darling::Result<N>
+where
+    N: std::str::FromStr,
+    N::Err: std::fmt::Display,
+{
+    let value = match lit {
+        Lit::Int(lit) => lit.base10_parse::<N>(),
+        Lit::Float(lit) => lit.base10_parse::<N>(),
+        Lit::Str(lit) => lit.value().parse::<N>().map_err(|e| syn::Error::new_spanned(lit, e)),
+        _ => return Err(darling::Error::unexpected_lit_type(lit)),
+    };
+    value.map_err(darling::Error::from)
+}
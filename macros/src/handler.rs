@@ -118,14 +118,23 @@ fn handle_fn(salvo: &Ident, sig: &Signature) -> syn::Result<TokenStream> {
                         ));
                     }
 
+                    if lcount == 0 {
+                        extract_ts.push(quote! {
+                            let #id: #ty = match <#ty as #salvo::extract::FromRequest>::from_request(req, depot).await {
+                                Ok(data) => data,
+                                Err(e) => {
+                                    #salvo::Writer::write(e, req, depot, res).await;
+                                    return;
+                                }
+                            };
+                        });
+                        continue;
+                    }
                     extract_ts.push(quote! {
                         let #id: #ty = match req.extract().await {
                             Ok(data) => data,
                             Err(e) => {
-                                #salvo::__private::tracing::error!(error = ?e, "failed to extract data");
-                                res.set_status_error(#salvo::http::errors::StatusError::bad_request().with_detail(
-                                    "Extract data failed."
-                                ));
+                                #salvo::Writer::write(#salvo::extract::ExtractRejection(e), req, depot, res).await;
                                 return;
                             }
                         };
@@ -165,6 +174,7 @@ fn handle_fn(salvo: &Ident, sig: &Signature) -> syn::Result<TokenStream> {
                 Ok(quote! {
                     #[inline]
                     async fn handle(&self, req: &mut #salvo::Request, depot: &mut #salvo::Depot, res: &mut #salvo::Response, ctrl: &mut #salvo::routing::FlowCtrl) {
+                        #(#extract_ts)*
                         #salvo::Writer::write(Self::#name(#(#call_args),*), req, depot, res).await;
                     }
                 })
@@ -11,6 +11,7 @@ use syn::{parse_macro_input, AttributeArgs, DeriveInput, Item};
 
 mod extract;
 mod handler;
+mod route;
 mod shared;
 
 /// `handler` is a pro macro to help create `Handler` from function or impl block easily.
@@ -55,8 +56,57 @@ pub fn fn_handler(args: TokenStream, input: TokenStream) -> TokenStream {
     handler(args, input)
 }
 
+macro_rules! route_attributes {
+    ($($(#[$meta:meta])* $name:ident => $method:literal;)+) => {
+        $(
+            $(#[$meta])*
+            #[proc_macro_attribute]
+            pub fn $name(args: TokenStream, input: TokenStream) -> TokenStream {
+                let args = parse_macro_input!(args as AttributeArgs);
+                let item = parse_macro_input!(input as Item);
+                match route::generate($method, args, item) {
+                    Ok(stream) => stream.into(),
+                    Err(e) => e.to_compile_error().into(),
+                }
+            }
+        )+
+    };
+}
+
+route_attributes! {
+    /// `get` is a pro macro like [`handler`](macro@handler) which also declares the route of the handler, for get method.
+    ///
+    /// The handler struct gets a `ROUTE` constant, so routers can be built with `Router::with_routes`. With the
+    /// `route-registry` feature, the route is also registered, so routers can be built with
+    /// `Router::with_module_routes` or `Router::with_registered_routes`.
+    ///
+    /// ```ignore
+    /// #[get("users/<id>")]
+    /// async fn show_user(req: &mut Request) -> String {
+    ///     req.param::<String>("id").unwrap()
+    /// }
+    ///
+    /// let router = Router::with_module_routes(module_path!());
+    /// ```
+    get => "GET";
+    /// `post` is a pro macro like [`get`](macro@get), for post method.
+    post => "POST";
+    /// `put` is a pro macro like [`get`](macro@get), for put method.
+    put => "PUT";
+    /// `delete` is a pro macro like [`get`](macro@get), for delete method.
+    delete => "DELETE";
+    /// `patch` is a pro macro like [`get`](macro@get), for patch method.
+    patch => "PATCH";
+    /// `head` is a pro macro like [`get`](macro@get), for head method.
+    head => "HEAD";
+    /// `options` is a pro macro like [`get`](macro@get), for options method.
+    options => "OPTIONS";
+}
+
 /// Generate code for extractible type.
-#[proc_macro_derive(Extractible, attributes(extract))]
+///
+/// Fields can be validated with `#[validate(...)]` attributes, see `salvo_core::extract::validation`.
+#[proc_macro_derive(Extractible, attributes(extract, validate))]
 pub fn derive_extractible(input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(input as DeriveInput);
     match extract::generate(args) {
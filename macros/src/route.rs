@@ -0,0 +1,59 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{AttributeArgs, Item, Lit, NestedMeta};
+
+use crate::handler;
+use crate::shared::*;
+
+pub(crate) fn generate(method: &str, args: AttributeArgs, input: Item) -> syn::Result<TokenStream> {
+    let internal = is_internal(args.iter());
+    let mut path = None;
+    for arg in &args {
+        if let NestedMeta::Lit(Lit::Str(lit)) = arg {
+            if path.is_some() {
+                return Err(syn::Error::new_spanned(lit, "only one path is allowed"));
+            }
+            path = Some(lit.value());
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "missing path, use it like `#[{}(\"/users/<id>\")]`",
+                    method.to_lowercase()
+                ),
+            ))
+        }
+    };
+    let item_fn = match &input {
+        Item::Fn(item_fn) => item_fn.clone(),
+        _ => return Err(syn::Error::new_spanned(input, "route attributes must be added to `fn`")),
+    };
+
+    let salvo = salvo_crate(internal);
+    let vis = &item_fn.vis;
+    let name = &item_fn.sig.ident;
+    let method = syn::Ident::new(method, proc_macro2::Span::call_site());
+    let handler = handler::generate(internal, input)?;
+    let route = quote! {
+        #salvo::routing::Route::new(
+            #salvo::http::Method::#method,
+            #path,
+            ::std::module_path!(),
+            || -> ::std::sync::Arc<dyn #salvo::Handler> { ::std::sync::Arc::new(#name) },
+        )
+    };
+    Ok(quote! {
+        #handler
+        impl #name {
+            /// The route of this handler.
+            #vis const ROUTE: #salvo::routing::Route = #route;
+        }
+        #salvo::__private::register_route! {
+            #route
+        }
+    })
+}
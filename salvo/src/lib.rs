@@ -19,11 +19,17 @@ cfg_feature! {
         feature = "extra",
         feature = "authorization",
         feature = "compression",
+        feature = "concurrency-limiter",
         feature = "cors",
         feature = "csrf",
+        feature = "force-https",
+        feature = "http-client",
+        feature = "http-client-rustls",
+        feature = "ip-filter",
         feature = "jwt-auth",
         feature = "logging",
         feature = "proxy",
+        feature = "security-headers",
         feature = "serve-static",
         feature = "session",
         feature = "size-limiter",